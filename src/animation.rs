@@ -0,0 +1,342 @@
+use std::{collections::HashMap, hash::Hash};
+
+use smitten::Vec2;
+
+use crate::atlas::{Atlas, Sprite};
+
+/// Frames shorter than this are bumped up to it so a zero duration can't
+/// spin forever.
+const MIN_FRAME: f32 = 0.001;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Frame {
+	pub sprite: Sprite,
+	/// Seconds
+	pub duration: f32,
+	/// Reported by `Animator::update` when this frame starts
+	pub event: Option<&'static str>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Playback {
+	Loop,
+	/// Forward to the end and then backward to the start, forever
+	PingPong,
+	/// Stop on the last frame
+	Once,
+}
+
+#[derive(Clone, Debug)]
+pub struct Clip {
+	frames: Vec<Frame>,
+	pub playback: Playback,
+}
+
+impl Clip {
+	/// There has to be at least one frame
+	pub fn new(frames: Vec<Frame>, playback: Playback) -> Self {
+		assert!(!frames.is_empty(), "a clip needs a frame");
+
+		Self { frames, playback }
+	}
+
+	/// Build a clip out of atlas regions named `prefix_0`, `prefix_1`, and so on
+	/// until one is missing. Every frame is the same length.
+	pub fn from_atlas(
+		atlas: &Atlas,
+		prefix: &str,
+		duration: f32,
+		playback: Playback,
+	) -> Option<Self> {
+		let frames: Vec<Frame> = (0..)
+			.map_while(|n| atlas.id(&format!("{}_{}", prefix, n)))
+			.map(|id| Frame {
+				sprite: id.into(),
+				duration,
+				event: None,
+			})
+			.collect();
+
+		if frames.is_empty() {
+			None
+		} else {
+			Some(Self::new(frames, playback))
+		}
+	}
+
+	/// Fire `event` whenever frame `frame` starts. Does nothing if there's no
+	/// such frame.
+	pub fn with_event(mut self, frame: usize, event: &'static str) -> Self {
+		if let Some(f) = self.frames.get_mut(frame) {
+			f.event = Some(event);
+		}
+		self
+	}
+}
+
+/// Where we are in a clip
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Playhead {
+	pub frame: usize,
+	/// Seconds into `frame`
+	pub elapsed: f32,
+	/// Whether a `Playback::PingPong` clip is on its way out rather than back
+	pub forward: bool,
+	/// Whether the first frame's event has gone off
+	pub started: bool,
+	pub finished: bool,
+}
+
+impl Playhead {
+	const START: Playhead = Playhead {
+		frame: 0,
+		elapsed: 0.0,
+		forward: true,
+		started: false,
+		finished: false,
+	};
+
+	// Move to the next frame. Returns false if there isn't one.
+	fn advance(&mut self, clip: &Clip) -> bool {
+		let last = clip.frames.len() - 1;
+
+		match clip.playback {
+			Playback::Loop => {
+				self.frame = if self.frame == last {
+					0
+				} else {
+					self.frame + 1
+				}
+			}
+			Playback::Once if self.frame == last => {
+				self.finished = true;
+				return false;
+			}
+			Playback::Once => self.frame += 1,
+			Playback::PingPong if last == 0 => return false,
+			Playback::PingPong => {
+				if self.forward && self.frame == last {
+					self.forward = false;
+				} else if !self.forward && self.frame == 0 {
+					self.forward = true;
+				}
+
+				if self.forward {
+					self.frame += 1;
+				} else {
+					self.frame -= 1;
+				}
+			}
+		}
+
+		true
+	}
+}
+
+/// Plays one clip per state and switches between them. The state is usually
+/// an enum like `Motion`.
+pub struct Animator<S> {
+	clips: HashMap<S, Clip>,
+	state: S,
+	playhead: Playhead,
+}
+
+impl<S: Copy + Eq + Hash> Animator<S> {
+	pub fn new(state: S, clip: Clip) -> Self {
+		let mut clips = HashMap::new();
+		clips.insert(state, clip);
+
+		Self {
+			clips,
+			state,
+			playhead: Playhead::START,
+		}
+	}
+
+	pub fn add(&mut self, state: S, clip: Clip) {
+		self.clips.insert(state, clip);
+	}
+
+	/// Change every frame's sprite with `f`
+	pub fn map<F: Fn(Sprite) -> Sprite>(&mut self, f: F) {
+		for frame in self.clips.values_mut().flat_map(|c| c.frames.iter_mut()) {
+			frame.sprite = f(frame.sprite);
+		}
+	}
+
+	pub fn state(&self) -> S {
+		self.state
+	}
+
+	/// Switch clips. Staying in the same state keeps playing where we were,
+	/// and states without a clip are ignored.
+	pub fn set_state(&mut self, state: S) {
+		if state != self.state && self.clips.contains_key(&state) {
+			self.state = state;
+			self.playhead = Playhead::START;
+		}
+	}
+
+	/// Whether a `Playback::Once` clip has reached its end
+	pub fn finished(&self) -> bool {
+		self.playhead.finished
+	}
+
+	pub fn playhead(&self) -> Playhead {
+		self.playhead
+	}
+
+	/// Pick up where `playhead` left off in `state`. States without a clip
+	/// are ignored, and frames past the end of the clip go to its last.
+	pub fn set_playhead(&mut self, state: S, playhead: Playhead) {
+		if let Some(clip) = self.clips.get(&state) {
+			self.state = state;
+			self.playhead = Playhead {
+				frame: playhead.frame.min(clip.frames.len() - 1),
+				..playhead
+			};
+		}
+	}
+
+	fn clip(&self) -> &Clip {
+		&self.clips[&self.state]
+	}
+
+	/// Move time forward. Returns the events of every frame that started.
+	pub fn update(&mut self, dt: f32) -> Vec<&'static str> {
+		let clip = &self.clips[&self.state];
+		let mut events = vec![];
+
+		// The first frame's event fires as soon as the clip starts
+		if !self.playhead.started {
+			self.playhead.started = true;
+			events.extend(clip.frames[0].event);
+		}
+
+		self.playhead.elapsed += dt;
+		loop {
+			let duration = clip.frames[self.playhead.frame].duration.max(MIN_FRAME);
+			if self.playhead.elapsed < duration {
+				break;
+			}
+
+			if !self.playhead.advance(clip) {
+				// Stopped on a frame, don't let time pile up
+				self.playhead.elapsed = duration;
+				break;
+			}
+
+			self.playhead.elapsed -= duration;
+			events.extend(clip.frames[self.playhead.frame].event);
+		}
+
+		events
+	}
+
+	pub fn sprite(&self) -> Sprite {
+		self.clip().frames[self.playhead.frame].sprite
+	}
+}
+
+/// The usual states for something that walks around. It's top down, so
+/// every direction is walking.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Motion {
+	Idle,
+	Walk,
+	/// Putting a tile down. It's played through once, whatever the velocity.
+	Place,
+}
+
+impl Motion {
+	/// Slower than this, in units per second, counts as standing still
+	const THRESHOLD: f32 = 0.1;
+
+	pub fn from_velocity(velocity: Vec2) -> Self {
+		if velocity.x.abs() > Self::THRESHOLD || velocity.y.abs() > Self::THRESHOLD {
+			Motion::Walk
+		} else {
+			Motion::Idle
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::{Color, Vec2};
+
+	use super::{Animator, Clip, Frame, Motion, Playback};
+
+	fn clip(length: usize, playback: Playback) -> Clip {
+		let frames = (0..length)
+			.map(|_| Frame {
+				sprite: Color::rgb(0.0, 0.0, 0.0).into(),
+				duration: 1.0,
+				event: None,
+			})
+			.collect();
+
+		Clip::new(frames, playback)
+	}
+
+	fn frames(playback: Playback, steps: usize) -> Vec<usize> {
+		let mut animator = Animator::new((), clip(3, playback));
+
+		(0..steps)
+			.map(|_| {
+				let frame = animator.playhead.frame;
+				animator.update(1.0);
+				frame
+			})
+			.collect()
+	}
+
+	#[test]
+	fn playback_modes() {
+		assert_eq!(frames(Playback::Loop, 7), vec![0, 1, 2, 0, 1, 2, 0]);
+		assert_eq!(frames(Playback::PingPong, 7), vec![0, 1, 2, 1, 0, 1, 2]);
+		assert_eq!(frames(Playback::Once, 5), vec![0, 1, 2, 2, 2]);
+	}
+
+	#[test]
+	fn events_fire_on_frames() {
+		let clip = clip(2, Playback::Loop)
+			.with_event(0, "step")
+			.with_event(1, "other");
+		let mut animator = Animator::new((), clip);
+
+		assert_eq!(animator.update(0.5), vec!["step"]);
+		assert_eq!(animator.update(0.5), vec!["other"]);
+		// Skipping over a whole loop reports everything we passed
+		assert_eq!(animator.update(2.0), vec!["step", "other"]);
+	}
+
+	#[test]
+	fn state_change_restarts() {
+		let mut animator = Animator::new(0, clip(3, Playback::Loop));
+		animator.add(1, clip(2, Playback::Loop));
+
+		animator.update(1.5);
+		assert_eq!(animator.playhead.frame, 1);
+
+		animator.set_state(1);
+		assert_eq!(animator.playhead.frame, 0);
+
+		// No clip for this state, so nothing changes
+		animator.set_state(5);
+		assert_eq!(animator.state(), 1);
+	}
+
+	#[test]
+	fn walking_up_is_walking() {
+		assert_eq!(Motion::from_velocity(Vec2::new(0.0, 2.0)), Motion::Walk);
+		assert_eq!(Motion::from_velocity(Vec2::new(-2.0, 0.0)), Motion::Walk);
+		assert_eq!(Motion::from_velocity(Vec2::new(0.05, 0.0)), Motion::Idle);
+	}
+
+	#[test]
+	#[should_panic]
+	fn clips_need_frames() {
+		Clip::new(vec![], Playback::Loop);
+	}
+}
@@ -0,0 +1,437 @@
+use std::{
+	collections::HashMap,
+	fmt, fs, io,
+	path::{Path, PathBuf},
+};
+
+use image::{GenericImage, ImageError, RgbaImage};
+use smitten::{Draw, TextureId, Vec2};
+
+/// Pixels left empty around every region so sampling doesn't bleed into the
+/// neighbouring sprite.
+const PADDING: u32 = 1;
+
+/// A rectangle of the sheet, in pixels from the top left.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Region {
+	pub x: u32,
+	pub y: u32,
+	pub width: u32,
+	pub height: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RegionId(usize);
+
+/// What a thing looks like. Either something smitten can draw on its own or
+/// a region of the atlas.
+#[derive(Copy, Clone, Debug)]
+pub enum Sprite {
+	Draw(Draw),
+	Region(RegionId),
+}
+
+impl<D: Into<Draw>> From<D> for Sprite {
+	fn from(d: D) -> Self {
+		Sprite::Draw(d.into())
+	}
+}
+
+impl Sprite {
+	/// This, but drawn with `new` if it was drawn with `old`
+	pub fn replace_texture(self, old: TextureId, new: TextureId) -> Self {
+		match self {
+			Sprite::Draw(Draw::Texture(t)) if t == old => new.into(),
+			sprite => sprite,
+		}
+	}
+}
+
+impl RegionId {
+	#[cfg(test)]
+	pub fn from_index(index: usize) -> Self {
+		RegionId(index)
+	}
+}
+
+impl From<RegionId> for Sprite {
+	fn from(id: RegionId) -> Self {
+		Sprite::Region(id)
+	}
+}
+
+/// Many sprites packed into one image. The layout lives in a manifest next
+/// to the image, a small subset of TOML:
+///
+/// ```toml
+/// image = "sheet.png"
+/// size = [64, 32]
+///
+/// [regions]
+/// puare = [0, 0, 32, 32]
+/// player = [33, 0, 16, 16]
+/// ```
+///
+/// The image path is relative to the manifest.
+pub struct Atlas {
+	pub image: PathBuf,
+	pub size: (u32, u32),
+
+	ids: HashMap<String, RegionId>,
+	/// Indexed by `RegionId`, like `regions`
+	names: Vec<String>,
+	regions: Vec<Region>,
+}
+
+impl Atlas {
+	pub fn load<P: AsRef<Path>>(manifest: P) -> Result<Self, AtlasError> {
+		let manifest = manifest.as_ref();
+		let mut atlas = Self::parse(&fs::read_to_string(manifest)?)?;
+
+		if let Some(dir) = manifest.parent() {
+			atlas.image = dir.join(atlas.image);
+		}
+
+		Ok(atlas)
+	}
+
+	pub fn parse(string: &str) -> Result<Self, AtlasError> {
+		let mut image = None;
+		let mut size = None;
+		let mut atlas = Atlas {
+			image: PathBuf::new(),
+			size: (0, 0),
+			ids: HashMap::new(),
+			names: vec![],
+			regions: vec![],
+		};
+
+		let mut section = String::new();
+		for (idx, line) in string.lines().enumerate() {
+			let line_number = idx + 1;
+			let line = line.split('#').next().unwrap_or_default().trim();
+
+			if line.is_empty() {
+				continue;
+			} else if line.starts_with('[') && line.ends_with(']') {
+				section = line[1..line.len() - 1].trim().to_owned();
+				continue;
+			}
+
+			let (key, value) = line
+				.split_once('=')
+				.map(|(k, v)| (k.trim(), v.trim()))
+				.ok_or(AtlasError::Syntax(line_number))?;
+
+			match (section.as_str(), key) {
+				("", "image") => image = Some(parse_string(value, line_number)?),
+				("", "size") => {
+					let [w, h] = parse_array(value, line_number)?;
+					size = Some((w, h));
+				}
+				("regions", name) => {
+					let [x, y, width, height] = parse_array(value, line_number)?;
+					atlas.insert(
+						name.trim_matches('"'),
+						Region {
+							x,
+							y,
+							width,
+							height,
+						},
+					)?;
+				}
+				_ => return Err(AtlasError::UnknownKey(line_number)),
+			}
+		}
+
+		atlas.image = PathBuf::from(image.ok_or(AtlasError::Missing("image"))?);
+		atlas.size = size.ok_or(AtlasError::Missing("size"))?;
+
+		Ok(atlas)
+	}
+
+	pub fn manifest(&self) -> String {
+		let mut string = format!(
+			"image = \"{}\"\nsize = [{}, {}]\n\n[regions]\n",
+			self.image.display(),
+			self.size.0,
+			self.size.1
+		);
+
+		for (name, r) in self.names.iter().zip(&self.regions) {
+			string.push_str(&format!(
+				"{} = [{}, {}, {}, {}]\n",
+				name, r.x, r.y, r.width, r.height
+			));
+		}
+
+		string
+	}
+
+	/// Pack a bunch of images into one sheet, save it and its manifest, and
+	/// return the atlas. Regions are named after the file stems, so those
+	/// can't repeat. Rows are
+	/// `max_width` pixels wide at most and the sheet grows down as needed.
+	pub fn pack<P: AsRef<Path>>(
+		images: &[P],
+		manifest: &Path,
+		max_width: u32,
+	) -> Result<Self, AtlasError> {
+		let mut loaded = vec![];
+		for path in images {
+			let path = path.as_ref();
+			let name = path
+				.file_stem()
+				.map(|s| s.to_string_lossy().into_owned())
+				.ok_or_else(|| AtlasError::Missing("image name"))?;
+
+			loaded.push((name, image::open(path)?.into_rgba8()));
+		}
+
+		let sizes: Vec<(u32, u32)> = loaded.iter().map(|(_, i)| i.dimensions()).collect();
+		let (placements, size) = shelf_pack(&sizes, max_width)?;
+
+		let mut sheet = RgbaImage::new(size.0, size.1);
+		let image = manifest.with_extension("png");
+		let mut atlas = Atlas {
+			image: PathBuf::from(image.file_name().unwrap_or_default()),
+			size,
+			ids: HashMap::new(),
+			names: vec![],
+			regions: vec![],
+		};
+
+		for ((name, img), region) in loaded.iter().zip(placements) {
+			sheet.copy_from(img, region.x, region.y)?;
+			atlas.insert(name, region)?;
+		}
+
+		sheet.save(&image)?;
+		fs::write(manifest, atlas.manifest())?;
+
+		atlas.image = image;
+		Ok(atlas)
+	}
+
+	/// Names have to be unique, the second one would leave the first region
+	/// with no way to get at it
+	fn insert(&mut self, name: &str, region: Region) -> Result<(), AtlasError> {
+		if self.ids.contains_key(name) {
+			return Err(AtlasError::Duplicate(name.to_owned()));
+		}
+
+		let id = RegionId(self.regions.len());
+		self.regions.push(region);
+		self.names.push(name.to_owned());
+		self.ids.insert(name.to_owned(), id);
+		Ok(())
+	}
+
+	pub fn id(&self, name: &str) -> Option<RegionId> {
+		self.ids.get(name).copied()
+	}
+
+	/// What the region was called in the manifest
+	pub fn name(&self, id: RegionId) -> Option<&str> {
+		self.names.get(id.0).map(String::as_str)
+	}
+
+	/// None if the id is from some other atlas with more regions
+	pub fn region(&self, id: RegionId) -> Option<Region> {
+		self.regions.get(id.0).copied()
+	}
+
+	/// Texture coordinates of the region's bottom left and top right corners,
+	/// from 0 to 1 with y going up like the rest of the world.
+	pub fn uv(&self, id: RegionId) -> Option<(Vec2, Vec2)> {
+		let r = self.region(id)?;
+		let (w, h) = (self.size.0 as f32, self.size.1 as f32);
+
+		Some((
+			Vec2::new(r.x as f32 / w, 1.0 - (r.y + r.height) as f32 / h),
+			Vec2::new((r.x + r.width) as f32 / w, 1.0 - r.y as f32 / h),
+		))
+	}
+}
+
+/// Place rectangles in rows, tallest first. Returns where each rectangle went,
+/// in the order they were given, and the size of the sheet they fit in.
+pub fn shelf_pack(
+	sizes: &[(u32, u32)],
+	max_width: u32,
+) -> Result<(Vec<Region>, (u32, u32)), AtlasError> {
+	let mut order: Vec<usize> = (0..sizes.len()).collect();
+	order.sort_by_key(|idx| std::cmp::Reverse(sizes[*idx].1));
+
+	let mut placements = vec![None; sizes.len()];
+	let mut x = PADDING;
+	let mut y = PADDING;
+	let mut shelf_height = 0;
+	let mut width = 0;
+
+	for idx in order {
+		let (w, h) = sizes[idx];
+		if w + PADDING * 2 > max_width {
+			return Err(AtlasError::TooWide(w));
+		}
+
+		if x + w + PADDING > max_width {
+			y += shelf_height + PADDING;
+			x = PADDING;
+			shelf_height = 0;
+		}
+
+		placements[idx] = Some(Region {
+			x,
+			y,
+			width: w,
+			height: h,
+		});
+
+		x += w + PADDING;
+		width = width.max(x);
+		shelf_height = shelf_height.max(h);
+	}
+
+	let placements = placements.into_iter().flatten().collect();
+	Ok((placements, (width, y + shelf_height + PADDING)))
+}
+
+fn parse_string(value: &str, line: usize) -> Result<String, AtlasError> {
+	value
+		.strip_prefix('"')
+		.and_then(|v| v.strip_suffix('"'))
+		.map(|v| v.to_owned())
+		.ok_or(AtlasError::Syntax(line))
+}
+
+fn parse_array<const N: usize>(value: &str, line: usize) -> Result<[u32; N], AtlasError> {
+	let inner = value
+		.strip_prefix('[')
+		.and_then(|v| v.strip_suffix(']'))
+		.ok_or(AtlasError::Syntax(line))?;
+
+	let numbers = inner
+		.split(',')
+		.map(|n| n.trim().parse())
+		.collect::<Result<Vec<u32>, _>>()
+		.map_err(|_| AtlasError::Syntax(line))?;
+
+	numbers.try_into().map_err(|_| AtlasError::Syntax(line))
+}
+
+#[derive(Debug)]
+pub enum AtlasError {
+	Io(io::Error),
+	Image(ImageError),
+	Syntax(usize),
+	UnknownKey(usize),
+	Missing(&'static str),
+	TooWide(u32),
+	/// Two regions with the same name, or two packed images with the same
+	/// file stem
+	Duplicate(String),
+}
+
+impl From<io::Error> for AtlasError {
+	fn from(e: io::Error) -> Self {
+		AtlasError::Io(e)
+	}
+}
+
+impl From<ImageError> for AtlasError {
+	fn from(e: ImageError) -> Self {
+		AtlasError::Image(e)
+	}
+}
+
+impl fmt::Display for AtlasError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			AtlasError::Io(e) => write!(f, "couldn't read atlas: {}", e),
+			AtlasError::Image(e) => write!(f, "couldn't load image: {}", e),
+			AtlasError::Syntax(line) => write!(f, "line {}: expected key = value", line),
+			AtlasError::UnknownKey(line) => write!(f, "line {}: unknown key", line),
+			AtlasError::Missing(what) => write!(f, "missing {}", what),
+			AtlasError::TooWide(w) => write!(f, "an image {}px wide doesn't fit in the sheet", w),
+			AtlasError::Duplicate(name) => {
+				write!(f, "there's more than one region called {}", name)
+			}
+		}
+	}
+}
+
+impl std::error::Error for AtlasError {}
+
+#[cfg(test)]
+mod test {
+	use std::fs;
+
+	use image::RgbaImage;
+
+	use super::{shelf_pack, Atlas, AtlasError, Region};
+
+	#[test]
+	fn manifest_round_trip() {
+		let string = "image = \"sheet.png\"\nsize = [64, 32]\n\n[regions]\npuare = [0, 0, 32, 32]\nplayer = [33, 0, 16, 16]\n";
+		let atlas = Atlas::parse(string).unwrap();
+
+		let player = atlas.id("player").unwrap();
+		assert_eq!(
+			atlas.region(player),
+			Some(Region {
+				x: 33,
+				y: 0,
+				width: 16,
+				height: 16
+			})
+		);
+		assert_eq!(atlas.manifest(), string);
+		assert_eq!(atlas.name(player), Some("player"));
+
+		let twice = string.to_owned() + "player = [50, 0, 8, 8]\n";
+		assert!(matches!(
+			Atlas::parse(&twice),
+			Err(AtlasError::Duplicate(name)) if name == "player"
+		));
+	}
+
+	#[test]
+	fn packed_names_dont_repeat() {
+		let dir = std::env::temp_dir().join(format!("notsure-pack-{}", std::process::id()));
+		let (a, b) = (dir.join("a"), dir.join("b"));
+		fs::create_dir_all(&a).unwrap();
+		fs::create_dir_all(&b).unwrap();
+
+		let image = RgbaImage::new(2, 2);
+		image.save(a.join("player.png")).unwrap();
+		image.save(b.join("player.png")).unwrap();
+
+		let manifest = dir.join("atlas.toml");
+		let packed = Atlas::pack(&[a.join("player.png"), b.join("player.png")], &manifest, 64);
+		assert!(matches!(packed, Err(AtlasError::Duplicate(name)) if name == "player"));
+		assert!(!manifest.exists());
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn packed_regions_dont_overlap() {
+		let sizes = [(10, 10), (20, 5), (8, 12), (30, 3), (4, 4)];
+		let (regions, size) = shelf_pack(&sizes, 40).unwrap();
+
+		for (i, a) in regions.iter().enumerate() {
+			assert_eq!((a.width, a.height), sizes[i]);
+			assert!(a.x + a.width <= size.0 && a.y + a.height <= size.1);
+
+			for b in &regions[i + 1..] {
+				let apart = a.x + a.width <= b.x
+					|| b.x + b.width <= a.x
+					|| a.y + a.height <= b.y
+					|| b.y + b.height <= a.y;
+				assert!(apart, "{:?} overlaps {:?}", a, b);
+			}
+		}
+	}
+}
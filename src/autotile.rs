@@ -0,0 +1,216 @@
+// Picking a sprite for a tile based on which of its neighbours are also
+// solid. The grid keeps an 8-bit mask of neighbours for every cell and this
+// turns that mask into an index into a set of sprite variants.
+
+// Bits of a neighbour mask, going clockwise from the top.
+pub const NORTH: u8 = 1;
+pub const NORTH_EAST: u8 = 2;
+pub const EAST: u8 = 4;
+pub const SOUTH_EAST: u8 = 8;
+pub const SOUTH: u8 = 16;
+pub const SOUTH_WEST: u8 = 32;
+pub const WEST: u8 = 64;
+pub const NORTH_WEST: u8 = 128;
+
+/// Cell offsets for each bit of a neighbour mask, in bit order.
+pub const OFFSETS: [(i32, i32, u8); 8] = [
+	(0, 1, NORTH),
+	(1, 1, NORTH_EAST),
+	(1, 0, EAST),
+	(1, -1, SOUTH_EAST),
+	(0, -1, SOUTH),
+	(-1, -1, SOUTH_WEST),
+	(-1, 0, WEST),
+	(-1, 1, NORTH_WEST),
+];
+
+/// How many variants each mode needs
+pub const FOUR_BIT_VARIANTS: usize = 16;
+pub const BLOB_VARIANTS: usize = 47;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+	/// Only looks at the four edges. 16 variants with the usual
+	/// north = 1, east = 2, south = 4, west = 8 numbering.
+	FourBit,
+	/// Looks at edges and corners, 47 variants. A corner only matters when
+	/// both edges next to it are solid, which is where the 47 comes from.
+	Blob,
+}
+
+impl Mode {
+	/// How many sprites a full set has
+	pub fn variants(&self) -> usize {
+		match self {
+			Mode::FourBit => FOUR_BIT_VARIANTS,
+			Mode::Blob => BLOB_VARIANTS,
+		}
+	}
+
+	pub fn variant(&self, neighbours: u8) -> usize {
+		match self {
+			Mode::FourBit => four_bit(neighbours),
+			Mode::Blob => blob(neighbours),
+		}
+	}
+}
+
+pub fn four_bit(neighbours: u8) -> usize {
+	let mut index = 0;
+
+	for (bit, value) in [(NORTH, 1), (EAST, 2), (SOUTH, 4), (WEST, 8)] {
+		if neighbours & bit > 0 {
+			index |= value;
+		}
+	}
+
+	index
+}
+
+/// Drop any corners that don't have both of their edges set.
+pub const fn reduce_corners(neighbours: u8) -> u8 {
+	let corners = [
+		(NORTH_EAST, NORTH, EAST),
+		(SOUTH_EAST, SOUTH, EAST),
+		(SOUTH_WEST, SOUTH, WEST),
+		(NORTH_WEST, NORTH, WEST),
+	];
+
+	let mut mask = neighbours;
+	let mut i = 0;
+	while i < corners.len() {
+		let (corner, a, b) = corners[i];
+		if neighbours & a == 0 || neighbours & b == 0 {
+			mask &= !corner;
+		}
+		i += 1;
+	}
+
+	mask
+}
+
+// Blob variants are numbered by counting the reduced masks in order, so the
+// all-empty mask is 0 and the all-solid mask is 46. Masks that reduce to
+// something else share its number.
+const BLOB_TABLE: [u8; 256] = {
+	let mut table = [0; 256];
+	let mut next = 0;
+
+	let mut mask = 0;
+	while mask < 256 {
+		if reduce_corners(mask as u8) == mask as u8 {
+			table[mask] = next;
+			next += 1;
+		}
+		mask += 1;
+	}
+
+	let mut mask = 0;
+	while mask < 256 {
+		table[mask] = table[reduce_corners(mask as u8) as usize];
+		mask += 1;
+	}
+
+	table
+};
+
+pub fn blob(neighbours: u8) -> usize {
+	BLOB_TABLE[neighbours as usize] as usize
+}
+
+/// The sprites for each variant of a tile. Missing variants fall back to a
+/// default, so a tileset that only has a few sprites drawn still works.
+pub struct Tileset<T> {
+	pub mode: Mode,
+	variants: Vec<Option<T>>,
+	fallback: T,
+}
+
+impl<T: Copy> Tileset<T> {
+	pub fn new(mode: Mode, fallback: T) -> Self {
+		Self {
+			mode,
+			variants: vec![None; mode.variants()],
+			fallback,
+		}
+	}
+
+	pub fn set(&mut self, variant: usize, sprite: T) {
+		if let Some(slot) = self.variants.get_mut(variant) {
+			*slot = Some(sprite);
+		}
+	}
+
+	/// Swap every sprite, the fallback too, for whatever `f` gives back
+	pub fn map<F: Fn(T) -> T>(&mut self, f: F) {
+		self.fallback = f(self.fallback);
+
+		for sprite in self.variants.iter_mut().flatten() {
+			*sprite = f(*sprite);
+		}
+	}
+
+	pub fn get(&self, neighbours: u8) -> T {
+		self.variants[self.mode.variant(neighbours)].unwrap_or(self.fallback)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::grid::{Grid, Size, Tile};
+
+	#[test]
+	fn four_bit_ignores_corners() {
+		assert_eq!(four_bit(NORTH | NORTH_EAST | WEST), 1 | 8);
+		assert_eq!(four_bit(0xFF), 15);
+	}
+
+	#[test]
+	fn blob_has_47_variants() {
+		let mut variants: Vec<usize> = (0..=255u8).map(blob).collect();
+		variants.sort_unstable();
+		variants.dedup();
+
+		assert_eq!(variants.len(), BLOB_VARIANTS);
+		assert_eq!(blob(0), 0);
+		assert_eq!(blob(0xFF), 46);
+
+		// A lone corner is the same as nothing at all
+		assert_eq!(blob(NORTH_EAST), blob(0));
+	}
+
+	#[test]
+	fn grid_updates_neighbours() {
+		let mut grid = Grid::new((3, 3), (0.0, 0.0), 1.0);
+		let center = Size {
+			width: 1,
+			height: 1,
+		};
+
+		grid.set_tile(
+			Size {
+				width: 1,
+				height: 2,
+			},
+			Some(Tile::Solid),
+		);
+		grid.set_tile(
+			Size {
+				width: 0,
+				height: 0,
+			},
+			Some(Tile::Solid),
+		);
+		assert_eq!(grid.neighbours(center), NORTH | SOUTH_WEST);
+
+		grid.set_tile(
+			Size {
+				width: 1,
+				height: 2,
+			},
+			None,
+		);
+		assert_eq!(grid.neighbours(center), SOUTH_WEST);
+	}
+}
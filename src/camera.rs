@@ -0,0 +1,318 @@
+use smitten::{SignedDistance, Vec2};
+
+use crate::grid::Grid;
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+
+/// How far, in units, the view can be thrown by a full strength shake
+const MAX_SHAKE: f32 = 0.4;
+/// Shake trauma lost per second
+const SHAKE_DECAY: f32 = 1.5;
+
+/// How hard the camera's shaking, and how long it's been going for the noise
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Shake {
+	pub trauma: f32,
+	pub time: f32,
+}
+
+/// What part of the world is on screen. Smitten draws with the world origin
+/// in the center of the window, so everything drawn goes through `view` and
+/// `scale` first to move it to where the camera is looking.
+pub struct Camera {
+	pub position: Vec2,
+	pub zoom: f32,
+
+	/// How quickly we catch up to the target. Higher is snappier.
+	pub smoothing: f32,
+	/// Half the size of the box around the center the target can move in
+	/// without the camera following, in units.
+	pub dead_zone: Vec2,
+	/// Keep the view inside this box, bottom left and top right, if there is one
+	pub bounds: Option<(Vec2, Vec2)>,
+
+	dimensions: (u32, u32),
+	pixels_per_unit: u32,
+
+	trauma: f32,
+	time: f32,
+	shake_offset: Vec2,
+}
+
+impl Camera {
+	pub fn new(dimensions: (u32, u32), pixels_per_unit: u32) -> Self {
+		Self {
+			position: Vec2::ZERO,
+			zoom: 1.0,
+
+			smoothing: 6.0,
+			dead_zone: Vec2::new(1.5, 1.0),
+			bounds: None,
+
+			dimensions,
+			pixels_per_unit,
+
+			trauma: 0.0,
+			time: 0.0,
+			shake_offset: Vec2::ZERO,
+		}
+	}
+
+	/// Keep the view within the grid
+	pub fn bound_to(&mut self, grid: &Grid) {
+		self.bounds = Some(grid.bounds());
+	}
+
+	/// Move toward `target`, which is usually the center of a Thing.
+	pub fn follow(&mut self, target: Vec2, dt: f32) {
+		let offset = target - self.position;
+		let mut desired = self.position;
+
+		// Only move enough to put the target back on the edge of the dead zone
+		if offset.x.abs() > self.dead_zone.x {
+			desired.x = target.x - self.dead_zone.x * offset.x.signum();
+		}
+		if offset.y.abs() > self.dead_zone.y {
+			desired.y = target.y - self.dead_zone.y * offset.y.signum();
+		}
+
+		// Framerate independant exponential smoothing
+		let t = 1.0 - (-self.smoothing * dt).exp();
+		self.position += (desired - self.position) * t;
+		self.position = self.clamp(self.position);
+
+		self.update_shake(dt);
+	}
+
+	/// Add some shake. Amounts add up and are capped at 1.0 which shakes as
+	/// hard as the camera goes.
+	pub fn shake(&mut self, amount: f32) {
+		self.trauma = (self.trauma + amount).min(1.0);
+	}
+
+	pub fn shaking(&self) -> Shake {
+		Shake {
+			trauma: self.trauma,
+			time: self.time,
+		}
+	}
+
+	/// Pick up shaking where `shake` left off
+	pub fn set_shaking(&mut self, shake: Shake) {
+		self.trauma = shake.trauma;
+		self.time = shake.time;
+		self.shake_offset = self.shake_offset();
+	}
+
+	pub fn set_zoom(&mut self, zoom: f32) {
+		self.zoom = Self::clamp_zoom(zoom);
+		self.position = self.clamp(self.position);
+	}
+
+	/// The closest zoom to `zoom` the camera will go to
+	pub fn clamp_zoom(zoom: f32) -> f32 {
+		zoom.clamp(MIN_ZOOM, MAX_ZOOM)
+	}
+
+	/// Half the size of the visible area, in units
+	pub fn half_extent(&self) -> Vec2 {
+		let ppu = self.pixels_per_unit as f32 * self.zoom;
+
+		Vec2::new(
+			self.dimensions.0 as f32 / ppu / 2.0,
+			self.dimensions.1 as f32 / ppu / 2.0,
+		)
+	}
+
+	fn clamp(&self, mut position: Vec2) -> Vec2 {
+		let (bl, tr) = match self.bounds {
+			Some(bounds) => bounds,
+			None => return position,
+		};
+		let half = self.half_extent();
+
+		// If the bounds are smaller than the view, just center on them
+		for (p, min, max, half) in [
+			(&mut position.x, bl.x, tr.x, half.x),
+			(&mut position.y, bl.y, tr.y, half.y),
+		] {
+			if max - min < half * 2.0 {
+				*p = (min + max) / 2.0;
+			} else {
+				*p = p.clamp(min + half, max - half);
+			}
+		}
+
+		position
+	}
+
+	fn update_shake(&mut self, dt: f32) {
+		self.time += dt;
+		self.trauma = (self.trauma - SHAKE_DECAY * dt).max(0.0);
+		self.shake_offset = self.shake_offset();
+	}
+
+	fn shake_offset(&self) -> Vec2 {
+		// Squaring makes small shakes subtle. The sines are cheap noise that
+		// doesn't line up between the axes.
+		let strength = MAX_SHAKE * self.trauma * self.trauma;
+		Vec2::new(
+			strength * (self.time * 41.0).sin() * (self.time * 13.0).cos(),
+			strength * (self.time * 37.0 + 1.7).sin() * (self.time * 17.0).cos(),
+		)
+	}
+
+	/// The point at the center of the screen, shake and all
+	fn eye(&self) -> Vec2 {
+		self.position + self.shake_offset
+	}
+
+	/// Where to draw something that's at `p` in the world
+	pub fn view<P: Into<Vec2>>(&self, p: P) -> Vec2 {
+		(p.into() - self.eye()) * self.zoom
+	}
+
+	/// How big to draw something that's `size` in the world
+	pub fn scale<S: Into<Vec2>>(&self, size: S) -> Vec2 {
+		size.into() * self.zoom
+	}
+
+	/// How many pixels long `length` units are on screen, for radii and line
+	/// thicknesses
+	pub fn pixels(&self, length: f32) -> u32 {
+		(length * self.pixels_per_unit as f32 * self.zoom).round() as u32
+	}
+
+	/// Move a shape to the screen. Thicknesses and radii are pixels at a zoom
+	/// of 1, so they grow and shrink with everything else.
+	pub fn sdf(&self, sdf: SignedDistance) -> SignedDistance {
+		let scale = |pixels: u32| ((pixels as f32 * self.zoom).round() as u32).max(1);
+
+		match sdf {
+			SignedDistance::LineSegment {
+				start,
+				end,
+				thickness,
+				color,
+			} => SignedDistance::LineSegment {
+				start: self.view(start),
+				end: self.view(end),
+				thickness: scale(thickness),
+				color,
+			},
+			SignedDistance::Circle {
+				center,
+				radius,
+				color,
+			} => SignedDistance::Circle {
+				center: self.view(center),
+				radius: scale(radius),
+				color,
+			},
+		}
+	}
+
+	/// Turn a position in window pixels, where the origin is the top left, into
+	/// a position in the world.
+	pub fn screen_to_world(&self, p: Vec2) -> Vec2 {
+		let ppu = self.pixels_per_unit as f32 * self.zoom;
+
+		Vec2::new(
+			(p.x - self.dimensions.0 as f32 / 2.0) / ppu,
+			(self.dimensions.1 as f32 / 2.0 - p.y) / ppu,
+		) + self.eye()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::{Color, SignedDistance, Vec2};
+
+	use super::Camera;
+	use crate::grid::Grid;
+
+	fn close(a: Vec2, b: Vec2) -> bool {
+		(a.x - b.x).abs() < 0.0001 && (a.y - b.y).abs() < 0.0001
+	}
+
+	#[test]
+	fn screen_world_round_trip() {
+		let mut camera = Camera::new((720, 480), 36);
+		camera.position = Vec2::new(3.0, -2.0);
+		camera.zoom = 2.0;
+
+		// Center of the screen is where the camera is
+		assert!(close(
+			camera.screen_to_world(Vec2::new(360.0, 240.0)),
+			camera.position
+		));
+
+		// 72 pixels to a unit, and y goes down on screen
+		assert!(close(
+			camera.screen_to_world(Vec2::new(0.0, 0.0)),
+			camera.position + Vec2::new(-5.0, 10.0 / 3.0)
+		));
+		assert!(close(
+			camera.screen_to_world(Vec2::new(432.0, 312.0)),
+			camera.position + Vec2::new(1.0, -1.0)
+		));
+	}
+
+	#[test]
+	fn shapes_zoom_too() {
+		let mut camera = Camera::new((720, 480), 36);
+		let circle = |camera: &Camera| match camera.sdf(SignedDistance::Circle {
+			center: Vec2::ZERO,
+			radius: 4,
+			color: Color::WHITE,
+		}) {
+			SignedDistance::Circle { radius, .. } => radius,
+			_ => unreachable!(),
+		};
+
+		assert_eq!(circle(&camera), 4);
+		camera.zoom = 2.0;
+		assert_eq!(circle(&camera), 8);
+
+		// Never thin enough to disappear
+		camera.zoom = 0.1;
+		assert_eq!(circle(&camera), 1);
+	}
+
+	#[test]
+	fn dead_zone_holds_still() {
+		let mut camera = Camera::new((720, 480), 36);
+		camera.follow(Vec2::new(1.0, 0.5), 1.0);
+		assert!(close(camera.position, Vec2::ZERO));
+
+		// Far outside, we end up with the target on the edge of the zone
+		for _ in 0..100 {
+			camera.follow(Vec2::new(10.0, 0.0), 0.1);
+		}
+		assert!(close(
+			camera.position,
+			Vec2::new(10.0 - camera.dead_zone.x, 0.0)
+		));
+	}
+
+	#[test]
+	fn clamps_to_grid() {
+		let mut camera = Camera::new((720, 480), 36);
+		camera.smoothing = 1000.0;
+		// 40x40 units, much bigger than the 20x13.3 view
+		camera.bound_to(&Grid::new((40, 40), (0.0, 0.0), 1.0));
+
+		camera.follow(Vec2::new(100.0, 100.0), 1.0);
+		let half = camera.half_extent();
+		assert!(close(
+			camera.position,
+			Vec2::new(20.0 - half.x, 20.0 - half.y)
+		));
+
+		// And a small grid sits in the middle
+		camera.bound_to(&Grid::new((4, 4), (1.0, 1.0), 1.0));
+		camera.follow(Vec2::new(100.0, 100.0), 1.0);
+		assert!(close(camera.position, Vec2::new(1.0, 1.0)));
+	}
+}
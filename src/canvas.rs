@@ -0,0 +1,422 @@
+use std::{collections::HashMap, path::Path};
+
+use image::{ImageResult, Rgba, RgbaImage};
+use smitten::{Color, Draw, SignedDistance, TextureId, Vec2};
+
+/// Draws what smitten draws, but into an image on the CPU. It's slow and
+/// only exists so drawing can be tested on machines without a display.
+///
+/// Positions work the same as smitten's: in units, with the origin in the
+/// middle of the image and y going up. Rects are positioned by their center,
+/// line thickness and circle radii are in pixels.
+pub struct Canvas {
+	pub image: RgbaImage,
+	pixels_per_unit: u32,
+	textures: HashMap<TextureId, RgbaImage>,
+}
+
+impl Canvas {
+	pub fn new(dimensions: (u32, u32), pixels_per_unit: u32) -> Self {
+		Self {
+			image: RgbaImage::new(dimensions.0, dimensions.1),
+			pixels_per_unit,
+			textures: HashMap::new(),
+		}
+	}
+
+	/// Use `image` whenever something is drawn with `id`
+	#[cfg(test)]
+	pub fn set_texture(&mut self, id: TextureId, image: RgbaImage) {
+		self.textures.insert(id, image);
+	}
+
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> ImageResult<()> {
+		self.image.save(path)
+	}
+
+	pub fn clear(&mut self) {
+		for pixel in self.image.pixels_mut() {
+			*pixel = Rgba([0, 0, 0, 255]);
+		}
+	}
+
+	pub fn rect<P: Into<Vec2>, S: Into<Vec2>, D: Into<Draw>>(&mut self, pos: P, size: S, draw: D) {
+		match draw.into() {
+			Draw::Color(color) => self.fill_rect(pos.into(), size.into(), |_, _| color),
+			Draw::Texture(id) => {
+				let full = (Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0));
+				self.rect_uv(pos, size, id, full)
+			}
+		}
+	}
+
+	/// Draw part of a texture. `uv` is the bottom left and top right of the
+	/// part, from 0 to 1 with y going up.
+	pub fn rect_uv<P: Into<Vec2>, S: Into<Vec2>>(
+		&mut self,
+		pos: P,
+		size: S,
+		id: TextureId,
+		uv: (Vec2, Vec2),
+	) {
+		let sample = self.sampler(id, uv);
+		self.fill_rect(pos.into(), size.into(), sample);
+	}
+
+	/// A rect turned `rotation` radians counterclockwise around its center
+	pub fn rect_rotated<P: Into<Vec2>, S: Into<Vec2>, D: Into<Draw>>(
+		&mut self,
+		pos: P,
+		size: S,
+		rotation: f32,
+		draw: D,
+	) {
+		let (pos, size) = (pos.into(), size.into());
+
+		match draw.into() {
+			Draw::Color(color) => self.fill_rotated(pos, size, rotation, |_, _| color),
+			Draw::Texture(id) => {
+				let full = (Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0));
+				self.rect_uv_rotated(pos, size, rotation, id, full)
+			}
+		}
+	}
+
+	/// Part of a texture, turned like `rect_rotated`
+	pub fn rect_uv_rotated<P: Into<Vec2>, S: Into<Vec2>>(
+		&mut self,
+		pos: P,
+		size: S,
+		rotation: f32,
+		id: TextureId,
+		uv: (Vec2, Vec2),
+	) {
+		let sample = self.sampler(id, uv);
+		self.fill_rotated(pos.into(), size.into(), rotation, sample);
+	}
+
+	/// Looks up the color `u` and `v` across a rect showing `uv` of a texture
+	fn sampler(&self, id: TextureId, uv: (Vec2, Vec2)) -> impl Fn(f32, f32) -> Color {
+		let texture = self.textures.get(&id).cloned();
+		let (bl, tr) = uv;
+
+		move |u, v| match &texture {
+			Some(texture) => {
+				// u and v go across the rect from the top left
+				let tu = bl.x + (tr.x - bl.x) * u;
+				let tv = tr.y + (bl.y - tr.y) * v;
+
+				let x = (tu * texture.width() as f32) as u32;
+				let y = ((1.0 - tv) * texture.height() as f32) as u32;
+				let Rgba([r, g, b, a]) =
+					*texture.get_pixel(x.min(texture.width() - 1), y.min(texture.height() - 1));
+
+				Color::rgba(
+					r as f32 / 255.0,
+					g as f32 / 255.0,
+					b as f32 / 255.0,
+					a as f32 / 255.0,
+				)
+			}
+			// Textures the canvas doesn't have are bright pink so they stand out
+			None => Color::rgb(1.0, 0.0, 1.0),
+		}
+	}
+
+	pub fn sdf(&mut self, sdf: SignedDistance) {
+		match sdf {
+			SignedDistance::LineSegment {
+				start,
+				end,
+				thickness,
+				color,
+			} => {
+				let (start, end) = (self.to_pixels(start), self.to_pixels(end));
+				let half = thickness as f32 / 2.0;
+
+				let min = Vec2::new(start.x.min(end.x) - half, start.y.min(end.y) - half);
+				let max = Vec2::new(start.x.max(end.x) + half, start.y.max(end.y) + half);
+
+				self.fill_where(min, max, color, |p| segment_distance(p, start, end) <= half);
+			}
+			SignedDistance::Circle {
+				center,
+				radius,
+				color,
+			} => {
+				let center = self.to_pixels(center);
+				let radius = radius as f32;
+				let corner = Vec2::new(radius, radius);
+
+				self.fill_where(center - corner, center + corner, color, |p| {
+					p.distance_with(center) <= radius
+				});
+			}
+		}
+	}
+
+	/// From units with the origin in the middle to pixels with the origin in
+	/// the top left
+	fn to_pixels(&self, p: Vec2) -> Vec2 {
+		let ppu = self.pixels_per_unit as f32;
+
+		Vec2::new(
+			p.x * ppu + self.image.width() as f32 / 2.0,
+			self.image.height() as f32 / 2.0 - p.y * ppu,
+		)
+	}
+
+	/// Fill the pixels whose centers are inside the rect, getting each one's
+	/// color from how far across the rect it is.
+	fn fill_rect<F: Fn(f32, f32) -> Color>(&mut self, pos: Vec2, size: Vec2, color: F) {
+		let ppu = self.pixels_per_unit as f32;
+		let center = self.to_pixels(pos);
+		let half = Vec2::new(size.x * ppu / 2.0, size.y * ppu / 2.0);
+		let (min, max) = (center - half, center + half);
+
+		for (x, y) in self.pixels_between(min, max) {
+			let u = (x as f32 + 0.5 - min.x) / (max.x - min.x);
+			let v = (y as f32 + 0.5 - min.y) / (max.y - min.y);
+
+			if (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v) {
+				self.blend(x, y, color(u, v));
+			}
+		}
+	}
+
+	/// `fill_rect`, but the rect is turned around its center
+	fn fill_rotated<F: Fn(f32, f32) -> Color>(
+		&mut self,
+		pos: Vec2,
+		size: Vec2,
+		rotation: f32,
+		color: F,
+	) {
+		let ppu = self.pixels_per_unit as f32;
+		let center = self.to_pixels(pos);
+		let half = Vec2::new(size.x * ppu / 2.0, size.y * ppu / 2.0);
+		let reach = (half.x * half.x + half.y * half.y).sqrt();
+		let corner = Vec2::new(reach, reach);
+
+		// Turning the pixel back the other way puts it in the rect's own space.
+		// Pixels have y going down, so that's flipped on the way in and out.
+		let (sin, cos) = (-rotation).sin_cos();
+
+		for (x, y) in self.pixels_between(center - corner, center + corner) {
+			let dx = x as f32 + 0.5 - center.x;
+			let dy = center.y - (y as f32 + 0.5);
+
+			let local_x = dx * cos - dy * sin;
+			let local_y = -(dx * sin + dy * cos);
+
+			let u = (local_x + half.x) / (half.x * 2.0);
+			let v = (local_y + half.y) / (half.y * 2.0);
+
+			if (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v) {
+				self.blend(x, y, color(u, v));
+			}
+		}
+	}
+
+	/// Fill the pixels between `min` and `max` whose centers pass `inside`
+	fn fill_where<F: Fn(Vec2) -> bool>(&mut self, min: Vec2, max: Vec2, color: Color, inside: F) {
+		for (x, y) in self.pixels_between(min, max) {
+			if inside(Vec2::new(x as f32 + 0.5, y as f32 + 0.5)) {
+				self.blend(x, y, color);
+			}
+		}
+	}
+
+	/// Every pixel in the image that's at least partly between the two corners
+	fn pixels_between(&self, min: Vec2, max: Vec2) -> Vec<(u32, u32)> {
+		let clamp = |v: f32, limit: u32| (v.max(0.0) as u32).min(limit);
+		let (width, height) = self.image.dimensions();
+
+		let (x0, x1) = (clamp(min.x.floor(), width), clamp(max.x.ceil(), width));
+		let (y0, y1) = (clamp(min.y.floor(), height), clamp(max.y.ceil(), height));
+
+		(y0..y1)
+			.flat_map(|y| (x0..x1).map(move |x| (x, y)))
+			.collect()
+	}
+
+	/// Alpha blend `color` over the pixel
+	fn blend(&mut self, x: u32, y: u32, color: Color) {
+		let pixel = self.image.get_pixel_mut(x, y);
+		let a = color.a.clamp(0.0, 1.0);
+
+		let mix = |dst: u8, src: f32| {
+			let dst = dst as f32 / 255.0;
+			((src.clamp(0.0, 1.0) * a + dst * (1.0 - a)) * 255.0).round() as u8
+		};
+
+		pixel.0 = [
+			mix(pixel.0[0], color.r),
+			mix(pixel.0[1], color.g),
+			mix(pixel.0[2], color.b),
+			mix(pixel.0[3], 1.0),
+		];
+	}
+}
+
+fn segment_distance(p: Vec2, start: Vec2, end: Vec2) -> f32 {
+	let line = end - start;
+	let length_squared = line.x * line.x + line.y * line.y;
+
+	if length_squared == 0.0 {
+		return p.distance_with(start);
+	}
+
+	let along = (p - start).x * line.x + (p - start).y * line.y;
+	let t = (along / length_squared).clamp(0.0, 1.0);
+
+	p.distance_with(start + line * t)
+}
+
+#[cfg(test)]
+mod test {
+	use std::path::PathBuf;
+
+	use image::{Rgba, RgbaImage};
+	use smitten::{Color, TextureId, Vec2};
+
+	use super::Canvas;
+	use crate::{
+		grid::{Grid, Size, Tile},
+		thing::Thing,
+	};
+
+	/// Compare against `snapshots/<name>.png`. If UPDATE_SNAPSHOTS is set the
+	/// snapshot is written instead, a missing one is a failure otherwise.
+	fn snapshot(name: &str, canvas: &Canvas) {
+		let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+			.join("snapshots")
+			.join(format!("{}.png", name));
+
+		if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+			std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+			canvas.save(&path).unwrap();
+			return;
+		}
+
+		assert!(
+			path.exists(),
+			"{} is missing, run with UPDATE_SNAPSHOTS=1 to make it",
+			path.display()
+		);
+		let golden: RgbaImage = image::open(&path).unwrap().into_rgba8();
+		assert!(
+			golden == canvas.image,
+			"{} doesn't match {}",
+			name,
+			path.display()
+		);
+	}
+
+	fn grid() -> Grid {
+		let mut grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		for (x, y) in [(0, 0), (1, 0), (2, 0), (2, 1), (4, 3)] {
+			grid.set_tile(
+				Size {
+					width: x,
+					height: y,
+				},
+				Some(Tile::Solid),
+			);
+		}
+
+		grid
+	}
+
+	fn canvas() -> Canvas {
+		let mut canvas = Canvas::new((120, 90), 16);
+		canvas.clear();
+		canvas
+	}
+
+	#[test]
+	fn gridlines() {
+		let mut canvas = canvas();
+		for line in grid().gridlines() {
+			canvas.sdf(line);
+		}
+
+		snapshot("gridlines", &canvas);
+	}
+
+	#[test]
+	fn tiles() {
+		let grid = grid();
+		let mut canvas = canvas();
+
+		for (_, tile, p) in grid.tiles_and_position() {
+			if tile.is_some() {
+				let size = (grid.side_length, grid.side_length);
+				canvas.rect(p, size, Color::rgb(0.6, 0.6, 0.7));
+				canvas.rect(p, size, Color::rgba(0.0, 0.0, 0.0, 0.5));
+			}
+		}
+
+		snapshot("tiles", &canvas);
+	}
+
+	#[test]
+	fn player() {
+		let grid = grid();
+		let us = Thing::new((-0.5, 1.25), (1, 1));
+		let mut canvas = canvas();
+
+		for line in grid.gridlines() {
+			canvas.sdf(line);
+		}
+		canvas.rect(us.center, us.half_size * 2.0, Color::rgb(0.1, 0.3, 0.5));
+
+		snapshot("player", &canvas);
+	}
+
+	#[test]
+	fn textures() {
+		let mut canvas = canvas();
+
+		// Red, green, blue and white quarters, red in the top left
+		let mut quarters = RgbaImage::new(2, 2);
+		quarters.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+		quarters.put_pixel(1, 0, Rgba([0, 255, 0, 255]));
+		quarters.put_pixel(0, 1, Rgba([0, 0, 255, 255]));
+		quarters.put_pixel(1, 1, Rgba([255, 255, 255, 255]));
+		canvas.set_texture(TextureId(1), quarters);
+
+		canvas.rect((-2.0, 0.0), (2.0, 2.0), TextureId(1));
+		// Only the green quarter, stretched
+		let green = (Vec2::new(0.5, 0.5), Vec2::new(1.0, 1.0));
+		canvas.rect_uv((0.5, 0.0), (1.0, 2.0), TextureId(1), green);
+		// Never set, so it's pink
+		canvas.rect((2.5, 0.0), (1.0, 1.0), TextureId(2));
+
+		snapshot("textures", &canvas);
+	}
+
+	#[test]
+	fn rotated() {
+		let mut canvas = canvas();
+		canvas.rect_rotated(
+			(0.0, 0.0),
+			(3.0, 0.5),
+			std::f32::consts::FRAC_PI_4,
+			Color::rgb(1.0, 1.0, 1.0),
+		);
+
+		// An eighth of a turn counterclockwise goes up to the right
+		let lit = |x: u32, y: u32| canvas.image.get_pixel(x, y).0[0] == 255;
+		assert!(lit(60 + 12, 45 - 12));
+		assert!(!lit(60 + 12, 45 + 12));
+		assert!(lit(60, 45));
+
+		// Straight edges right where they should be, 24 pixels out along it
+		// and 4 across
+		assert!(lit(76, 28) && !lit(77, 27));
+		assert!(lit(43, 61) && !lit(42, 62));
+		assert!(lit(62, 47) && !lit(63, 48));
+		assert!(lit(57, 42) && !lit(56, 41));
+	}
+}
@@ -0,0 +1,242 @@
+use std::cell::RefCell;
+
+use smitten::{Color, SignedDistance, Vec2};
+
+use crate::{
+	camera::Camera,
+	grid::{Grid, Size},
+	physics::AxisAlignedBoundingBox,
+	render::Command,
+};
+
+/// More than this in a frame and something's calling us in a loop it
+/// shouldn't be, so the rest are dropped
+const MAX_SHAPES: usize = 8192;
+
+// Color::rgba can't be used in a const
+const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+	Color { r, g, b, a }
+}
+
+const AABB: Color = rgba(0.2, 1.0, 0.3, 0.9);
+const MOTION: Color = rgba(0.2, 0.8, 1.0, 0.9);
+const CONTACT: Color = rgba(1.0, 0.2, 0.2, 1.0);
+const NORMAL: Color = rgba(1.0, 0.9, 0.2, 1.0);
+const RAY: Color = rgba(1.0, 0.3, 1.0, 0.8);
+const BROAD_PHASE: Color = rgba(1.0, 1.0, 1.0, 0.25);
+
+/// How long, in units, normals are drawn
+const NORMAL_LENGTH: f32 = 0.5;
+
+thread_local! {
+	static OVERLAY: RefCell<Overlay> = RefCell::new(Overlay::default());
+}
+
+/// Everything asked to be drawn during the last step, in the world
+#[derive(Default)]
+struct Overlay {
+	enabled: bool,
+	shapes: Vec<SignedDistance>,
+}
+
+/// Whether anything's being collected. Worth checking before doing any work
+/// that's only for the overlay.
+pub fn enabled() -> bool {
+	OVERLAY.with(|o| o.borrow().enabled)
+}
+
+pub fn set_enabled(enabled: bool) {
+	OVERLAY.with(|o| {
+		let mut overlay = o.borrow_mut();
+		overlay.enabled = enabled;
+		overlay.shapes.clear();
+	});
+}
+
+pub fn toggle() {
+	set_enabled(!enabled());
+}
+
+fn push(shape: SignedDistance) {
+	OVERLAY.with(|o| {
+		let mut overlay = o.borrow_mut();
+
+		if overlay.enabled && overlay.shapes.len() < MAX_SHAPES {
+			overlay.shapes.push(shape);
+		}
+	});
+}
+
+pub fn line(start: Vec2, end: Vec2, thickness: u32, color: Color) {
+	push(SignedDistance::LineSegment {
+		start,
+		end,
+		thickness,
+		color,
+	});
+}
+
+pub fn point(center: Vec2, radius: u32, color: Color) {
+	push(SignedDistance::Circle {
+		center,
+		radius,
+		color,
+	});
+}
+
+/// A closed outline through every corner
+pub fn polygon(corners: &[Vec2], color: Color) {
+	for (i, start) in corners.iter().enumerate() {
+		line(*start, corners[(i + 1) % corners.len()], 1, color);
+	}
+}
+
+pub fn aabb<A: AxisAlignedBoundingBox>(a: &A) {
+	let (bl, tr) = (a.bottom_left(), a.top_right());
+	polygon(
+		&[bl, Vec2::new(tr.x, bl.y), tr, Vec2::new(bl.x, tr.y)],
+		AABB,
+	);
+}
+
+/// A rotated box, from its corners
+pub fn obb(corners: &[Vec2]) {
+	polygon(corners, AABB);
+}
+
+/// Where something moved this step
+pub fn motion(from: Vec2, to: Vec2) {
+	line(from, to, 1, MOTION);
+	point(to, 2, MOTION);
+}
+
+/// A point things touched at, and which way they were pushed apart
+pub fn contact(point_at: Vec2, normal: Vec2) {
+	let length = normal.distance_with(Vec2::ZERO);
+	if length > 0.0 {
+		line(
+			point_at,
+			point_at + normal * (NORMAL_LENGTH / length),
+			1,
+			NORMAL,
+		);
+	}
+
+	point(point_at, 3, CONTACT);
+}
+
+/// A ray from `start` toward `end`, stopping where it hit something if it did
+pub fn ray(start: Vec2, end: Vec2, hit: Option<Vec2>) {
+	match hit {
+		Some(hit) => {
+			line(start, hit, 1, RAY);
+			point(hit, 2, CONTACT);
+		}
+		None => line(start, end, 1, RAY),
+	}
+}
+
+/// Every line of the grid, dimmed
+pub fn grid(grid: &Grid) {
+	for line in grid.gridlines() {
+		if let SignedDistance::LineSegment { start, end, .. } = line {
+			self::line(start, end, 1, BROAD_PHASE);
+		}
+	}
+}
+
+/// Highlight a cell that something's being checked against
+pub fn cell(grid: &Grid, coords: Size) {
+	if let Some(center) = grid.coordinate_center(coords.width, coords.height) {
+		let half = grid.side_length / 2.0 * 0.9;
+
+		polygon(
+			&[
+				center + Vec2::new(-half, -half),
+				center + Vec2::new(half, -half),
+				center + Vec2::new(half, half),
+				center + Vec2::new(-half, half),
+			],
+			BROAD_PHASE,
+		);
+	}
+}
+
+/// Forget the last step's shapes. Call it as a step starts, so frames
+/// drawn between steps all show the same ones.
+pub fn clear() {
+	OVERLAY.with(|o| o.borrow_mut().shapes.clear());
+}
+
+/// Everything from the last step, ready to be drawn on top of the frame
+pub fn commands(camera: &Camera) -> Vec<Command> {
+	OVERLAY.with(|o| {
+		o.borrow()
+			.shapes
+			.iter()
+			.map(|shape| Command::Sdf(camera.sdf(*shape)))
+			.collect()
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::{SignedDistance, Vec2};
+
+	use super::{clear, commands, contact, motion, ray, set_enabled};
+	use crate::{camera::Camera, render::Command};
+
+	#[test]
+	fn collects_only_when_enabled() {
+		let mut camera = Camera::new((100, 100), 10);
+
+		motion(Vec2::ZERO, Vec2::new(1.0, 0.0));
+		assert!(commands(&camera).is_empty());
+
+		set_enabled(true);
+		contact(Vec2::new(2.0, 2.0), Vec2::new(0.0, 3.0));
+
+		// Drawn like everything else, through the camera
+		camera.position = Vec2::new(1.0, 1.0);
+		let drawn = commands(&camera);
+		assert_eq!(drawn.len(), 2);
+		match &drawn[0] {
+			Command::Sdf(SignedDistance::LineSegment { start, end, .. }) => {
+				assert_eq!((start.x, start.y), (1.0, 1.0));
+				assert_eq!((end.x, end.y), (1.0, 1.5));
+			}
+			other => panic!("expected the normal, got {:?}", other),
+		}
+
+		// Every frame gets them until the next step starts over
+		assert_eq!(commands(&camera).len(), 2);
+		clear();
+		assert!(commands(&camera).is_empty());
+		set_enabled(false);
+	}
+
+	#[test]
+	fn rays_stop_at_what_they_hit() {
+		let camera = Camera::new((100, 100), 10);
+		set_enabled(true);
+
+		ray(Vec2::ZERO, Vec2::new(2.0, 0.0), None);
+		ray(Vec2::ZERO, Vec2::new(2.0, 0.0), Some(Vec2::new(1.0, 0.0)));
+
+		let drawn = commands(&camera);
+		assert_eq!(drawn.len(), 3);
+		match (&drawn[0], &drawn[1], &drawn[2]) {
+			(
+				Command::Sdf(SignedDistance::LineSegment { end: missed, .. }),
+				Command::Sdf(SignedDistance::LineSegment { end: hit, .. }),
+				Command::Sdf(SignedDistance::Circle { center, .. }),
+			) => {
+				assert_eq!((missed.x, missed.y), (2.0, 0.0));
+				assert_eq!((hit.x, hit.y), (1.0, 0.0));
+				assert_eq!((center.x, center.y), (1.0, 0.0));
+			}
+			other => panic!("expected two rays and a hit, got {:?}", other),
+		}
+		set_enabled(false);
+	}
+}
@@ -0,0 +1,402 @@
+use std::collections::HashSet;
+
+use smitten::Vec2;
+
+use crate::{
+	grid::{Grid, Size, Tile},
+	history::History,
+	ui::Ui,
+};
+
+const MAX_BRUSH: u32 = 5;
+/// About how wide the panel is, so it can go in the top right
+const PANEL_WIDTH: f32 = 4.6;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tool {
+	/// Paint a square of tiles under the cursor while the button is held
+	Brush,
+	/// Drag out a rectangle, it's filled when the button is released
+	Rectangle,
+	/// Replace the connected area of matching tiles under the cursor
+	Fill,
+}
+
+/// What the panel wants done outside of the editor
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PanelAction {
+	Save,
+	/// The level field was entered, so the level lives there now
+	UseLevel,
+}
+
+/// What the mouse is doing this frame
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Pointer {
+	/// The grid cell under the cursor, if there is one
+	pub cell: Option<Size>,
+	/// Primary button, places the selected tile
+	pub place: bool,
+	/// Secondary button, erases
+	pub erase: bool,
+}
+
+pub struct Editor {
+	pub enabled: bool,
+	pub tool: Tool,
+	pub brush_size: u32,
+
+	pub palette: Vec<Tile>,
+	pub selected: usize,
+
+	// The button that started the current stroke and, for rectangles, where
+	// the drag started.
+	stroke: Option<Stroke>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Stroke {
+	tile: Option<Tile>,
+	start: Size,
+	/// The last cell the cursor was on, so a rectangle dragged off the grid
+	/// stops at the edge it left by
+	last: Size,
+}
+
+impl Default for Editor {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Editor {
+	pub fn new() -> Self {
+		Self {
+			enabled: false,
+			tool: Tool::Brush,
+			brush_size: 1,
+
+			palette: vec![Tile::Solid, Tile::Backdrop],
+			selected: 0,
+
+			stroke: None,
+		}
+	}
+
+	pub fn toggle(&mut self, history: &mut History) {
+		self.enabled = !self.enabled;
+		self.stroke = None;
+		history.end_group();
+	}
+
+	pub fn grow_brush(&mut self) {
+		self.brush_size = (self.brush_size + 1).min(MAX_BRUSH);
+	}
+
+	pub fn shrink_brush(&mut self) {
+		self.brush_size = (self.brush_size - 1).max(1);
+	}
+
+	/// Select a palette entry. Out of range selections are ignored.
+	pub fn select(&mut self, idx: usize) {
+		if idx < self.palette.len() {
+			self.selected = idx;
+		}
+	}
+
+	pub fn selected_tile(&self) -> Tile {
+		self.palette[self.selected]
+	}
+
+	/// Tools, brush size and the palette as widgets in the top right. `path`
+	/// is what's typed in the level field, which isn't used until it's
+	/// entered.
+	pub fn panel(&mut self, ui: &mut Ui, path: &mut String) -> Option<PanelAction> {
+		let corner = ui.top_left();
+		let top_left = Vec2::new(-corner.x - PANEL_WIDTH, corner.y);
+		let mut action = None;
+
+		ui.panel("EDITOR", top_left, |ui| {
+			ui.row(|ui| {
+				for (tool, name) in [
+					(Tool::Brush, "BRUSH"),
+					(Tool::Rectangle, "RECT"),
+					(Tool::Fill, "FILL"),
+				] {
+					if ui.button(name) {
+						self.tool = tool;
+					}
+				}
+			});
+
+			let mut size = self.brush_size as f32;
+			if ui.slider("SIZE", &mut size, 1.0, MAX_BRUSH as f32) {
+				self.brush_size = size.round() as u32;
+			}
+
+			ui.row(|ui| {
+				for idx in 0..self.palette.len() {
+					if ui.button(&format!("{}", idx + 1)) {
+						self.select(idx);
+					}
+				}
+			});
+
+			if ui.text_field("LEVEL", path) {
+				action = Some(PanelAction::UseLevel);
+			}
+			if ui.button("SAVE") {
+				action = Some(PanelAction::Save);
+			}
+		});
+
+		action
+	}
+
+	pub fn update(&mut self, grid: &mut Grid, history: &mut History, pointer: Pointer) {
+		if !self.enabled {
+			return;
+		}
+
+		let held = if pointer.place {
+			Some(Some(self.selected_tile()))
+		} else if pointer.erase {
+			Some(None)
+		} else {
+			None
+		};
+
+		match (self.stroke, held, pointer.cell) {
+			// Button just went down. Group everything until it's released so
+			// the whole stroke can be undone at once.
+			(None, Some(tile), Some(cell)) => {
+				history.begin_group();
+				self.stroke = Some(Stroke {
+					tile,
+					start: cell,
+					last: cell,
+				});
+
+				match self.tool {
+					Tool::Brush => self.paint(grid, history, cell, tile),
+					Tool::Fill => flood_fill(grid, history, cell, tile),
+					Tool::Rectangle => (),
+				}
+			}
+			// Still held
+			(Some(mut stroke), Some(_), Some(cell)) => {
+				stroke.last = cell;
+				self.stroke = Some(stroke);
+
+				if self.tool == Tool::Brush {
+					self.paint(grid, history, cell, stroke.tile);
+				}
+			}
+			// Released
+			(Some(stroke), None, cell) => {
+				if self.tool == Tool::Rectangle {
+					let end = cell.unwrap_or(stroke.last);
+					fill_rect(grid, history, stroke.start, end, stroke.tile);
+				}
+
+				self.stroke = None;
+				history.end_group();
+			}
+			_ => (),
+		}
+	}
+
+	/// The cells that'll change if the current action is finished, for
+	/// drawing a preview under the cursor.
+	pub fn preview(&self, cursor: Option<Size>) -> Vec<Size> {
+		if !self.enabled {
+			return vec![];
+		}
+
+		match (self.tool, self.stroke, cursor) {
+			(Tool::Rectangle, Some(stroke), cursor) => {
+				rect_cells(stroke.start, cursor.unwrap_or(stroke.last))
+			}
+			(_, _, None) => vec![],
+			(Tool::Brush, _, Some(cursor)) => self.brush_cells(cursor),
+			(_, _, Some(cursor)) => vec![cursor],
+		}
+	}
+
+	fn brush_cells(&self, center: Size) -> Vec<Size> {
+		// Odd sizes are centered on the cursor, even ones lean up and right
+		let back = (self.brush_size - 1) / 2;
+		let start = Size {
+			width: center.width.saturating_sub(back),
+			height: center.height.saturating_sub(back),
+		};
+		let end = Size {
+			width: center.width + self.brush_size / 2,
+			height: center.height + self.brush_size / 2,
+		};
+
+		rect_cells(start, end)
+	}
+
+	fn paint(&self, grid: &mut Grid, history: &mut History, center: Size, tile: Option<Tile>) {
+		for cell in self.brush_cells(center) {
+			history.set_tile(grid, cell, tile);
+		}
+	}
+}
+
+/// All of the cells in the rectangle with corners at `a` and `b`, inclusive.
+fn rect_cells(a: Size, b: Size) -> Vec<Size> {
+	let mut cells = vec![];
+
+	for x in a.width.min(b.width)..=a.width.max(b.width) {
+		for y in a.height.min(b.height)..=a.height.max(b.height) {
+			cells.push(Size {
+				width: x,
+				height: y,
+			});
+		}
+	}
+
+	cells
+}
+
+pub fn fill_rect(grid: &mut Grid, history: &mut History, a: Size, b: Size, tile: Option<Tile>) {
+	for cell in rect_cells(a, b) {
+		history.set_tile(grid, cell, tile);
+	}
+}
+
+/// Four-way flood fill starting at `start`
+pub fn flood_fill(grid: &mut Grid, history: &mut History, start: Size, tile: Option<Tile>) {
+	if !grid.contains(start.width as i32, start.height as i32) {
+		return;
+	}
+
+	let target = grid.tile(start).copied();
+	if target == tile {
+		return;
+	}
+
+	let mut seen = HashSet::new();
+	let mut stack = vec![(start.width as i32, start.height as i32)];
+
+	while let Some((x, y)) = stack.pop() {
+		if !grid.contains(x, y) || !seen.insert((x, y)) {
+			continue;
+		}
+
+		let cell = Size {
+			width: x as u32,
+			height: y as u32,
+		};
+
+		if grid.tile(cell).copied() != target {
+			continue;
+		}
+
+		history.set_tile(grid, cell, tile);
+		stack.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{flood_fill, Editor, Pointer, Tool};
+	use crate::{
+		grid::{Grid, Size, Tile},
+		history::History,
+	};
+
+	fn size(x: u32, y: u32) -> Size {
+		Size {
+			width: x,
+			height: y,
+		}
+	}
+
+	fn solid_count(grid: &Grid) -> usize {
+		grid.tiles_and_position()
+			.iter()
+			.filter(|(_, t, _)| t.is_some())
+			.count()
+	}
+
+	#[test]
+	fn flood_fill_stops_at_walls() {
+		let mut grid = Grid::new((5, 1), (0.0, 0.0), 1.0);
+		grid.set_tile(size(2, 0), Some(Tile::Solid));
+
+		flood_fill(
+			&mut grid,
+			&mut History::new(8),
+			size(0, 0),
+			Some(Tile::Solid),
+		);
+
+		assert!(grid.is_solid(size(1, 0)));
+		assert!(!grid.is_solid(size(3, 0)));
+	}
+
+	#[test]
+	fn rectangle_fills_on_release() {
+		let mut grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		let mut history = History::new(8);
+		let mut editor = Editor::new();
+		editor.toggle(&mut history);
+		editor.tool = Tool::Rectangle;
+
+		let mut pointer = Pointer {
+			cell: Some(size(1, 1)),
+			place: true,
+			erase: false,
+		};
+		editor.update(&mut grid, &mut history, pointer);
+		pointer.cell = Some(size(3, 2));
+		editor.update(&mut grid, &mut history, pointer);
+		assert_eq!(solid_count(&grid), 0);
+
+		pointer.place = false;
+		editor.update(&mut grid, &mut history, pointer);
+		assert_eq!(solid_count(&grid), 6);
+
+		// Let go off the grid and it stops at the edge it left by
+		pointer.place = true;
+		pointer.cell = Some(size(4, 4));
+		editor.update(&mut grid, &mut history, pointer);
+		pointer.cell = Some(size(4, 3));
+		editor.update(&mut grid, &mut history, pointer);
+		pointer.cell = None;
+		editor.update(&mut grid, &mut history, pointer);
+		assert_eq!(editor.preview(None).len(), 2);
+
+		pointer.place = false;
+		editor.update(&mut grid, &mut history, pointer);
+		assert_eq!(solid_count(&grid), 8);
+	}
+
+	#[test]
+	fn undo_whole_stroke() {
+		let mut grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		let mut history = History::new(8);
+		let mut editor = Editor::new();
+		editor.toggle(&mut history);
+
+		for x in 0..3 {
+			let pointer = Pointer {
+				cell: Some(size(x, 0)),
+				place: true,
+				erase: false,
+			};
+			editor.update(&mut grid, &mut history, pointer);
+		}
+		editor.update(&mut grid, &mut history, Pointer::default());
+		assert_eq!(solid_count(&grid), 3);
+
+		history.undo(&mut grid);
+		assert_eq!(solid_count(&grid), 0);
+
+		history.redo(&mut grid);
+		assert_eq!(solid_count(&grid), 3);
+	}
+}
@@ -0,0 +1,319 @@
+use std::ops::{Index, IndexMut};
+
+use smitten::Vec2;
+
+use crate::{atlas::Sprite, platform::Kinematic, scene::Attachment, thing::Thing};
+
+/// A handle to something in the world. Ids are reused after a despawn, the
+/// generation is what stops an old handle from reaching the new entity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+	index: u32,
+	generation: u32,
+}
+
+impl Entity {
+	pub fn index(&self) -> u32 {
+		self.index
+	}
+}
+
+/// Where something is, in the world
+#[derive(Copy, Clone, Debug)]
+pub struct Transform {
+	pub position: Vec2,
+	/// Where it was at the start of the step
+	pub previous: Vec2,
+	/// Radians, counterclockwise
+	pub rotation: f32,
+	pub scale: f32,
+}
+
+impl Transform {
+	pub fn new<P: Into<Vec2>>(position: P) -> Self {
+		let position = position.into();
+
+		Self {
+			position,
+			previous: position,
+			rotation: 0.0,
+			scale: 1.0,
+		}
+	}
+}
+
+/// A box centered on the transform that can be collided with
+#[derive(Copy, Clone, Debug)]
+pub struct Collider {
+	pub size: Vec2,
+}
+
+/// Something that moves on its own
+#[derive(Copy, Clone, Debug)]
+pub struct Body {
+	/// Units per second
+	pub velocity: Vec2,
+}
+
+/// What something looks like, centered on the transform
+#[derive(Copy, Clone, Debug)]
+pub struct Look {
+	pub sprite: Sprite,
+	pub size: Vec2,
+}
+
+/// Can be picked up and carried around by the player
+#[derive(Copy, Clone, Debug)]
+pub struct Pickup;
+
+/// Moved around by the player's input
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Controller {
+	/// Units per step
+	pub speed: f32,
+}
+
+/// One kind of component for every entity that has it, indexed by the
+/// entity's index.
+pub struct Components<T> {
+	slots: Vec<Option<(Entity, T)>>,
+}
+
+impl<T> Default for Components<T> {
+	fn default() -> Self {
+		Self { slots: vec![] }
+	}
+}
+
+impl<T> Components<T> {
+	/// Give `entity` this component, replacing the one it had
+	pub fn insert(&mut self, entity: Entity, component: T) {
+		let idx = entity.index as usize;
+		if idx >= self.slots.len() {
+			self.slots.resize_with(idx + 1, || None);
+		}
+
+		self.slots[idx] = Some((entity, component));
+	}
+
+	pub fn remove(&mut self, entity: Entity) -> Option<T> {
+		let slot = self.slots.get_mut(entity.index as usize)?;
+
+		match slot {
+			Some((e, _)) if *e == entity => slot.take().map(|(_, c)| c),
+			_ => None,
+		}
+	}
+
+	pub fn get(&self, entity: Entity) -> Option<&T> {
+		match self.slots.get(entity.index as usize)? {
+			Some((e, component)) if *e == entity => Some(component),
+			_ => None,
+		}
+	}
+
+	pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+		match self.slots.get_mut(entity.index as usize)? {
+			Some((e, component)) if *e == entity => Some(component),
+			_ => None,
+		}
+	}
+
+	pub fn contains(&self, entity: Entity) -> bool {
+		self.get(entity).is_some()
+	}
+
+	/// Every entity with this component, oldest index first
+	pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+		self.slots.iter().flatten().map(|(e, c)| (*e, c))
+	}
+
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+		self.slots.iter_mut().flatten().map(|(e, c)| (*e, c))
+	}
+}
+
+impl<T> Index<Entity> for Components<T> {
+	type Output = T;
+
+	fn index(&self, entity: Entity) -> &T {
+		self.get(entity)
+			.expect("entity doesn't have that component")
+	}
+}
+
+impl<T> IndexMut<Entity> for Components<T> {
+	fn index_mut(&mut self, entity: Entity) -> &mut T {
+		self.get_mut(entity)
+			.expect("entity doesn't have that component")
+	}
+}
+
+/// Every entity and their components. Systems iterate over one component and
+/// look up the others they need, so a query for things with a transform and
+/// a body looks like:
+///
+/// ```ignore
+/// for (entity, body) in entities.bodies.iter() {
+///     if let Some(transform) = entities.transforms.get_mut(entity) {
+///         ...
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct Entities {
+	pub transforms: Components<Transform>,
+	pub colliders: Components<Collider>,
+	pub bodies: Components<Body>,
+	pub looks: Components<Look>,
+	pub controllers: Components<Controller>,
+	pub attachments: Components<Attachment>,
+	pub kinematics: Components<Kinematic>,
+	pub pickups: Components<Pickup>,
+
+	generations: Vec<u32>,
+	alive: Vec<bool>,
+	free: Vec<u32>,
+}
+
+impl Entities {
+	/// No components, with every index's generation and the indices waiting
+	/// to be reused, like `allocator` gives them. Anything not free is alive.
+	/// Indices in `free` have to be below the number of generations.
+	pub fn with_allocator(generations: Vec<u32>, free: Vec<u32>) -> Self {
+		let mut alive = vec![true; generations.len()];
+		for index in &free {
+			alive[*index as usize] = false;
+		}
+
+		Self {
+			generations,
+			alive,
+			free,
+			..Self::default()
+		}
+	}
+
+	/// Every index's generation, and the indices that'll be reused, last
+	/// first. Enough to bring back the same handles later.
+	pub fn allocator(&self) -> (&[u32], &[u32]) {
+		(&self.generations, &self.free)
+	}
+
+	/// The live entity at `index`, if there is one
+	pub fn at(&self, index: u32) -> Option<Entity> {
+		let idx = index as usize;
+
+		match self.alive.get(idx) {
+			Some(true) => Some(Entity {
+				index,
+				generation: self.generations[idx],
+			}),
+			_ => None,
+		}
+	}
+
+	pub fn spawn(&mut self) -> Entity {
+		match self.free.pop() {
+			Some(index) => {
+				self.alive[index as usize] = true;
+
+				Entity {
+					index,
+					generation: self.generations[index as usize],
+				}
+			}
+			None => {
+				self.generations.push(0);
+				self.alive.push(true);
+
+				Entity {
+					index: self.generations.len() as u32 - 1,
+					generation: 0,
+				}
+			}
+		}
+	}
+
+	/// Remove an entity and all its components. Its handle, and any copies,
+	/// stop working.
+	pub fn despawn(&mut self, entity: Entity) {
+		if !self.is_alive(entity) {
+			return;
+		}
+
+		self.transforms.remove(entity);
+		self.colliders.remove(entity);
+		self.bodies.remove(entity);
+		self.looks.remove(entity);
+		self.controllers.remove(entity);
+		self.attachments.remove(entity);
+		self.kinematics.remove(entity);
+		self.pickups.remove(entity);
+
+		let idx = entity.index as usize;
+		self.alive[idx] = false;
+		self.generations[idx] += 1;
+		self.free.push(entity.index);
+	}
+
+	pub fn is_alive(&self, entity: Entity) -> bool {
+		let idx = entity.index as usize;
+		self.alive.get(idx) == Some(&true) && self.generations[idx] == entity.generation
+	}
+
+	/// A `Thing` for an entity's transform and collider, for the physics
+	/// code that works with those.
+	pub fn thing(&self, entity: Entity) -> Option<Thing> {
+		let transform = self.transforms.get(entity)?;
+		let collider = self.colliders.get(entity)?;
+
+		let mut thing = Thing::new(transform.previous, collider.size);
+		thing.offset(transform.position - transform.previous);
+		thing.rotation = transform.rotation;
+		Some(thing)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::Vec2;
+
+	use super::{Body, Entities, Transform};
+
+	#[test]
+	fn stale_handles_miss() {
+		let mut entities = Entities::default();
+		let a = entities.spawn();
+		entities.transforms.insert(a, Transform::new((1.0, 2.0)));
+
+		entities.despawn(a);
+		let b = entities.spawn();
+		entities.transforms.insert(b, Transform::new((3.0, 4.0)));
+
+		// Same slot, different generation
+		assert!(!entities.is_alive(a));
+		assert!(entities.transforms.get(a).is_none());
+		assert_eq!(entities.transforms[b].position.x, 3.0);
+	}
+
+	#[test]
+	fn iterates_only_with_component() {
+		let mut entities = Entities::default();
+		let moving = entities.spawn();
+		let still = entities.spawn();
+
+		for e in [moving, still] {
+			entities.transforms.insert(e, Transform::new((0.0, 0.0)));
+		}
+		entities.bodies.insert(
+			moving,
+			Body {
+				velocity: Vec2::new(1.0, 0.0),
+			},
+		);
+
+		let with_bodies: Vec<_> = entities.bodies.iter().map(|(e, _)| e).collect();
+		assert_eq!(with_bodies, vec![moving]);
+	}
+}
@@ -0,0 +1,159 @@
+use smitten::Vec2;
+
+/// Pixels across a glyph, not counting the gap after it
+pub const WIDTH: usize = 3;
+/// Pixels down a glyph, not counting the gap under it
+pub const HEIGHT: usize = 5;
+
+/// A tiny bitmap font. Every glyph is five rows from the top, each three bits
+/// with the leftmost pixel as the highest bit. Lowercase letters are drawn
+/// as uppercase and anything we don't have is a solid block.
+fn glyph(c: char) -> [u8; HEIGHT] {
+	match c.to_ascii_uppercase() {
+		' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+		'0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+		'1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+		'2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+		'3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+		'4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+		'5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+		'6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+		'7' => [0b111, 0b001, 0b001, 0b010, 0b010],
+		'8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+		'9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+		'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+		'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+		'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+		'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+		'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+		'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+		'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+		'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+		'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+		'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+		'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+		'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+		'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+		'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+		'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+		'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+		'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+		'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+		'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+		'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+		'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+		'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+		'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+		'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+		'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+		'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+		'.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+		',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+		':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+		';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+		'-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+		'+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+		'=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+		'_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+		'*' => [0b000, 0b101, 0b010, 0b101, 0b000],
+		'#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+		'/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+		'%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+		'(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+		')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+		'[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+		']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+		'<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+		'>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+		'!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+		'?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+		'\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+		'"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+		_ => [0b111, 0b111, 0b111, 0b111, 0b111],
+	}
+}
+
+/// How big one pixel of the font is when a line is `size` units tall. Lines
+/// have a pixel of space under them.
+fn pixel(size: f32) -> f32 {
+	size / (HEIGHT + 1) as f32
+}
+
+/// How wide the longest line of `text` is, in units
+pub fn width(text: &str, size: f32) -> f32 {
+	let longest = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+
+	// Every glyph has a pixel of space after it but the last doesn't need it
+	(longest * (WIDTH + 1)).saturating_sub(1) as f32 * pixel(size)
+}
+
+/// How tall `text` is, in units
+pub fn height(text: &str, size: f32) -> f32 {
+	text.lines().count().max(1) as f32 * size
+}
+
+/// The rects that make up `text`, as centers and sizes. `position` is the top
+/// left of the first line. Pixels next to each other in a row are joined
+/// into one rect so there's less to draw.
+pub fn layout(position: Vec2, size: f32, text: &str) -> Vec<(Vec2, Vec2)> {
+	let px = pixel(size);
+	let mut rects = vec![];
+
+	for (line_idx, line) in text.lines().enumerate() {
+		let top = position.y - line_idx as f32 * size;
+
+		for (char_idx, c) in line.chars().enumerate() {
+			let left = position.x + (char_idx * (WIDTH + 1)) as f32 * px;
+
+			for (row_idx, row) in glyph(c).iter().enumerate() {
+				let y = top - (row_idx as f32 + 0.5) * px;
+				let mut col = 0;
+
+				while col < WIDTH {
+					if row & (1 << (WIDTH - 1 - col)) == 0 {
+						col += 1;
+						continue;
+					}
+
+					let start = col;
+					while col < WIDTH && row & (1 << (WIDTH - 1 - col)) != 0 {
+						col += 1;
+					}
+
+					let run = (col - start) as f32;
+					rects.push((
+						Vec2::new(left + (start as f32 + run / 2.0) * px, y),
+						Vec2::new(run * px, px),
+					));
+				}
+			}
+		}
+	}
+
+	rects
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::Vec2;
+
+	use super::{layout, width};
+
+	#[test]
+	fn runs_are_joined() {
+		// The top of a T is one rect and its stem is one pixel each row
+		let rects = layout(Vec2::ZERO, 6.0, "T");
+		assert_eq!(rects.len(), 5);
+
+		let (center, size) = rects[0];
+		assert_eq!((center.x, center.y), (1.5, -0.5));
+		assert_eq!((size.x, size.y), (3.0, 1.0));
+
+		// Second line starts a whole line down
+		let rects = layout(Vec2::ZERO, 6.0, " \n.");
+		assert_eq!(rects.len(), 1);
+		assert_eq!(rects[0].0.y, -6.0 - 4.5);
+
+		assert_eq!(width("AB\nC", 6.0), 7.0);
+	}
+}
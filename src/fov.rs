@@ -0,0 +1,279 @@
+use std::collections::{HashMap, HashSet};
+
+use smitten::{Color, Vec2};
+
+use crate::grid::{Grid, Size};
+
+// Transforms that take the first octant to each of the eight. They're
+// xx, xy, yx, yy, the same layout as the RogueBasin python example this
+// is based on:
+// http://www.roguebasin.com/index.php/FOV_using_recursive_shadowcasting
+const OCTANTS: [[i32; 4]; 8] = [
+	[1, 0, 0, 1],
+	[0, 1, 1, 0],
+	[0, -1, 1, 0],
+	[-1, 0, 0, 1],
+	[-1, 0, 0, -1],
+	[0, -1, -1, 0],
+	[0, 1, -1, 0],
+	[1, 0, 0, -1],
+];
+
+/// Every cell that can be seen from `origin`, no further than `radius` cells
+/// away. Solid tiles block sight but are themselves visible, so walls light
+/// up when you look at them.
+pub fn visible_cells(grid: &Grid, origin: Size, radius: u32) -> HashSet<Size> {
+	let mut visible = HashSet::new();
+
+	if !grid.contains(origin.width as i32, origin.height as i32) {
+		return visible;
+	}
+	visible.insert(origin);
+
+	let caster = Caster {
+		grid,
+		origin: (origin.width as i32, origin.height as i32),
+		radius: radius as i32,
+	};
+
+	for octant in OCTANTS {
+		caster.cast(1, 1.0, 0.0, octant, &mut visible);
+	}
+
+	visible
+}
+
+struct Caster<'g> {
+	grid: &'g Grid,
+	origin: (i32, i32),
+	radius: i32,
+}
+
+impl<'g> Caster<'g> {
+	// Anything off the edge of the grid blocks sight. There's nothing out
+	// there to see anyway.
+	fn blocks(&self, x: i32, y: i32) -> bool {
+		!self.grid.contains(x, y)
+			|| self.grid.is_solid(Size {
+				width: x as u32,
+				height: y as u32,
+			})
+	}
+
+	fn cast(
+		&self,
+		row: i32,
+		mut start: f32,
+		end: f32,
+		octant: [i32; 4],
+		visible: &mut HashSet<Size>,
+	) {
+		if start < end {
+			return;
+		}
+
+		let [xx, xy, yx, yy] = octant;
+		let mut new_start = 0.0;
+
+		for distance in row..=self.radius {
+			let dy = -distance;
+			let mut dx = -distance - 1;
+			let mut blocked = false;
+
+			while dx <= 0 {
+				dx += 1;
+
+				let x = self.origin.0 + dx * xx + dy * xy;
+				let y = self.origin.1 + dx * yx + dy * yy;
+
+				// Slopes to the left and right edges of this cell
+				let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+				let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+
+				if start < right_slope {
+					continue;
+				} else if end > left_slope {
+					break;
+				}
+
+				if dx * dx + dy * dy <= self.radius * self.radius && self.grid.contains(x, y) {
+					visible.insert(Size {
+						width: x as u32,
+						height: y as u32,
+					});
+				}
+
+				if blocked {
+					if self.blocks(x, y) {
+						new_start = right_slope;
+						continue;
+					}
+
+					blocked = false;
+					start = new_start;
+				} else if self.blocks(x, y) && distance < self.radius {
+					// Start of a wall. Scan the part of the next row that's
+					// still in light and then keep going past the wall.
+					blocked = true;
+					self.cast(distance + 1, start, left_slope, octant, visible);
+					new_start = right_slope;
+				}
+			}
+
+			if blocked {
+				break;
+			}
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Light {
+	pub position: Vec2,
+	/// How far the light reaches, in cells
+	pub radius: u32,
+	pub color: Color,
+}
+
+impl Light {
+	pub fn new<P: Into<Vec2>>(position: P, radius: u32, color: Color) -> Self {
+		Self {
+			position: position.into(),
+			radius,
+			color,
+		}
+	}
+}
+
+/// How much light lands on every cell of a grid. Lights don't shine through
+/// solid tiles, and fall off with the square of their distance.
+pub struct LightMap {
+	ambient: Color,
+	cells: HashMap<Size, Color>,
+}
+
+impl LightMap {
+	pub fn new(grid: &Grid, lights: &[Light], ambient: Color) -> Self {
+		let mut cells: HashMap<Size, Color> = HashMap::new();
+
+		for light in lights {
+			let origin = match grid.get_coords(light.position) {
+				Some(origin) => origin,
+				None => continue,
+			};
+
+			for cell in visible_cells(grid, origin, light.radius) {
+				let dx = cell.width as f32 - origin.width as f32;
+				let dy = cell.height as f32 - origin.height as f32;
+				let distance = (dx * dx + dy * dy).sqrt();
+
+				// +1 so the edge of the radius is dim but not fully dark
+				let falloff = 1.0 - distance / (light.radius as f32 + 1.0);
+				let falloff = falloff * falloff;
+
+				let lit = cells.entry(cell).or_insert(Color::rgba(0.0, 0.0, 0.0, 1.0));
+				lit.r += light.color.r * falloff;
+				lit.g += light.color.g * falloff;
+				lit.b += light.color.b * falloff;
+			}
+		}
+
+		Self { ambient, cells }
+	}
+
+	/// The light reaching a cell, ambient included. Components may go over
+	/// 1.0 where lights overlap.
+	pub fn light(&self, coords: Size) -> Color {
+		let mut light = self.ambient;
+
+		if let Some(lit) = self.cells.get(&coords) {
+			light.r += lit.r;
+			light.g += lit.g;
+			light.b += lit.b;
+		}
+
+		light
+	}
+
+	/// A translucent color to draw over a cell to make it look lit. Dark
+	/// cells get close to black, lit ones pick up a little of the light's hue.
+	pub fn overlay(&self, coords: Size) -> Color {
+		let light = self.light(coords);
+		let brightness = light.r.max(light.g).max(light.b).clamp(0.0, 1.0);
+
+		Color::rgba(
+			light.r.min(1.0),
+			light.g.min(1.0),
+			light.b.min(1.0),
+			1.0 - brightness * 0.75,
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::{Color, Vec2};
+
+	use super::{visible_cells, Light, LightMap};
+	use crate::grid::{Grid, Size, Tile};
+
+	fn size(x: u32, y: u32) -> Size {
+		Size {
+			width: x,
+			height: y,
+		}
+	}
+
+	#[test]
+	fn open_grid_is_all_visible() {
+		let grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		let visible = visible_cells(&grid, size(2, 2), 5);
+
+		assert_eq!(visible.len(), 25)
+	}
+
+	#[test]
+	fn radius_limits_sight() {
+		let grid = Grid::new((9, 9), (0.0, 0.0), 1.0);
+		let visible = visible_cells(&grid, size(4, 4), 2);
+
+		assert!(visible.contains(&size(6, 4)));
+		assert!(!visible.contains(&size(7, 4)));
+		assert!(!visible.contains(&size(6, 6)));
+	}
+
+	#[test]
+	fn walls_cast_shadows() {
+		let mut grid = Grid::new((7, 1), (0.0, 0.0), 1.0);
+		grid.set_tile(size(3, 0), Some(Tile::Solid));
+
+		let visible = visible_cells(&grid, size(0, 0), 10);
+
+		// The wall itself is visible, what's behind it isn't
+		assert!(visible.contains(&size(3, 0)));
+		assert!(!visible.contains(&size(4, 0)));
+		assert!(!visible.contains(&size(6, 0)));
+	}
+
+	#[test]
+	fn light_falls_off() {
+		let grid = Grid::new((5, 1), (0.0, 0.0), 1.0);
+		let center = grid.coordinate_center(0, 0).unwrap();
+		let lights = [Light::new(center, 4, Color::rgb(1.0, 1.0, 1.0))];
+		let map = LightMap::new(&grid, &lights, Color::rgb(0.0, 0.0, 0.0));
+
+		let near = map.light(size(0, 0)).r;
+		let far = map.light(size(3, 0)).r;
+		assert!(near > far);
+		assert!(far > 0.0);
+
+		// Off of the grid there's only ambient
+		let outside = [Light::new(
+			Vec2::new(100.0, 0.0),
+			4,
+			Color::rgb(1.0, 0.0, 0.0),
+		)];
+		let nothing = LightMap::new(&grid, &outside, Color::rgb(0.0, 0.0, 0.0));
+		assert_eq!(nothing.light(size(0, 0)).r, 0.0);
+	}
+}
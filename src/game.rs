@@ -0,0 +1,817 @@
+use smitten::{Color, TextureId, Vec2};
+
+use crate::{
+	animation::{Animator, Motion},
+	atlas::{Atlas, Sprite},
+	camera::Camera,
+	debug,
+	editor::PanelAction,
+	grid::Size,
+	hud::Hud,
+	input::{Action, ActionState},
+	level,
+	particle::Emitter,
+	render::{Assets, Command, MISSING},
+	replay::Recording,
+	save::{self, View},
+	state::{Stack, State, Transition},
+	tween::{Animation, Curve, Ease, Tweens},
+	ui::Ui,
+	world::{Event, World, TIMESTEP},
+};
+
+/// Seconds the camera takes to get to a new zoom
+const ZOOM_TIME: f32 = 0.2;
+/// How far out the camera is on the title, to show off the level
+const TITLE_ZOOM: f32 = 0.5;
+/// Seconds to hold the title's view after pressing play
+const INTRO_PAUSE: f32 = 0.2;
+/// Seconds to get from the title's view down to the player
+const INTRO_TIME: f32 = 0.8;
+const SNAPSHOT_PATH: &str = "saves/quick.txt";
+
+/// Everything the states share
+pub struct Game {
+	pub world: World,
+	pub camera: Camera,
+	pub assets: Assets,
+	pub hud: Hud,
+	pub input: ActionState,
+	pub animator: Option<Animator<Motion>>,
+	pub recording: Option<Recording>,
+	pub level_path: String,
+	/// The cell under the mouse, if the mouse is on the grid
+	pub cursor: Option<Size>,
+
+	/// Where the zoom is headed, the camera eases its way there
+	zoom: f32,
+	/// Where it started easing from
+	zoom_from: f32,
+	camera_tweens: Tweens<Camera>,
+	/// Half the screen, in units
+	half_screen: Vec2,
+}
+
+impl Game {
+	pub fn new(
+		world: World,
+		assets: Assets,
+		input: ActionState,
+		level_path: &str,
+		dimensions: (u32, u32),
+		pixels_per_unit: u32,
+	) -> Self {
+		let mut camera = Camera::new(dimensions, pixels_per_unit);
+		camera.position = world.player_position();
+		camera.bound_to(&world.grid);
+		let ppu = pixels_per_unit as f32;
+
+		Self {
+			world,
+			zoom: camera.zoom,
+			zoom_from: camera.zoom,
+			camera,
+			assets,
+			hud: Hud::new(dimensions, pixels_per_unit),
+			input,
+			animator: None,
+			recording: None,
+			level_path: level_path.to_owned(),
+			cursor: None,
+
+			camera_tweens: Tweens::default(),
+			half_screen: Vec2::new(
+				dimensions.0 as f32 / ppu / 2.0,
+				dimensions.1 as f32 / ppu / 2.0,
+			),
+		}
+	}
+
+	pub fn save_level(&self) {
+		if let Err(e) = level::save(&self.world.grid, &self.level_path) {
+			eprintln!("Couldn't save {}: {}", self.level_path, e);
+		}
+	}
+
+	pub fn save_snapshot(&mut self) {
+		match save::save(&self.world, &self.view(), &self.assets, SNAPSHOT_PATH) {
+			Ok(()) => {
+				self.hud.clear_error(SNAPSHOT_PATH);
+				self.hud.set("SNAPSHOT", "SAVED");
+			}
+			Err(e) => self.hud.set_error(SNAPSHOT_PATH, e),
+		}
+	}
+
+	pub fn load_snapshot(&mut self) {
+		let (world, view) = match save::load(SNAPSHOT_PATH, &self.assets) {
+			Ok(loaded) => loaded,
+			Err(e) => {
+				self.hud.set_error(SNAPSHOT_PATH, e);
+				return;
+			}
+		};
+		self.hud.clear_error(SNAPSHOT_PATH);
+		self.hud.set("SNAPSHOT", "LOADED");
+
+		// A replay starts from a fresh world, it can't jump to a snapshot
+		if self.recording.take().is_some() {
+			eprintln!("Loaded a snapshot, so stopped recording");
+		}
+
+		self.world = world;
+		self.camera.bound_to(&self.world.grid);
+		match view {
+			Some(view) => self.set_view(view),
+			None => self.camera.position = self.world.player_position(),
+		}
+	}
+
+	/// Everything that's saved with the world
+	fn view(&self) -> View {
+		View {
+			camera: self.camera.position,
+			zoom: self.camera.zoom,
+			shake: self.camera.shaking(),
+			zoom_to: self.zoom,
+			zooming: self
+				.camera_tweens
+				.playing()
+				.first()
+				.map(|tween| (self.zoom_from, tween.elapsed())),
+			animation: self
+				.animator
+				.as_ref()
+				.map(|animator| (animator.state(), animator.playhead())),
+		}
+	}
+
+	/// Pick up where `view` left off
+	fn set_view(&mut self, view: View) {
+		self.zoom = view.zoom_to;
+		self.camera_tweens.clear();
+		if let Some((from, elapsed)) = view.zooming {
+			self.ease_zoom(from);
+			self.camera_tweens.update(&mut self.camera, elapsed);
+		}
+
+		self.camera.position = view.camera;
+		self.camera.zoom = view.zoom;
+		self.camera.set_shaking(view.shake);
+
+		if let (Some(animator), Some((state, playhead))) = (self.animator.as_mut(), view.animation)
+		{
+			animator.set_playhead(state, playhead);
+		}
+	}
+
+	/// Ease the camera's zoom from `from` to wherever it's headed
+	fn ease_zoom(&mut self, from: f32) {
+		self.zoom_from = from;
+		self.camera_tweens.clear();
+		self.camera_tweens.play(Animation::tween(
+			from,
+			self.zoom,
+			ZOOM_TIME,
+			Ease::Out(Curve::Quad),
+			Camera::set_zoom,
+		));
+	}
+
+	/// Save and reload the level somewhere else from now on
+	pub fn set_level_path(&mut self, path: &str) {
+		// Errors are by path, so one for the old level would never clear
+		self.hud.clear_error(&self.level_path);
+		self.level_path = path.to_owned();
+	}
+
+	/// Swap in the level from disk, if it's changed, without moving anyone.
+	/// If it doesn't parse, the error's shown until it does.
+	pub fn reload_level(&mut self) {
+		let grid = match level::load(&self.level_path) {
+			Ok(grid) => grid,
+			Err(e) => {
+				self.hud.set_error(&self.level_path, e);
+				return;
+			}
+		};
+		self.hud.clear_error(&self.level_path);
+
+		// Saving from the editor looks like a change too
+		if level::serialize(&grid) == level::serialize(&self.world.grid) {
+			return;
+		}
+
+		if self.recording.take().is_some() {
+			eprintln!("Reloaded the level, so stopped recording");
+		}
+
+		self.world.set_grid(grid);
+		self.camera.bound_to(&self.world.grid);
+	}
+
+	/// Use `new` wherever `old` was, after a texture's been loaded again
+	pub fn replace_texture(&mut self, old: TextureId, new: TextureId) {
+		self.assets.replace_texture(old, new);
+
+		for (_, look) in self.world.entities.looks.iter_mut() {
+			look.sprite = look.sprite.replace_texture(old, new);
+		}
+	}
+
+	/// Use `atlas` after its manifest's changed. Regions are matched up by
+	/// name, since adding one can move the rest, and ones that are gone are
+	/// drawn pink.
+	pub fn replace_atlas(&mut self, atlas: Atlas, sheet: TextureId) {
+		let old = self.assets.atlas.take();
+		let remap = |sprite| match sprite {
+			Sprite::Region(id) => old
+				.as_ref()
+				.and_then(|(old, _)| old.name(id))
+				.and_then(|name| atlas.id(name))
+				.map(Sprite::Region)
+				.unwrap_or_else(|| MISSING.into()),
+			sprite => sprite,
+		};
+
+		self.assets.tileset.map(remap);
+		for (_, look) in self.world.entities.looks.iter_mut() {
+			look.sprite = remap(look.sprite);
+		}
+		if let Some(animator) = self.animator.as_mut() {
+			animator.map(remap);
+		}
+
+		self.assets.atlas = Some((atlas, sheet));
+	}
+
+	/// One fixed step of the state on top. The input's recorded even if the
+	/// world doesn't move, so what's pressed under the pause menu comes out
+	/// the same in the replay.
+	pub fn step(&mut self, states: &mut Stack<Game>) {
+		if let Some(recording) = self.recording.as_mut() {
+			recording.record(&self.input, self.cursor);
+		}
+
+		states.step(self);
+	}
+
+	/// Every state that's showing, and the HUD over all of them so broken
+	/// files show up even on the title
+	pub fn draw(&self, states: &Stack<Game>) -> Vec<Command> {
+		let mut commands = states.draw(self);
+		commands.extend(self.hud.draw(&self.world));
+		commands
+	}
+
+	/// Move the world, and everything following it, one step
+	fn step_world(&mut self) {
+		if self.input.pressed(Action::QuickSave) {
+			self.save_snapshot();
+		}
+		if self.input.pressed(Action::QuickLoad) {
+			self.load_snapshot();
+		}
+
+		let start = self.world.player_position();
+
+		let factor = if self.input.pressed(Action::ZoomIn) {
+			Some(1.25)
+		} else if self.input.pressed(Action::ZoomOut) {
+			Some(0.8)
+		} else {
+			None
+		};
+
+		if let Some(factor) = factor {
+			self.zoom = Camera::clamp_zoom(self.zoom * factor);
+			self.ease_zoom(self.camera.zoom);
+		}
+		self.camera_tweens.update(&mut self.camera, TIMESTEP);
+
+		if self.world.editor.enabled && self.input.pressed(Action::SaveLevel) {
+			self.save_level();
+		}
+
+		if let Some(recording) = self.recording.as_mut() {
+			recording.stepped();
+		}
+
+		let mut placed = false;
+		for event in self.world.step(&self.input, self.cursor) {
+			match event {
+				Event::TilePlaced(_) => {
+					self.camera.shake(0.3);
+					placed = true;
+				}
+			}
+		}
+
+		if let Some(animator) = self.animator.as_mut() {
+			let velocity = (self.world.player_position() - start) / TIMESTEP;
+			let placing = animator.state() == Motion::Place && !animator.finished();
+
+			if placed {
+				animator.set_state(Motion::Place);
+			} else if !placing {
+				animator.set_state(Motion::from_velocity(velocity));
+			}
+
+			for event in animator.update(TIMESTEP) {
+				if event == "step" {
+					let feet = self.world.player_position();
+					self.world.particles.burst(Emitter::dust(feet), 3);
+				}
+			}
+			self.world.entities.looks[self.world.player].sprite = animator.sprite();
+		}
+
+		self.camera.follow(self.world.player_position(), TIMESTEP);
+	}
+
+	fn draw_world(&self, commands: &mut Vec<Command>) {
+		commands.extend(self.world.draw(&self.camera, &self.assets, self.cursor));
+		commands.extend(debug::commands(&self.camera));
+	}
+
+	/// Where a menu goes so it's around the middle of the screen
+	fn menu_corner(&self) -> Vec2 {
+		Vec2::new(-1.5, self.half_screen.y / 2.0)
+	}
+}
+
+/// The menu we start on, over the level
+pub struct Title;
+
+impl Title {
+	/// Pull the camera back to look over the whole level
+	pub fn new(game: &mut Game) -> Self {
+		let (bottom_left, top_right) = game.world.grid.bounds();
+		game.camera.position = (bottom_left + top_right) / 2.0;
+		game.camera.set_zoom(TITLE_ZOOM);
+
+		Title
+	}
+}
+
+impl State<Game> for Title {
+	fn step(&mut self, game: &mut Game) -> Transition<Game> {
+		if game.input.pressed(Action::Pause) {
+			Transition::Quit
+		} else {
+			Transition::None
+		}
+	}
+
+	fn ui(&mut self, game: &mut Game, ui: &mut Ui) -> Transition<Game> {
+		let mut transition = Transition::None;
+
+		ui.panel("NOTSURE", game.menu_corner(), |ui| {
+			if ui.button("PLAY") {
+				transition = Transition::Replace(Box::new(Intro::new(game)));
+			}
+			if ui.button("QUIT") {
+				transition = Transition::Quit;
+			}
+		});
+
+		transition
+	}
+
+	fn draw(&self, game: &Game, commands: &mut Vec<Command>) {
+		commands.extend(game.world.draw(&game.camera, &game.assets, None));
+	}
+}
+
+/// The camera swooping down from the title to the player. Nothing moves
+/// until it gets there.
+pub struct Intro {
+	tweens: Tweens<Camera>,
+}
+
+impl Intro {
+	pub fn new(game: &Game) -> Self {
+		let ease = Ease::InOut(Curve::Cubic);
+		let swoop = Animation::parallel(vec![
+			Animation::tween(
+				game.camera.zoom,
+				game.zoom,
+				INTRO_TIME,
+				ease,
+				Camera::set_zoom,
+			),
+			Animation::tween(
+				game.camera.position,
+				game.world.player_position(),
+				INTRO_TIME,
+				ease,
+				|camera: &mut Camera, position| camera.position = position,
+			),
+		]);
+
+		let mut tweens = Tweens::default();
+		tweens.play(
+			Animation::sequence(vec![Animation::wait(INTRO_PAUSE), swoop])
+				.on_complete(|camera| camera.shake(0.2)),
+		);
+
+		Self { tweens }
+	}
+}
+
+impl State<Game> for Intro {
+	fn step(&mut self, game: &mut Game) -> Transition<Game> {
+		self.tweens.update(&mut game.camera, TIMESTEP);
+
+		if self.tweens.is_empty() {
+			Transition::Replace(Box::new(Playing))
+		} else {
+			Transition::None
+		}
+	}
+
+	fn draw(&self, game: &Game, commands: &mut Vec<Command>) {
+		game.draw_world(commands);
+	}
+}
+
+pub struct Playing;
+
+impl State<Game> for Playing {
+	fn step(&mut self, game: &mut Game) -> Transition<Game> {
+		if game.input.pressed(Action::Pause) {
+			return Transition::Push(Box::new(Paused));
+		}
+
+		game.step_world();
+
+		// The editor's toggled in the world so replays see it
+		if game.world.editor.enabled {
+			Transition::Push(Box::new(Editing::new(game)))
+		} else {
+			Transition::None
+		}
+	}
+
+	fn draw(&self, game: &Game, commands: &mut Vec<Command>) {
+		game.draw_world(commands);
+	}
+}
+
+/// Playing, with the editor's panel open
+pub struct Editing {
+	/// What's in the level field, it's only used once it's entered
+	path: String,
+}
+
+impl Editing {
+	pub fn new(game: &Game) -> Self {
+		Self {
+			path: game.level_path.clone(),
+		}
+	}
+}
+
+impl State<Game> for Editing {
+	fn step(&mut self, game: &mut Game) -> Transition<Game> {
+		if game.input.pressed(Action::Pause) {
+			return Transition::Push(Box::new(Paused));
+		}
+
+		game.step_world();
+
+		if game.world.editor.enabled {
+			Transition::None
+		} else {
+			Transition::Pop
+		}
+	}
+
+	fn ui(&mut self, game: &mut Game, ui: &mut Ui) -> Transition<Game> {
+		match game.world.editor.panel(ui, &mut self.path) {
+			Some(PanelAction::Save) => game.save_level(),
+			Some(PanelAction::UseLevel) => game.set_level_path(&self.path),
+			None => (),
+		}
+
+		Transition::None
+	}
+
+	fn draw(&self, game: &Game, commands: &mut Vec<Command>) {
+		game.draw_world(commands);
+	}
+}
+
+/// Stops whatever's under it and dims it
+pub struct Paused;
+
+impl State<Game> for Paused {
+	fn step(&mut self, game: &mut Game) -> Transition<Game> {
+		if game.input.pressed(Action::Pause) {
+			Transition::Pop
+		} else {
+			Transition::None
+		}
+	}
+
+	fn ui(&mut self, game: &mut Game, ui: &mut Ui) -> Transition<Game> {
+		let mut transition = Transition::None;
+
+		ui.panel("PAUSED", game.menu_corner(), |ui| {
+			if ui.button("RESUME") {
+				transition = Transition::Pop;
+			}
+			ui.row(|ui| {
+				if ui.button("SAVE") {
+					game.save_snapshot();
+				}
+				if ui.button("LOAD") {
+					game.load_snapshot();
+				}
+			});
+			ui.row(|ui| {
+				ui.toggle("HUD", &mut game.hud.visible);
+
+				let mut overlay = debug::enabled();
+				if ui.toggle("DEBUG", &mut overlay) {
+					debug::set_enabled(overlay);
+				}
+			});
+			if ui.button("QUIT") {
+				transition = Transition::Quit;
+			}
+		});
+
+		transition
+	}
+
+	fn draw(&self, game: &Game, commands: &mut Vec<Command>) {
+		commands.push(Command::Rect {
+			position: Vec2::ZERO,
+			size: game.half_screen * 2.0,
+			draw: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+		});
+	}
+
+	fn overlay(&self) -> bool {
+		true
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::collections::HashSet;
+
+	use smitten::{Color, Draw, TextureId, Vec2};
+
+	use super::{Game, Intro, Paused, Playing, Title, INTRO_PAUSE, INTRO_TIME};
+	use crate::{
+		animation::{Animator, Clip, Frame, Motion, Playback},
+		atlas::{Atlas, Sprite},
+		autotile::{Mode, Tileset},
+		debug,
+		input::{Action, ActionState, Bindings},
+		level,
+		render::Command,
+		render::{Assets, MISSING},
+		replay::Recording,
+		save,
+		state::Stack,
+		ui::{Ui, UiInput},
+		world::{World, TIMESTEP},
+	};
+
+	fn game() -> Game {
+		let assets = Assets {
+			atlas: None,
+			tileset: Tileset::new(Mode::FourBit, Color::rgb(0.6, 0.6, 0.7).into()),
+		};
+		let world = World::new(level::empty(), (0.0, 0.0));
+		let input = ActionState::new(Bindings::default());
+		Game::new(world, assets, input, "level.txt", (90, 90), 10)
+	}
+
+	fn step(game: &mut Game, stack: &mut Stack<Game>, down: &[Action]) {
+		game.input
+			.set_down(down.iter().copied().collect::<HashSet<_>>());
+		stack.step(game);
+	}
+
+	#[test]
+	fn intro_lands_on_the_player() {
+		let mut game = game();
+		let zoom = game.camera.zoom;
+		Title::new(&mut game);
+		assert!(game.camera.zoom < zoom);
+
+		let mut stack = Stack::new(Box::new(Intro::new(&game)));
+		let start = game.world.player_position();
+		let steps = ((INTRO_PAUSE + INTRO_TIME) / TIMESTEP) as usize;
+
+		// Held the whole way down, but nobody moves until the camera's there
+		for _ in 0..steps {
+			step(&mut game, &mut stack, &[Action::MoveRight]);
+		}
+		assert_eq!(game.world.player_position(), start);
+
+		for _ in 0..3 {
+			step(&mut game, &mut stack, &[Action::MoveRight]);
+		}
+		assert_eq!(game.camera.zoom, zoom);
+		assert!(game.world.player_position().x > start.x);
+	}
+
+	#[test]
+	fn atlas_reloads_match_regions_by_name() {
+		let mut game = game();
+		let atlas = |regions: &str| {
+			Atlas::parse(&format!(
+				"image = \"sheet.png\"\nsize = [32, 16]\n\n[regions]\n{}",
+				regions
+			))
+			.unwrap()
+		};
+
+		let old = atlas("player = [0, 0, 16, 16]\nhanger = [17, 0, 8, 8]\n");
+		game.assets.tileset.set(0, old.id("player").unwrap().into());
+		game.world.entities.looks[game.world.player].sprite = old.id("hanger").unwrap().into();
+		game.assets.atlas = Some((old, TextureId(1)));
+
+		// The hanger moves up to where the player was
+		let new = atlas("hanger = [0, 0, 8, 8]\n");
+		let hanger = new.id("hanger").unwrap();
+		game.replace_atlas(new, TextureId(1));
+
+		let look = game.world.entities.looks[game.world.player].sprite;
+		assert!(matches!(look, Sprite::Region(id) if id == hanger));
+		assert!(matches!(
+			game.assets.tileset.get(0),
+			Sprite::Draw(Draw::Color(c)) if c == MISSING
+		));
+	}
+
+	#[test]
+	fn errors_show_on_the_title() {
+		let mut game = game();
+		let stack = Stack::new(Box::new(Title::new(&mut game)));
+		game.hud.visible = false;
+
+		let before = game.draw(&stack).len();
+		game.hud.set_error("level.txt", "line 5: 'x' isn't a tile");
+		assert!(game.draw(&stack).len() > before);
+	}
+
+	/// Stands still, or walks with a step every other frame
+	fn walker() -> Animator<Motion> {
+		let frame = |event| Frame {
+			sprite: Color::WHITE.into(),
+			duration: 0.1,
+			event,
+		};
+		let mut animator =
+			Animator::new(Motion::Idle, Clip::new(vec![frame(None)], Playback::Loop));
+		animator.add(
+			Motion::Walk,
+			Clip::new(vec![frame(None), frame(None)], Playback::Loop).with_event(0, "step"),
+		);
+		animator
+	}
+
+	#[test]
+	fn footsteps_kick_up_dust() {
+		let mut game = game();
+		game.animator = Some(walker());
+
+		let mut stack = Stack::new(Box::new(Playing));
+		step(&mut game, &mut stack, &[]);
+		assert_eq!(game.world.particles.len(), 0);
+
+		step(&mut game, &mut stack, &[Action::MoveRight]);
+		assert!(game.world.particles.len() > 0);
+	}
+
+	#[test]
+	fn snapshots_keep_the_view() {
+		let mut game = game();
+		game.animator = Some(walker());
+		let mut stack = Stack::new(Box::new(Playing));
+
+		step(&mut game, &mut stack, &[Action::ZoomIn]);
+		for _ in 0..4 {
+			step(&mut game, &mut stack, &[Action::MoveRight]);
+		}
+		game.camera.shake(0.5);
+		step(&mut game, &mut stack, &[Action::MoveRight]);
+
+		// Halfway through zooming, shaking and walking
+		let view = game.view();
+		assert!(view.zooming.is_some());
+		assert!(view.shake.trauma > 0.0);
+		assert!(matches!(view.animation, Some((Motion::Walk, _))));
+
+		let string = save::serialize(&game.world, Some(&view), &game.assets);
+		let (world, loaded) = save::parse(&string, &game.assets).unwrap();
+		let mut copy = self::game();
+		copy.animator = Some(walker());
+		copy.world = world;
+		copy.set_view(loaded.unwrap());
+		assert_eq!(copy.view(), view);
+
+		let mut copy_stack = Stack::new(Box::new(Playing));
+		for _ in 0..20 {
+			step(&mut game, &mut stack, &[Action::MoveRight]);
+			step(&mut copy, &mut copy_stack, &[Action::MoveRight]);
+			assert_eq!(copy.view(), game.view());
+		}
+		assert_eq!(copy.world.particles.len(), game.world.particles.len());
+	}
+
+	#[test]
+	fn replays_see_what_was_pressed_while_paused() {
+		let mut game = game();
+		game.recording = Some(Recording::new(&game.world, &game.input.bindings));
+		let mut stack = Stack::new(Box::new(Playing));
+		let start = game.world.player_position();
+
+		// Right goes down after left, both under the pause menu
+		let steps: &[&[Action]] = &[
+			&[Action::Pause],
+			&[Action::MoveLeft],
+			&[Action::MoveLeft, Action::MoveRight],
+			&[Action::MoveLeft, Action::MoveRight, Action::Pause],
+		];
+		for down in steps
+			.iter()
+			.copied()
+			.chain([&[Action::MoveLeft, Action::MoveRight][..]; 5])
+		{
+			game.input
+				.set_down(down.iter().copied().collect::<HashSet<_>>());
+			game.step(&mut stack);
+		}
+		assert!(game.world.player_position().x > start.x);
+
+		let mut recording = game.recording.take().unwrap();
+		recording.finish(&game.world);
+		assert!(recording.check().is_ok());
+	}
+
+	#[test]
+	fn pausing_stops_the_world() {
+		let mut game = game();
+		let mut stack = Stack::new(Box::new(Playing));
+
+		step(&mut game, &mut stack, &[Action::Pause]);
+		assert_eq!(stack.len(), 2);
+
+		// Nothing moves under the pause menu
+		let start = game.world.player_position();
+		step(&mut game, &mut stack, &[Action::MoveRight]);
+		assert_eq!(game.world.player_position(), start);
+
+		step(&mut game, &mut stack, &[Action::Pause]);
+		assert_eq!(stack.len(), 1);
+		step(&mut game, &mut stack, &[Action::MoveRight]);
+		assert!(game.world.player_position().x > start.x);
+	}
+
+	#[test]
+	fn pause_menu_toggles_the_hud_and_overlay() {
+		let mut game = game();
+		let mut stack = Stack::new(Box::new(Paused));
+		let mut ui = Ui::new((90, 90), 10);
+		let mut frame = |game: &mut Game, mouse: Option<(Vec2, bool)>| {
+			ui.begin(UiInput {
+				mouse: mouse.map(|(p, _)| p),
+				mouse_down: mouse.is_some_and(|(_, down)| down),
+				..UiInput::default()
+			});
+			stack.ui(game, &mut ui);
+			ui.end()
+		};
+		let find = |drawn: &[Command], label: &str| {
+			drawn
+				.iter()
+				.find_map(|c| match c {
+					Command::Text { position, text, .. } if text == label => {
+						Some(*position + Vec2::new(0.1, -0.2))
+					}
+					_ => None,
+				})
+				.unwrap()
+		};
+
+		let drawn = frame(&mut game, None);
+		let (hud, overlay) = (find(&drawn, "HUD"), find(&drawn, "DEBUG"));
+		assert!(game.hud.visible);
+		assert!(!debug::enabled());
+
+		for at in [hud, overlay] {
+			frame(&mut game, Some((at, true)));
+			frame(&mut game, Some((at, false)));
+		}
+		assert!(!game.hud.visible);
+		assert!(debug::enabled());
+		debug::set_enabled(false);
+	}
+}
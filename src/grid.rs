@@ -0,0 +1,230 @@
+use smitten::{Color, SignedDistance, Vec2};
+
+use crate::autotile;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Size {
+	pub width: u32,
+	pub height: u32,
+}
+
+impl Size {
+	pub fn stride(&self) -> usize {
+		self.width as usize * self.height as usize
+	}
+
+	pub fn half(&self) -> Vec2 {
+		Vec2::new(self.width as f32 / 2.0, self.height as f32 / 2.0)
+	}
+}
+
+impl From<(u32, u32)> for Size {
+	fn from(t: (u32, u32)) -> Self {
+		Size {
+			width: t.0,
+			height: t.1,
+		}
+	}
+}
+
+impl From<Size> for Vec2 {
+	fn from(s: Size) -> Self {
+		Vec2 {
+			x: s.width as f32,
+			y: s.width as f32,
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Grid {
+	pub size: Size,
+	pub position: Vec2,
+
+	pub side_length: f32,
+	/// The cells each moving platform goes back and forth between. Only the
+	/// world does anything with these, they're here so they're saved with
+	/// the level.
+	pub platforms: Vec<Vec<Size>>,
+	grid: Vec<Option<Tile>>,
+	// Which neighbours of each cell are solid. See the autotile module for
+	// the bit layout. Kept up to date by set_tile.
+	neighbours: Vec<u8>,
+}
+
+impl Grid {
+	pub fn new<S: Into<Size>, P: Into<Vec2>>(size: S, position: P, side_length: f32) -> Self {
+		let size = size.into();
+
+		Self {
+			size,
+			position: position.into(),
+			side_length,
+			platforms: vec![],
+			grid: vec![None; size.stride()],
+			neighbours: vec![0; size.stride()],
+		}
+	}
+
+	pub fn gridlines(&self) -> Vec<SignedDistance> {
+		let mut lines = vec![];
+
+		let half_size = self.size.half();
+
+		let x_start = self.position.x - half_size.x;
+		let x_end = self.position.x + half_size.x;
+
+		let y_start = self.position.y - half_size.y;
+		let y_end = self.position.y + half_size.y;
+
+		for x in 0..=self.size.width {
+			let x_position = (x_start + x as f32) * self.side_length;
+
+			lines.push(SignedDistance::LineSegment {
+				start: Vec2::new(x_position, y_start),
+				end: Vec2::new(x_position, y_end),
+				thickness: 2,
+				color: Color::rgb(0.5, 0.3, 0.0),
+			});
+		}
+
+		for y in 0..=self.size.height {
+			let y_position = (y_start + y as f32) * self.side_length;
+
+			lines.push(SignedDistance::LineSegment {
+				start: Vec2::new(x_start, y_position),
+				end: Vec2::new(x_end, y_position),
+				thickness: 2,
+				color: Color::rgb(0.5, 0.3, 0.0),
+			});
+		}
+
+		lines
+	}
+
+	/// Bottom left and top right corners of the grid in the world
+	pub fn bounds(&self) -> (Vec2, Vec2) {
+		let half_size = self.size.half() * self.side_length;
+
+		(self.position - half_size, self.position + half_size)
+	}
+
+	pub fn coordinate_center(&self, x: u32, y: u32) -> Option<Vec2> {
+		if x >= self.size.width && y >= self.size.height {
+			return None;
+		}
+
+		let half_size = self.size.half();
+		Some(Vec2 {
+			x: (x as f32 - half_size.x) * self.side_length
+				+ (self.side_length / 2.0)
+				+ self.position.x,
+			y: (y as f32 - half_size.y) * self.side_length
+				+ (self.side_length / 2.0)
+				+ self.position.y,
+		})
+	}
+
+	//TODO: gen- function name?
+	pub fn get_coords(&self, loc: Vec2) -> Option<Size> {
+		let offset_to_center = loc - self.position;
+
+		//TODO: gen- Why uh, why is it half_size PLUS offset?
+		let half_size = self.size.half();
+		let coords = (half_size + offset_to_center) / self.side_length;
+
+		if coords.x < 0.0
+			|| coords.y < 0.0
+			|| coords.x >= self.size.width as f32
+			|| coords.y >= self.size.height as f32
+		{
+			None
+		} else {
+			Some(Size {
+				width: coords.x as u32,
+				height: coords.y as u32,
+			})
+		}
+	}
+
+	pub fn set_tile(&mut self, coords: Size, tile: Option<Tile>) {
+		//TODO: gen- check coordinates valid
+		self.grid[coords.height as usize * self.size.width as usize + coords.width as usize] = tile;
+
+		// We're only a neighbour to the cells around us, so those are the
+		// only masks that can change.
+		let solid = matches!(tile, Some(Tile::Solid));
+		for (dx, dy, bit) in autotile::OFFSETS {
+			let x = coords.width as i32 - dx;
+			let y = coords.height as i32 - dy;
+
+			if self.contains(x, y) {
+				let idx = y as usize * self.size.width as usize + x as usize;
+
+				if solid {
+					self.neighbours[idx] |= bit;
+				} else {
+					self.neighbours[idx] &= !bit;
+				}
+			}
+		}
+	}
+
+	/// A mask of which neighbouring cells are solid, for autotiling.
+	pub fn neighbours(&self, coords: Size) -> u8 {
+		if !self.contains(coords.width as i32, coords.height as i32) {
+			return 0;
+		}
+
+		self.neighbours[coords.height as usize * self.size.width as usize + coords.width as usize]
+	}
+
+	/// Get the tile at the given coordinates. Anything outside of the grid
+	/// is treated as empty.
+	pub fn tile(&self, coords: Size) -> Option<&Tile> {
+		if coords.width >= self.size.width || coords.height >= self.size.height {
+			return None;
+		}
+
+		self.grid[coords.height as usize * self.size.width as usize + coords.width as usize]
+			.as_ref()
+	}
+
+	/// Whether or not the coordinates are inside the grid. Takes signed
+	/// coordinates so callers walking outward from a cell don't have to
+	/// check for underflow themselves.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		x >= 0 && y >= 0 && (x as u32) < self.size.width && (y as u32) < self.size.height
+	}
+
+	pub fn is_solid(&self, coords: Size) -> bool {
+		matches!(self.tile(coords), Some(Tile::Solid))
+	}
+
+	pub fn tiles_and_position(&self) -> Vec<(Size, Option<&Tile>, Vec2)> {
+		let mut ret = vec![];
+
+		for (idx, tile) in self.grid.iter().enumerate() {
+			let y = idx / self.size.width as usize;
+			let x = idx % self.size.width as usize;
+
+			ret.push((
+				Size {
+					width: x as u32,
+					height: y as u32,
+				},
+				tile.as_ref(),
+				self.coordinate_center(x as u32, y as u32).unwrap(),
+			))
+		}
+
+		ret
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tile {
+	Solid,
+	/// Drawn behind everything, but nothing bumps into it or is hidden by it
+	Backdrop,
+}
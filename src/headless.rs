@@ -0,0 +1,254 @@
+use std::{collections::HashSet, fmt, fs, io, path::Path};
+
+use smitten::{Color, Vec2};
+
+use crate::{
+	atlas::Sprite,
+	autotile::{Mode, Tileset},
+	camera::Camera,
+	grid::{Grid, Size},
+	input::{Action, ActionState, Bindings},
+	level::{self, LevelError},
+	render::{Assets, Command},
+	world::World,
+};
+
+/// How close a position has to be to pass an `expect position`
+const TOLERANCE: f32 = 0.001;
+
+/// Run a script against a world with no window. Scripts are a list of
+/// commands, one per line:
+///
+/// ```text
+/// # Walk right and drop a tile
+/// level ../levels/level.txt
+/// start -3 -3
+/// hold 20 MoveRight
+/// hold 1 PlaceTile
+/// wait 10
+/// cursor 2 3
+/// expect position -1.5 -3
+/// expect solid 1 1
+/// expect empty 2 2
+/// ```
+///
+/// `level` and `start` have to come before anything that steps the world,
+/// and the level path is relative to `dir`. Without a level we use
+/// `level::empty`. `hold` steps with the actions held down and `wait` steps
+/// with nothing held. `cursor` puts the mouse over a cell for every step
+/// after it, or takes it off the grid with `cursor none`.
+pub fn run(script: &str, dir: &Path) -> Result<World, ScriptError> {
+	let mut runner = Runner {
+		grid: None,
+		start: Vec2::new(-3.0, -3.0),
+		world: None,
+		input: ActionState::new(Bindings::default()),
+		cursor: None,
+	};
+
+	for (idx, line) in script.lines().enumerate() {
+		let line_number = idx + 1;
+		let line = line.split('#').next().unwrap_or_default();
+		let words: Vec<&str> = line.split_whitespace().collect();
+
+		if !words.is_empty() {
+			runner.command(&words, dir, line_number)?;
+		}
+	}
+
+	Ok(runner.into_world())
+}
+
+/// Run a script file
+pub fn run_file<P: AsRef<Path>>(path: P) -> Result<World, ScriptError> {
+	let path = path.as_ref();
+	let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+	run(&fs::read_to_string(path)?, dir)
+}
+
+/// Draw the world like the game would, looking at the player. There aren't
+/// any textures without a window, so tiles are plain squares.
+pub fn draw(world: &World, dimensions: (u32, u32), pixels_per_unit: u32) -> Vec<Command> {
+	let mut camera = Camera::new(dimensions, pixels_per_unit);
+	camera.position = world.player_position();
+
+	let assets = Assets {
+		atlas: None,
+		tileset: Tileset::new(Mode::FourBit, Sprite::from(Color::rgb(0.6, 0.6, 0.7))),
+	};
+
+	world.draw(&camera, &assets, None)
+}
+
+struct Runner {
+	grid: Option<Grid>,
+	start: Vec2,
+	world: Option<World>,
+
+	input: ActionState,
+	cursor: Option<Size>,
+}
+
+impl Runner {
+	fn command(&mut self, words: &[&str], dir: &Path, line: usize) -> Result<(), ScriptError> {
+		match words {
+			["level", path] if self.world.is_none() => {
+				self.grid = Some(level::load(dir.join(path))?);
+			}
+			["start", x, y] if self.world.is_none() => self.start = parse_position(x, y, line)?,
+			["hold", steps, actions @ ..] => {
+				let down = actions
+					.iter()
+					.map(|a| Action::from_name(a).ok_or(ScriptError::UnknownAction(line)))
+					.collect::<Result<HashSet<Action>, _>>()?;
+
+				self.step(parse(steps, line)?, down);
+			}
+			["wait", steps] => self.step(parse(steps, line)?, HashSet::new()),
+			["cursor", "none"] => self.cursor = None,
+			["cursor", x, y] => {
+				self.cursor = Some(Size {
+					width: parse(x, line)?,
+					height: parse(y, line)?,
+				})
+			}
+			["expect", "position", x, y] => {
+				let expected = parse_position(x, y, line)?;
+				let actual = self.world().player_position();
+
+				if (actual.x - expected.x).abs() > TOLERANCE
+					|| (actual.y - expected.y).abs() > TOLERANCE
+				{
+					return Err(ScriptError::Failed {
+						line,
+						message: format!(
+							"expected to be at {} {} but was at {} {}",
+							expected.x, expected.y, actual.x, actual.y
+						),
+					});
+				}
+			}
+			["expect", which @ ("solid" | "empty"), x, y] => {
+				let coords = Size {
+					width: parse(x, line)?,
+					height: parse(y, line)?,
+				};
+				let solid = self.world().grid.is_solid(coords);
+
+				if solid != (*which == "solid") {
+					return Err(ScriptError::Failed {
+						line,
+						message: format!("expected {} {} to be {}", x, y, which),
+					});
+				}
+			}
+			_ => return Err(ScriptError::Syntax(line)),
+		}
+
+		Ok(())
+	}
+
+	/// The world, made the first time something needs it
+	fn world(&mut self) -> &mut World {
+		let grid = &mut self.grid;
+		let start = self.start;
+
+		self.world
+			.get_or_insert_with(|| World::new(grid.take().unwrap_or_else(level::empty), start))
+	}
+
+	fn step(&mut self, steps: u32, down: HashSet<Action>) {
+		let cursor = self.cursor;
+		self.world();
+
+		if let Some(world) = self.world.as_mut() {
+			for _ in 0..steps {
+				self.input.set_down(down.clone());
+				world.step(&self.input, cursor);
+			}
+		}
+	}
+
+	fn into_world(mut self) -> World {
+		self.world();
+		self.world.unwrap()
+	}
+}
+
+fn parse<T: std::str::FromStr>(word: &str, line: usize) -> Result<T, ScriptError> {
+	word.parse().map_err(|_| ScriptError::Syntax(line))
+}
+
+fn parse_position(x: &str, y: &str, line: usize) -> Result<Vec2, ScriptError> {
+	Ok(Vec2::new(parse(x, line)?, parse(y, line)?))
+}
+
+#[derive(Debug)]
+pub enum ScriptError {
+	Io(io::Error),
+	Level(LevelError),
+	Syntax(usize),
+	UnknownAction(usize),
+	Failed { line: usize, message: String },
+}
+
+impl From<io::Error> for ScriptError {
+	fn from(e: io::Error) -> Self {
+		ScriptError::Io(e)
+	}
+}
+
+impl From<LevelError> for ScriptError {
+	fn from(e: LevelError) -> Self {
+		ScriptError::Level(e)
+	}
+}
+
+impl fmt::Display for ScriptError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ScriptError::Io(e) => write!(f, "couldn't read script: {}", e),
+			ScriptError::Level(e) => write!(f, "bad level: {}", e),
+			ScriptError::Syntax(line) => write!(f, "line {}: unknown command", line),
+			ScriptError::UnknownAction(line) => write!(f, "line {}: unknown action", line),
+			ScriptError::Failed { line, message } => write!(f, "line {}: {}", line, message),
+		}
+	}
+}
+
+impl std::error::Error for ScriptError {}
+
+#[cfg(test)]
+mod test {
+	use std::{fs, path::Path};
+
+	use super::{run, run_file, ScriptError};
+
+	#[test]
+	fn failures_point_at_the_line() {
+		let script = "start 0 0\nhold 10 MoveRight\nexpect position 5 0\n";
+
+		match run(script, Path::new(".")) {
+			Err(ScriptError::Failed { line, .. }) => assert_eq!(line, 3),
+			other => panic!(
+				"expected a failure, got {:?}",
+				other.map(|w| w.player_position())
+			),
+		}
+	}
+
+	/// Every script in `scripts/` is an integration test
+	#[test]
+	fn scripts_pass() {
+		let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts");
+
+		for entry in fs::read_dir(dir).unwrap() {
+			let path = entry.unwrap().path();
+
+			if let Err(e) = run_file(&path) {
+				panic!("{}: {}", path.display(), e);
+			}
+		}
+	}
+}
@@ -0,0 +1,233 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::grid::{Grid, Size, Tile};
+
+/// A single tile changing
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Edit {
+	coords: Size,
+	before: Option<Tile>,
+	after: Option<Tile>,
+}
+
+/// Edits that are undone and redone together
+#[derive(Clone, Debug, Default)]
+struct Command {
+	edits: Vec<Edit>,
+	/// Where each cell's edit is in `edits`, so big fills don't have to
+	/// search through everything they've done so far
+	cells: HashMap<Size, usize>,
+}
+
+impl Command {
+	fn single(edit: Edit) -> Self {
+		let mut command = Self::default();
+		command.record(edit);
+		command
+	}
+
+	fn record(&mut self, edit: Edit) {
+		// If a cell is changed twice we only want the very first before and
+		// the very last after, otherwise undo would stop half way.
+		match self.cells.get(&edit.coords) {
+			Some(idx) => self.edits[*idx].after = edit.after,
+			None => {
+				self.cells.insert(edit.coords, self.edits.len());
+				self.edits.push(edit);
+			}
+		}
+	}
+}
+
+/// Every change to a grid should go through here so it can be undone. Single
+/// changes are their own command, but anything between `begin_group` and
+/// `end_group` is undone as a whole.
+pub struct History {
+	limit: usize,
+
+	undo: VecDeque<Command>,
+	redo: Vec<Command>,
+	group: Option<Command>,
+}
+
+impl History {
+	/// Create a history that remembers at most `limit` commands. There has
+	/// to be room for at least one.
+	pub fn new(limit: usize) -> Self {
+		assert!(limit > 0, "a history has to remember something");
+
+		Self {
+			limit,
+			undo: VecDeque::new(),
+			redo: vec![],
+			group: None,
+		}
+	}
+
+	pub fn set_tile(&mut self, grid: &mut Grid, coords: Size, tile: Option<Tile>) {
+		if !grid.contains(coords.width as i32, coords.height as i32) {
+			return;
+		}
+
+		let before = grid.tile(coords).copied();
+		if before == tile {
+			return;
+		}
+
+		grid.set_tile(coords, tile);
+
+		let edit = Edit {
+			coords,
+			before,
+			after: tile,
+		};
+
+		match self.group.as_mut() {
+			Some(group) => group.record(edit),
+			None => self.push(Command::single(edit)),
+		}
+	}
+
+	/// Start collecting edits into one command. Starting a group while one is
+	/// already open closes the old one first.
+	pub fn begin_group(&mut self) {
+		self.end_group();
+		self.group = Some(Command::default());
+	}
+
+	pub fn end_group(&mut self) {
+		if let Some(group) = self.group.take() {
+			self.push(group);
+		}
+	}
+
+	/// Undo the last command. Returns false if there was nothing to undo.
+	pub fn undo(&mut self, grid: &mut Grid) -> bool {
+		self.end_group();
+
+		match self.undo.pop_back() {
+			Some(command) => {
+				for edit in command.edits.iter().rev() {
+					grid.set_tile(edit.coords, edit.before);
+				}
+
+				self.redo.push(command);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Redo the last undone command. Returns false if there was nothing to redo.
+	pub fn redo(&mut self, grid: &mut Grid) -> bool {
+		self.end_group();
+
+		match self.redo.pop() {
+			Some(command) => {
+				for edit in &command.edits {
+					grid.set_tile(edit.coords, edit.after);
+				}
+
+				self.undo.push_back(command);
+				true
+			}
+			None => false,
+		}
+	}
+
+	fn push(&mut self, command: Command) {
+		// Groups where nothing actually changed aren't worth undoing
+		if command.edits.is_empty() {
+			return;
+		}
+
+		if self.undo.len() == self.limit {
+			self.undo.pop_front();
+		}
+
+		self.undo.push_back(command);
+		self.redo.clear();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::History;
+	use crate::grid::{Grid, Size, Tile};
+
+	fn size(x: u32, y: u32) -> Size {
+		Size {
+			width: x,
+			height: y,
+		}
+	}
+
+	#[test]
+	fn undo_redo_single() {
+		let mut grid = Grid::new((3, 3), (0.0, 0.0), 1.0);
+		let mut history = History::new(8);
+
+		history.set_tile(&mut grid, size(1, 1), Some(Tile::Solid));
+		assert!(grid.is_solid(size(1, 1)));
+
+		assert!(history.undo(&mut grid));
+		assert!(!grid.is_solid(size(1, 1)));
+		assert!(!history.undo(&mut grid));
+
+		assert!(history.redo(&mut grid));
+		assert!(grid.is_solid(size(1, 1)));
+	}
+
+	#[test]
+	fn groups_undo_together() {
+		let mut grid = Grid::new((3, 3), (0.0, 0.0), 1.0);
+		let mut history = History::new(8);
+
+		history.begin_group();
+		history.set_tile(&mut grid, size(0, 0), Some(Tile::Solid));
+		history.set_tile(&mut grid, size(1, 0), Some(Tile::Solid));
+		// Touching the same cell twice in a group still undoes to the start
+		history.set_tile(&mut grid, size(0, 0), None);
+		history.set_tile(&mut grid, size(0, 0), Some(Tile::Solid));
+		history.end_group();
+
+		history.undo(&mut grid);
+		assert!(!grid.is_solid(size(0, 0)));
+		assert!(!grid.is_solid(size(1, 0)));
+	}
+
+	#[test]
+	fn history_is_bounded() {
+		let mut grid = Grid::new((3, 1), (0.0, 0.0), 1.0);
+		let mut history = History::new(2);
+
+		for x in 0..3 {
+			history.set_tile(&mut grid, size(x, 0), Some(Tile::Solid));
+		}
+
+		assert!(history.undo(&mut grid));
+		assert!(history.undo(&mut grid));
+		assert!(!history.undo(&mut grid));
+
+		// The first edit fell off the end and stays
+		assert!(grid.is_solid(size(0, 0)));
+	}
+
+	#[test]
+	fn new_edit_clears_redo() {
+		let mut grid = Grid::new((3, 1), (0.0, 0.0), 1.0);
+		let mut history = History::new(8);
+
+		history.set_tile(&mut grid, size(0, 0), Some(Tile::Solid));
+		history.undo(&mut grid);
+		history.set_tile(&mut grid, size(1, 0), Some(Tile::Solid));
+
+		assert!(!history.redo(&mut grid));
+	}
+
+	#[test]
+	#[should_panic]
+	fn no_room_is_a_mistake() {
+		History::new(0);
+	}
+}
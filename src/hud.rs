@@ -0,0 +1,212 @@
+use std::fmt::Display;
+
+use smitten::{Color, Vec2};
+
+use crate::{font, render::Command, world::World};
+
+/// Height of a line of text, in units
+const TEXT_SIZE: f32 = 0.5;
+/// Space between the edge of the screen, the panel and the text, in units
+const MARGIN: f32 = 0.2;
+/// How much of every new frame time goes into the average
+const FPS_SMOOTHING: f32 = 0.05;
+
+// Color::rgba can't be used in a const
+const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+	Color { r, g, b, a }
+}
+
+const PANEL: Color = rgba(0.0, 0.0, 0.0, 0.5);
+const ERROR_PANEL: Color = rgba(0.3, 0.0, 0.0, 0.8);
+const ERROR_TEXT: Color = rgba(1.0, 0.6, 0.5, 1.0);
+
+/// Text drawn over the top left corner of the screen. It doesn't move with the
+/// camera or zoom with it. Errors go along the bottom, and are shown even
+/// when the rest is hidden.
+pub struct Hud {
+	pub visible: bool,
+
+	/// Half the screen, in units
+	half_screen: Vec2,
+	/// Frame time averaged over the last little while, so it's readable
+	frame_time: f32,
+	values: Vec<(String, String)>,
+	/// What went wrong, and where, like a level that doesn't parse
+	errors: Vec<(String, String)>,
+}
+
+impl Hud {
+	pub fn new(dimensions: (u32, u32), pixels_per_unit: u32) -> Self {
+		let ppu = pixels_per_unit as f32;
+
+		Self {
+			visible: true,
+			half_screen: Vec2::new(
+				dimensions.0 as f32 / ppu / 2.0,
+				dimensions.1 as f32 / ppu / 2.0,
+			),
+			frame_time: 0.0,
+			values: vec![],
+			errors: vec![],
+		}
+	}
+
+	/// Count a frame that took `dt` seconds
+	pub fn frame(&mut self, dt: f32) {
+		if self.frame_time == 0.0 {
+			self.frame_time = dt;
+		} else {
+			self.frame_time += (dt - self.frame_time) * FPS_SMOOTHING;
+		}
+	}
+
+	pub fn fps(&self) -> f32 {
+		if self.frame_time > 0.0 {
+			1.0 / self.frame_time
+		} else {
+			0.0
+		}
+	}
+
+	/// Show `value` next to `name` until it's set again
+	pub fn set<V: Display>(&mut self, name: &str, value: V) {
+		let value = value.to_string();
+
+		match self.values.iter_mut().find(|(n, _)| n == name) {
+			Some((_, v)) => *v = value,
+			None => self.values.push((name.to_owned(), value)),
+		}
+	}
+
+	/// Show `error` until `source` is fixed, replacing whatever was wrong with
+	/// it before
+	pub fn set_error<E: Display>(&mut self, source: &str, error: E) {
+		let error = error.to_string();
+
+		match self.errors.iter_mut().find(|(s, _)| s == source) {
+			Some((_, e)) => *e = error,
+			None => self.errors.push((source.to_owned(), error)),
+		}
+	}
+
+	pub fn clear_error(&mut self, source: &str) {
+		self.errors.retain(|(s, _)| s != source);
+	}
+
+	/// Everything we'd show, a line each
+	pub fn lines(&self, world: &World) -> Vec<String> {
+		let mut lines = vec![format!("FPS {:.0}", self.fps())];
+
+		if let Some(player) = world.entities.thing(world.player) {
+			let center = player.center;
+			lines.push(format!("POS {:.2} {:.2}", center.x, center.y));
+
+			lines.push(match world.grid.get_coords(center) {
+				Some(cell) => format!("CELL {} {}", cell.width, cell.height),
+				None => "CELL -".to_owned(),
+			});
+		}
+
+		for (name, value) in &self.values {
+			lines.push(format!("{} {}", name, value));
+		}
+
+		lines
+	}
+
+	pub fn draw(&self, world: &World) -> Vec<Command> {
+		let mut commands = vec![];
+
+		if self.visible {
+			let text = self.lines(world).join("\n");
+			let corner = Vec2::new(-self.half_screen.x + MARGIN, self.half_screen.y - MARGIN);
+			commands.extend(panel(corner, text, PANEL, Color::WHITE));
+		}
+
+		if !self.errors.is_empty() {
+			let lines: Vec<String> = self
+				.errors
+				.iter()
+				.map(|(source, error)| format!("{}: {}", source, error))
+				.collect();
+			let text = lines.join("\n");
+
+			let height = font::height(&text, TEXT_SIZE) + MARGIN * 2.0;
+			let corner = Vec2::new(
+				-self.half_screen.x + MARGIN,
+				-self.half_screen.y + MARGIN + height,
+			);
+			commands.extend(panel(corner, text, ERROR_PANEL, ERROR_TEXT));
+		}
+
+		commands
+	}
+}
+
+/// `text` on a box that fits it, hanging from its top left `corner`
+fn panel(corner: Vec2, text: String, background: Color, color: Color) -> [Command; 2] {
+	let size = Vec2::new(
+		font::width(&text, TEXT_SIZE) + MARGIN * 2.0,
+		font::height(&text, TEXT_SIZE) + MARGIN * 2.0,
+	);
+
+	[
+		Command::Rect {
+			position: corner + Vec2::new(size.x / 2.0, -size.y / 2.0),
+			size,
+			draw: background.into(),
+		},
+		Command::Text {
+			position: corner + Vec2::new(MARGIN, -MARGIN),
+			size: TEXT_SIZE,
+			text,
+			color,
+		},
+	]
+}
+
+#[cfg(test)]
+mod test {
+	use super::Hud;
+	use crate::{canvas::Canvas, level, render::Renderer, world::World};
+
+	#[test]
+	fn shows_where_the_player_is() {
+		let world = World::new(level::empty(), (0.6, -1.2));
+		let mut hud = Hud::new((120, 90), 16);
+		hud.frame(0.02);
+		hud.set("zoom", 1.5);
+		hud.set("zoom", 2);
+
+		assert_eq!(
+			hud.lines(&world),
+			vec!["FPS 50", "POS 0.60 -1.20", "CELL 2 0", "zoom 2"]
+		);
+
+		// The panel's dark, the text on it isn't
+		let mut canvas = Canvas::new((120, 90), 16);
+		canvas.clear();
+		canvas.draw_all(&hud.draw(&world));
+		let brightest = canvas.image.pixels().map(|p| p.0[0]).max().unwrap();
+		assert_eq!(brightest, 255);
+
+		// Errors stay up with everything else hidden, until they're fixed
+		hud.visible = false;
+		hud.set_error("level.txt", "line 5: 'x' isn't a tile");
+		assert_eq!(hud.draw(&world).len(), 2);
+		hud.clear_error("level.txt");
+		assert!(hud.draw(&world).is_empty());
+	}
+
+	#[test]
+	fn no_cell_past_the_edge() {
+		let hud = Hud::new((120, 90), 16);
+		let cell = |x, y| hud.lines(&World::new(level::empty(), (x, y)))[2].clone();
+
+		// The grid goes from -1.5 to 3.5 both ways
+		assert_eq!(cell(3.49, 3.49), "CELL 4 4");
+		assert_eq!(cell(3.5, 0.0), "CELL -");
+		assert_eq!(cell(0.0, 3.5), "CELL -");
+		assert_eq!(cell(-1.5, -1.5), "CELL 0 0");
+	}
+}
@@ -0,0 +1,598 @@
+use std::{
+	collections::{HashMap, HashSet},
+	fmt, fs, io,
+	path::Path,
+};
+
+use smitten::{Key, MouseButton, Vec2};
+
+/// Analog values smaller than this are treated as zero. Sticks rarely rest at
+/// exactly the center.
+const ANALOG_DEAD_ZONE: f32 = 0.15;
+
+/// Declares the action enum along with the names it's loaded by, so adding
+/// an action is one line
+macro_rules! actions {
+	($(#[$meta:meta])* $vis:vis enum $name:ident { $($action:ident,)* }) => {
+		$(#[$meta])*
+		$vis enum $name {
+			$($action,)*
+		}
+
+		const ACTIONS: &[(&str, $name)] = &[$((stringify!($action), $name::$action)),*];
+	};
+}
+
+actions! {
+	/// Something the player can do. Keys and buttons are bound to these so the
+	/// game never has to care which key was actually pressed.
+	#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+	pub enum Action {
+		MoveUp,
+		MoveDown,
+		MoveLeft,
+		MoveRight,
+		PlaceTile,
+		Grab,
+		Pause,
+		Quit,
+
+		Undo,
+		Redo,
+		ZoomIn,
+		ZoomOut,
+		ToggleDebug,
+		ToggleHud,
+		QuickSave,
+		QuickLoad,
+
+		ToggleEditor,
+		EditorPlace,
+		EditorErase,
+		ToolBrush,
+		ToolRectangle,
+		ToolFill,
+		BrushGrow,
+		BrushShrink,
+		PaletteNext,
+		PalettePrevious,
+		SaveLevel,
+	}
+}
+
+impl Action {
+	pub fn name(&self) -> &'static str {
+		ACTIONS
+			.iter()
+			.find(|(_, a)| a == self)
+			.map(|(n, _)| *n)
+			.unwrap()
+	}
+
+	pub fn from_name(name: &str) -> Option<Action> {
+		ACTIONS.iter().find(|(n, _)| *n == name).map(|(_, a)| *a)
+	}
+}
+
+/// A pair of actions that pull in opposite directions
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Axis {
+	Horizontal,
+	Vertical,
+}
+
+const AXES: &[(&str, Axis)] = &[
+	("Horizontal", Axis::Horizontal),
+	("Vertical", Axis::Vertical),
+];
+
+impl Axis {
+	pub fn all() -> impl Iterator<Item = Axis> {
+		AXES.iter().map(|(_, a)| *a)
+	}
+
+	pub fn name(&self) -> &'static str {
+		AXES.iter()
+			.find(|(_, a)| a == self)
+			.map(|(n, _)| *n)
+			.unwrap()
+	}
+
+	pub fn from_name(name: &str) -> Option<Axis> {
+		AXES.iter().find(|(n, _)| *n == name).map(|(_, a)| *a)
+	}
+}
+
+/// A physical thing that can be held down
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+	Key(Key),
+	Mouse(MouseButton),
+}
+
+macro_rules! keys {
+	($($key:ident),*) => {
+		const KEYS: &[(&str, Key)] = &[$((stringify!($key), Key::$key)),*];
+	};
+}
+
+#[rustfmt::skip]
+keys!(
+	A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+	Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+	F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+	Left, Up, Right, Down,
+	Escape, Back, Return, Space, Tab,
+	LShift, RShift, LControl, RControl, LAlt, RAlt,
+	Minus, Equals, LBracket, RBracket, Comma, Period, Slash, Semicolon, Apostrophe, Backslash, Grave
+);
+
+const BUTTONS: &[(&str, MouseButton)] = &[
+	("MouseLeft", MouseButton::Left),
+	("MouseRight", MouseButton::Right),
+	("MouseMiddle", MouseButton::Middle),
+];
+
+impl Input {
+	pub fn from_name(name: &str) -> Option<Input> {
+		KEYS.iter()
+			.find(|(n, _)| *n == name)
+			.map(|(_, k)| Input::Key(*k))
+			.or_else(|| {
+				BUTTONS
+					.iter()
+					.find(|(n, _)| *n == name)
+					.map(|(_, b)| Input::Mouse(*b))
+			})
+	}
+}
+
+/// Which inputs trigger which actions. An action can have any number of
+/// inputs, and is down if any of them are.
+///
+/// Bindings can be loaded from a file that looks like this. Actions and axes
+/// that aren't in the file keep their defaults.
+///
+/// ```text
+/// # action = inputs
+/// MoveLeft = A, Left
+/// EditorPlace = MouseLeft
+///
+/// # axis = negative, positive
+/// Vertical = MoveUp, MoveDown
+/// Opposing = Cancel
+/// ```
+#[derive(Clone, Debug)]
+pub struct Bindings {
+	bindings: HashMap<Action, Vec<Input>>,
+	/// The actions that make each axis go negative and positive
+	axes: HashMap<Axis, (Action, Action)>,
+	pub opposing: Opposing,
+}
+
+impl Default for Bindings {
+	fn default() -> Self {
+		use Action::*;
+		use Input::Mouse;
+
+		let k = Input::Key;
+		let defaults = [
+			(MoveUp, vec![k(Key::W), k(Key::Up)]),
+			(MoveDown, vec![k(Key::S), k(Key::Down)]),
+			(MoveLeft, vec![k(Key::A), k(Key::Left)]),
+			(MoveRight, vec![k(Key::D), k(Key::Right)]),
+			(PlaceTile, vec![k(Key::E)]),
+			(Grab, vec![k(Key::Q)]),
+			(Pause, vec![k(Key::Escape)]),
+			(Quit, vec![]),
+			(Undo, vec![k(Key::Z)]),
+			(Redo, vec![k(Key::Y)]),
+			(ZoomIn, vec![k(Key::Equals)]),
+			(ZoomOut, vec![k(Key::Minus)]),
+			(ToggleDebug, vec![k(Key::F3)]),
+			(ToggleHud, vec![k(Key::F2)]),
+			(QuickSave, vec![k(Key::F5)]),
+			(QuickLoad, vec![k(Key::F9)]),
+			// Tab's for moving between the UI's widgets
+			(ToggleEditor, vec![k(Key::F1)]),
+			(EditorPlace, vec![Mouse(MouseButton::Left)]),
+			(EditorErase, vec![Mouse(MouseButton::Right)]),
+			(ToolBrush, vec![k(Key::Key1)]),
+			(ToolRectangle, vec![k(Key::Key2)]),
+			(ToolFill, vec![k(Key::Key3)]),
+			(BrushGrow, vec![k(Key::RBracket)]),
+			(BrushShrink, vec![k(Key::LBracket)]),
+			(PaletteNext, vec![k(Key::Period)]),
+			(PalettePrevious, vec![k(Key::Comma)]),
+			(SaveLevel, vec![k(Key::P)]),
+		];
+
+		Self {
+			bindings: defaults.into_iter().collect(),
+			axes: [
+				(Axis::Horizontal, (MoveLeft, MoveRight)),
+				(Axis::Vertical, (MoveDown, MoveUp)),
+			]
+			.into_iter()
+			.collect(),
+			opposing: Opposing::LastPressed,
+		}
+	}
+}
+
+impl Bindings {
+	/// The default bindings, overridden by the ones in the file
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BindingsError> {
+		Self::parse(&fs::read_to_string(path)?)
+	}
+
+	pub fn parse(string: &str) -> Result<Self, BindingsError> {
+		let mut bindings = Self::default();
+
+		for (idx, line) in string.lines().enumerate() {
+			let line_number = idx + 1;
+			let line = line.split('#').next().unwrap_or_default().trim();
+
+			if line.is_empty() {
+				continue;
+			}
+
+			let (name, values) = line
+				.split_once('=')
+				.ok_or(BindingsError::Syntax(line_number))?;
+			let name = name.trim();
+
+			if name == "Opposing" {
+				bindings.opposing =
+					Opposing::from_name(values.trim()).ok_or(BindingsError::Syntax(line_number))?;
+				continue;
+			}
+
+			if let Some(axis) = Axis::from_name(name) {
+				let actions = values
+					.split(',')
+					.map(|name| {
+						Action::from_name(name.trim())
+							.ok_or(BindingsError::UnknownAction(line_number))
+					})
+					.collect::<Result<Vec<Action>, BindingsError>>()?;
+
+				match actions[..] {
+					[negative, positive] => bindings.bind_axis(axis, negative, positive),
+					_ => return Err(BindingsError::Syntax(line_number)),
+				}
+				continue;
+			}
+
+			let action =
+				Action::from_name(name).ok_or(BindingsError::UnknownAction(line_number))?;
+			let inputs = values
+				.split(',')
+				.map(str::trim)
+				.filter(|s| !s.is_empty())
+				.map(|name| Input::from_name(name).ok_or(BindingsError::UnknownInput(line_number)))
+				.collect::<Result<Vec<Input>, BindingsError>>()?;
+
+			bindings.bind(action, inputs);
+		}
+
+		Ok(bindings)
+	}
+
+	/// Replace whatever was bound to the action
+	pub fn bind(&mut self, action: Action, inputs: Vec<Input>) {
+		self.bindings.insert(action, inputs);
+	}
+
+	pub fn bind_axis(&mut self, axis: Axis, negative: Action, positive: Action) {
+		self.axes.insert(axis, (negative, positive));
+	}
+
+	/// The actions that make the axis go negative and positive
+	pub fn axis(&self, axis: Axis) -> (Action, Action) {
+		self.axes[&axis]
+	}
+
+	/// What's bound to `action`, for showing the controls
+	#[allow(dead_code)] // Nothing lists the controls yet
+	pub fn inputs(&self, action: Action) -> &[Input] {
+		self.bindings
+			.get(&action)
+			.map(Vec::as_slice)
+			.unwrap_or_default()
+	}
+}
+
+/// What to do when both directions of an axis are held
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Opposing {
+	/// The one pressed most recently wins. If they went down on the same
+	/// frame they cancel out.
+	LastPressed,
+	/// Always cancel out
+	Cancel,
+}
+
+impl Opposing {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Opposing::LastPressed => "LastPressed",
+			Opposing::Cancel => "Cancel",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Opposing> {
+		match name {
+			"LastPressed" => Some(Opposing::LastPressed),
+			"Cancel" => Some(Opposing::Cancel),
+			_ => None,
+		}
+	}
+}
+
+/// Which actions are down this frame and which were down last frame, so we
+/// can tell when one starts and stops.
+pub struct ActionState {
+	pub bindings: Bindings,
+
+	down: HashSet<Action>,
+	previous: HashSet<Action>,
+
+	// The frame each held action went down on, for Opposing::LastPressed
+	frame: u64,
+	pressed_on: HashMap<Action, u64>,
+
+	analog: HashMap<Axis, f32>,
+}
+
+impl ActionState {
+	pub fn new(bindings: Bindings) -> Self {
+		Self {
+			bindings,
+
+			down: HashSet::new(),
+			previous: HashSet::new(),
+
+			frame: 0,
+			pressed_on: HashMap::new(),
+
+			analog: HashMap::new(),
+		}
+	}
+
+	/// Poll every binding. Call this once a frame before asking about actions.
+	pub fn update<F: Fn(Input) -> bool>(&mut self, is_down: F) {
+		let down = self
+			.bindings
+			.bindings
+			.iter()
+			.filter(|(_, inputs)| inputs.iter().any(|i| is_down(*i)))
+			.map(|(action, _)| *action)
+			.collect();
+
+		self.set_down(down);
+	}
+
+	/// Set the actions that are down directly, instead of polling bindings.
+	pub fn set_down(&mut self, down: HashSet<Action>) {
+		self.frame += 1;
+		self.previous = std::mem::replace(&mut self.down, down);
+
+		let frame = self.frame;
+		self.pressed_on
+			.retain(|action, _| self.down.contains(action));
+		for action in &self.down {
+			self.pressed_on.entry(*action).or_insert(frame);
+		}
+	}
+
+	/// Set an analog axis, like a gamepad stick, from -1 to 1. While it's
+	/// outside of the dead zone it's used instead of the axis' actions.
+	pub fn set_analog(&mut self, axis: Axis, value: f32) {
+		self.analog.insert(axis, value.clamp(-1.0, 1.0));
+	}
+
+	/// What the axis was last set to with `set_analog`, dead zone and all
+	pub fn analog(&self, axis: Axis) -> f32 {
+		self.analog.get(&axis).copied().unwrap_or(0.0)
+	}
+
+	/// Every action that's down this frame
+	pub fn down(&self) -> impl Iterator<Item = Action> + '_ {
+		self.down.iter().copied()
+	}
+
+	pub fn held(&self, action: Action) -> bool {
+		self.down.contains(&action)
+	}
+
+	/// Down this frame but not last
+	pub fn pressed(&self, action: Action) -> bool {
+		self.down.contains(&action) && !self.previous.contains(&action)
+	}
+
+	/// Down last frame but not this one
+	#[allow(dead_code)] // Every action so far goes off when it's pressed
+	pub fn released(&self, action: Action) -> bool {
+		!self.down.contains(&action) && self.previous.contains(&action)
+	}
+
+	/// From -1 to 1. Analog input if there is any, otherwise -1, 0, or 1
+	/// depending on which of the axis' actions are held. See `Opposing` for
+	/// what happens when both are.
+	pub fn axis(&self, axis: Axis) -> f32 {
+		match self.analog.get(&axis) {
+			Some(value) if value.abs() > ANALOG_DEAD_ZONE => return *value,
+			_ => (),
+		}
+
+		let (negative, positive) = self.bindings.axis(axis);
+
+		match (
+			self.pressed_on.get(&negative),
+			self.pressed_on.get(&positive),
+		) {
+			(None, None) => 0.0,
+			(Some(_), None) => -1.0,
+			(None, Some(_)) => 1.0,
+			(Some(neg), Some(pos)) => match self.bindings.opposing {
+				Opposing::LastPressed if neg > pos => -1.0,
+				Opposing::LastPressed if pos > neg => 1.0,
+				_ => 0.0,
+			},
+		}
+	}
+
+	/// Which way to move, with a length of at most one. Holding two directions
+	/// isn't any faster than holding one, but a stick that's only pushed part
+	/// of the way moves slower.
+	pub fn movement(&self) -> Vec2 {
+		let movement = Vec2::new(self.axis(Axis::Horizontal), self.axis(Axis::Vertical));
+		let length = (movement.x * movement.x + movement.y * movement.y).sqrt();
+
+		if length > 1.0 {
+			movement / length
+		} else {
+			movement
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+	Io(io::Error),
+	Syntax(usize),
+	UnknownAction(usize),
+	UnknownInput(usize),
+}
+
+impl From<io::Error> for BindingsError {
+	fn from(e: io::Error) -> Self {
+		BindingsError::Io(e)
+	}
+}
+
+impl fmt::Display for BindingsError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			BindingsError::Io(e) => write!(f, "couldn't read bindings: {}", e),
+			BindingsError::Syntax(line) => {
+				write!(
+					f,
+					"line {}: expected action = inputs or axis = actions",
+					line
+				)
+			}
+			BindingsError::UnknownAction(line) => write!(f, "line {}: unknown action", line),
+			BindingsError::UnknownInput(line) => write!(f, "line {}: unknown key or button", line),
+		}
+	}
+}
+
+impl std::error::Error for BindingsError {}
+
+#[cfg(test)]
+mod test {
+	use smitten::{Key, MouseButton};
+
+	use super::{Action, ActionState, Axis, Bindings, Input, Opposing};
+
+	#[test]
+	fn file_overrides_defaults() {
+		let bindings =
+			Bindings::parse("# comment\nMoveLeft = J, Left\nPlaceTile = MouseMiddle\n").unwrap();
+
+		assert_eq!(
+			bindings.inputs(Action::MoveLeft),
+			&[Input::Key(Key::J), Input::Key(Key::Left)]
+		);
+		assert_eq!(
+			bindings.inputs(Action::PlaceTile),
+			&[Input::Mouse(MouseButton::Middle)]
+		);
+		// Untouched
+		assert_eq!(bindings.inputs(Action::MoveRight)[0], Input::Key(Key::D));
+
+		assert!(Bindings::parse("Jump = Space").is_err());
+		assert!(Bindings::parse("Horizontal = MoveLeft").is_err());
+		assert_eq!(
+			Bindings::parse("Opposing = Cancel").unwrap().opposing,
+			Opposing::Cancel
+		);
+	}
+
+	#[test]
+	fn axes_can_be_flipped() {
+		let bindings = Bindings::parse("Vertical = MoveUp, MoveDown").unwrap();
+		assert_eq!(
+			bindings.axis(Axis::Vertical),
+			(Action::MoveUp, Action::MoveDown)
+		);
+
+		let mut state = ActionState::new(bindings);
+		state.update(|i| i == Input::Key(Key::W));
+		assert_eq!(state.axis(Axis::Vertical), -1.0);
+		assert!(Bindings::parse("MoveUp = Banana").is_err());
+	}
+
+	#[test]
+	fn edges() {
+		let mut state = ActionState::new(Bindings::default());
+
+		state.update(|i| i == Input::Key(Key::E));
+		assert!(state.pressed(Action::PlaceTile));
+		assert!(state.held(Action::PlaceTile));
+
+		state.update(|i| i == Input::Key(Key::E));
+		assert!(!state.pressed(Action::PlaceTile));
+		assert!(state.held(Action::PlaceTile));
+
+		state.update(|_| false);
+		assert!(state.released(Action::PlaceTile));
+		assert!(!state.held(Action::PlaceTile));
+	}
+
+	#[test]
+	fn axes() {
+		let mut state = ActionState::new(Bindings::default());
+
+		state.update(|i| i == Input::Key(Key::A));
+		assert_eq!(state.axis(Axis::Horizontal), -1.0);
+
+		// Right went down after left, so it wins
+		state.update(|i| i == Input::Key(Key::A) || i == Input::Key(Key::Right));
+		assert_eq!(state.axis(Axis::Horizontal), 1.0);
+
+		state.bindings.opposing = Opposing::Cancel;
+		assert_eq!(state.axis(Axis::Horizontal), 0.0);
+
+		// Both on the same frame cancel either way
+		let mut state = ActionState::new(Bindings::default());
+		state.update(|i| i == Input::Key(Key::W) || i == Input::Key(Key::S));
+		assert_eq!(state.axis(Axis::Vertical), 0.0);
+	}
+
+	#[test]
+	fn diagonals_are_normalized() {
+		let mut state = ActionState::new(Bindings::default());
+
+		state.update(|i| i == Input::Key(Key::W) || i == Input::Key(Key::D));
+		let movement = state.movement();
+		let length = (movement.x * movement.x + movement.y * movement.y).sqrt();
+		assert!((length - 1.0).abs() < 0.0001);
+		assert!(movement.x > 0.0 && movement.y > 0.0);
+	}
+
+	#[test]
+	fn analog_overrides_keys() {
+		let mut state = ActionState::new(Bindings::default());
+		state.update(|i| i == Input::Key(Key::A));
+
+		// Inside of the dead zone, so the key still counts
+		state.set_analog(Axis::Horizontal, 0.05);
+		assert_eq!(state.axis(Axis::Horizontal), -1.0);
+
+		state.set_analog(Axis::Horizontal, 0.5);
+		assert_eq!(state.movement().x, 0.5);
+	}
+}
@@ -0,0 +1,323 @@
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+use smitten::Vec2;
+
+use crate::grid::{Grid, Size, Tile};
+
+/// Levels are plain text so they're easy to diff and poke at by hand. They
+/// look like this, with the top row of the grid first:
+///
+/// ```text
+/// size 5 5
+/// position 1 1
+/// side 1
+/// tiles
+/// .....
+/// ..#..
+/// ..:..
+/// .#...
+/// .....
+/// platform 0 0 4 0
+/// ```
+///
+/// After the tiles, each `platform` line is a moving platform and the cells
+/// it goes back and forth between, x then y.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Grid, LevelError> {
+	let string = fs::read_to_string(path)?;
+	parse(&string)
+}
+
+/// What we start with when there isn't a level to load
+pub fn empty() -> Grid {
+	Grid::new((5, 5), (1, 1), 1.0)
+}
+
+pub fn save<P: AsRef<Path>>(grid: &Grid, path: P) -> io::Result<()> {
+	if let Some(parent) = path.as_ref().parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	fs::write(path, serialize(grid))
+}
+
+pub fn serialize(grid: &Grid) -> String {
+	let mut string = format!(
+		"size {} {}\nposition {} {}\nside {}\ntiles\n",
+		grid.size.width, grid.size.height, grid.position.x, grid.position.y, grid.side_length
+	);
+
+	for y in (0..grid.size.height).rev() {
+		for x in 0..grid.size.width {
+			let tile = grid.tile(Size {
+				width: x,
+				height: y,
+			});
+
+			string.push(tile_char(tile.copied()));
+		}
+		string.push('\n');
+	}
+
+	for cells in &grid.platforms {
+		let cells: Vec<String> = cells
+			.iter()
+			.map(|c| format!(" {} {}", c.width, c.height))
+			.collect();
+		string.push_str(&format!("platform{}\n", cells.concat()));
+	}
+
+	string
+}
+
+pub fn parse(string: &str) -> Result<Grid, LevelError> {
+	let mut lines = Lines::from(string.lines());
+
+	let size: [u32; 2] = lines.values("size")?;
+	let position: [f32; 2] = lines.values("position")?;
+	let [side_length]: [f32; 1] = lines.values("side")?;
+	lines.expect("tiles")?;
+
+	let mut grid = Grid::new(
+		(size[0], size[1]),
+		Vec2::new(position[0], position[1]),
+		side_length,
+	);
+
+	for y in (0..size[1]).rev() {
+		let (line_number, row) = lines.next().ok_or(LevelError::MissingRows)?;
+
+		if row.chars().count() != size[0] as usize {
+			return Err(LevelError::RowLength {
+				line: line_number,
+				expected: size[0],
+			});
+		}
+
+		for (x, c) in row.chars().enumerate() {
+			let tile = char_tile(c).ok_or(LevelError::UnknownTile {
+				line: line_number,
+				tile: c,
+			})?;
+
+			grid.set_tile(
+				Size {
+					width: x as u32,
+					height: y,
+				},
+				tile,
+			);
+		}
+	}
+
+	while let Some((line, cells)) = lines.platform()? {
+		let bad = || LevelError::BadValue {
+			line,
+			key: "platform",
+		};
+		if cells.is_empty() || cells.len() % 2 != 0 {
+			return Err(bad());
+		}
+
+		let cells: Vec<Size> = cells
+			.chunks(2)
+			.map(|c| Size {
+				width: c[0],
+				height: c[1],
+			})
+			.collect();
+		if cells
+			.iter()
+			.any(|c| !grid.contains(c.width as i32, c.height as i32))
+		{
+			return Err(bad());
+		}
+
+		grid.platforms.push(cells);
+	}
+
+	Ok(grid)
+}
+
+fn tile_char(tile: Option<Tile>) -> char {
+	match tile {
+		None => '.',
+		Some(Tile::Solid) => '#',
+		Some(Tile::Backdrop) => ':',
+	}
+}
+
+fn char_tile(c: char) -> Option<Option<Tile>> {
+	match c {
+		'.' => Some(None),
+		'#' => Some(Some(Tile::Solid)),
+		':' => Some(Some(Tile::Backdrop)),
+		_ => None,
+	}
+}
+
+/// Line iterator that skips blank lines and remembers line numbers for
+/// error messages.
+struct Lines<'a> {
+	inner: std::iter::Enumerate<std::str::Lines<'a>>,
+}
+
+impl<'a> From<std::str::Lines<'a>> for Lines<'a> {
+	fn from(lines: std::str::Lines<'a>) -> Self {
+		Self {
+			inner: lines.enumerate(),
+		}
+	}
+}
+
+impl<'a> Lines<'a> {
+	fn next(&mut self) -> Option<(usize, &'a str)> {
+		self.inner
+			.by_ref()
+			.map(|(idx, line)| (idx + 1, line.trim()))
+			.find(|(_, line)| !line.is_empty())
+	}
+
+	fn expect(&mut self, key: &'static str) -> Result<(usize, Vec<&'a str>), LevelError> {
+		let (line_number, line) = self.next().ok_or(LevelError::Missing(key))?;
+		let mut words = line.split_whitespace();
+
+		if words.next() != Some(key) {
+			return Err(LevelError::Missing(key));
+		}
+
+		Ok((line_number, words.collect()))
+	}
+
+	/// The next `platform` line's numbers, if there are any lines left
+	fn platform(&mut self) -> Result<Option<(usize, Vec<u32>)>, LevelError> {
+		if self.inner.clone().all(|(_, line)| line.trim().is_empty()) {
+			return Ok(None);
+		}
+
+		let (line, words) = self.expect("platform")?;
+		let cells = words
+			.iter()
+			.map(|w| w.parse())
+			.collect::<Result<_, _>>()
+			.map_err(|_| LevelError::BadValue {
+				line,
+				key: "platform",
+			})?;
+
+		Ok(Some((line, cells)))
+	}
+
+	fn values<T: FromStr + Copy + Default, const N: usize>(
+		&mut self,
+		key: &'static str,
+	) -> Result<[T; N], LevelError> {
+		let (line_number, words) = self.expect(key)?;
+
+		if words.len() != N {
+			return Err(LevelError::BadValue {
+				line: line_number,
+				key,
+			});
+		}
+
+		let mut values = [T::default(); N];
+		for (value, word) in values.iter_mut().zip(words) {
+			*value = word.parse().map_err(|_| LevelError::BadValue {
+				line: line_number,
+				key,
+			})?;
+		}
+
+		Ok(values)
+	}
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+	Io(io::Error),
+	Missing(&'static str),
+	BadValue { line: usize, key: &'static str },
+	MissingRows,
+	RowLength { line: usize, expected: u32 },
+	UnknownTile { line: usize, tile: char },
+}
+
+impl From<io::Error> for LevelError {
+	fn from(e: io::Error) -> Self {
+		LevelError::Io(e)
+	}
+}
+
+impl fmt::Display for LevelError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LevelError::Io(e) => write!(f, "couldn't read level: {}", e),
+			LevelError::Missing(key) => write!(f, "expected a '{}' line", key),
+			LevelError::BadValue { line, key } => {
+				write!(f, "line {}: couldn't parse the values of '{}'", line, key)
+			}
+			LevelError::MissingRows => write!(f, "there are fewer rows than the size says"),
+			LevelError::RowLength { line, expected } => {
+				write!(f, "line {}: rows should be {} tiles long", line, expected)
+			}
+			LevelError::UnknownTile { line, tile } => {
+				write!(f, "line {}: '{}' isn't a tile", line, tile)
+			}
+		}
+	}
+}
+
+impl std::error::Error for LevelError {}
+
+#[cfg(test)]
+mod test {
+	use super::{parse, serialize, LevelError};
+	use crate::grid::{Grid, Size, Tile};
+
+	#[test]
+	fn round_trip() {
+		let mut grid = Grid::new((3, 2), (1.0, -2.5), 1.0);
+		grid.set_tile(
+			Size {
+				width: 0,
+				height: 1,
+			},
+			Some(Tile::Solid),
+		);
+		grid.set_tile(
+			Size {
+				width: 2,
+				height: 0,
+			},
+			Some(Tile::Backdrop),
+		);
+
+		let string = serialize(&grid);
+		assert_eq!(
+			string,
+			"size 3 2\nposition 1 -2.5\nside 1\ntiles\n#..\n..:\n"
+		);
+
+		let parsed = parse(&string).unwrap();
+		assert_eq!(serialize(&parsed), string);
+
+		let platform = string.clone() + "platform 0 0 2 0\n";
+		let parsed = parse(&platform).unwrap();
+		assert_eq!(parsed.platforms.len(), 1);
+		assert_eq!(serialize(&parsed), platform);
+
+		// Off the grid, or half a cell
+		assert!(parse(&(string.clone() + "platform 0 0 3 0\n")).is_err());
+		assert!(parse(&(string + "platform 0 0 2\n")).is_err());
+	}
+
+	#[test]
+	fn bad_tile_reports_line() {
+		let err = parse("size 2 1\nposition 0 0\nside 1\ntiles\n.x\n").unwrap_err();
+
+		assert!(matches!(
+			err,
+			LevelError::UnknownTile { line: 5, tile: 'x' }
+		))
+	}
+}
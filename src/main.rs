@@ -1,82 +1,388 @@
+mod animation;
+mod atlas;
+mod autotile;
+mod camera;
+mod canvas;
+mod debug;
+mod editor;
+mod entity;
+mod font;
+mod fov;
+mod game;
+mod grid;
+mod headless;
+mod history;
+mod hud;
+mod input;
+mod level;
+mod particle;
 mod physics;
+mod platform;
+mod render;
+mod replay;
+mod save;
+mod scene;
+mod state;
+mod texture;
 mod thing;
-
+mod tween;
+mod ui;
+mod watch;
+mod world;
+
+use std::{fs, path::Path, time::Instant};
+
+use animation::{Animator, Clip, Motion, Playback};
+use atlas::{Atlas, Sprite};
+use autotile::{Mode, Tileset};
+use canvas::Canvas;
+use game::{Game, Title};
+use input::{Action, ActionState, Bindings, Input};
 use physics::{aabb_check, Intersection, LineSegment};
-use smitten::{self, Color, Key, SignedDistance, Smitten, Vec2};
+use render::{Assets, Recorder, Renderer};
+use replay::Recording;
+use smitten::{self, MouseButton, Smitten, Vec2};
+use state::Stack;
+use texture::Textures;
 use thing::Thing;
+use ui::{Keyboard, Ui, UiInput};
+use watch::Watcher;
+use world::{World, TIMESTEP};
+
+const DIMENSIONS: (u32, u32) = (720, 480);
+const PIXELS_PER_UNIT: u32 = 36;
+const LEVEL_PATH: &str = "levels/level.txt";
+const BINDINGS_PATH: &str = "bindings.txt";
+/// Seconds between checking whether any loaded files have changed
+const WATCH_INTERVAL: f32 = 0.5;
+const SQUARE_PATH: &str = "images/puare.png";
+const ATLAS_PATH: &str = "images/atlas.toml";
+const ATLAS_WIDTH: u32 = 512;
 
 fn main() {
-	let mut smitty = Smitten::new((720, 480), "Square", 36);
-	smitty.texture_coloring(false);
-
-	let sq = smitty.make_texture("images/puare.png");
+	let args: Vec<String> = std::env::args().collect();
+
+	// notsure pack <manifest> <images...>
+	if args.get(1).map(String::as_str) == Some("pack") {
+		match args.get(2) {
+			Some(manifest) => match Atlas::pack(&args[3..], Path::new(manifest), ATLAS_WIDTH) {
+				Ok(atlas) => println!("Packed {} into {}", manifest, atlas.image.display()),
+				Err(e) => eprintln!("Couldn't pack {}: {}", manifest, e),
+			},
+			None => eprintln!("usage: notsure pack <manifest> <images...>"),
+		}
+		return;
+	}
 
-	let mut us = Thing::new((-3.0, -3.0), (1, 1), Color::rgb(0.1, 0.3, 0.5));
-	let speed = 0.075;
+	// notsure replay <recording>
+	if args.get(1).map(String::as_str) == Some("replay") {
+		match args
+			.get(2)
+			.map(|path| Recording::load(path).and_then(|r| r.check()))
+		{
+			Some(Ok(end)) => println!("Ended at {} {} as recorded", end.x, end.y),
+			Some(Err(e)) => {
+				eprintln!("Replay failed: {}", e);
+				std::process::exit(1);
+			}
+			None => eprintln!("usage: notsure replay <recording>"),
+		}
+		return;
+	}
 
-	let mut grid = Grid::new((5, 5), (1, 1), 1.0);
-	let gridlines = grid.gridlines();
+	// notsure headless <scripts...>
+	if args.get(1).map(String::as_str) == Some("headless") {
+		let mut failed = false;
+
+		for script in &args[2..] {
+			match headless::run_file(script) {
+				Ok(_) => println!("{}: ok", script),
+				Err(e) => {
+					println!("{}: {}", script, e);
+					failed = true;
+				}
+			}
+		}
 
-	loop {
-		let _events = smitty.events();
+		if failed {
+			std::process::exit(1);
+		}
+		return;
+	}
 
-		// Keyboard Control
-		if smitty.is_key_down(Key::Escape) {
-			break;
+	// notsure snapshot <script> <image.png or drawing.svg>
+	if args.get(1).map(String::as_str) == Some("snapshot") {
+		match (args.get(2), args.get(3)) {
+			(Some(script), Some(out)) => match headless::run_file(script) {
+				Ok(world) => {
+					let commands = headless::draw(&world, DIMENSIONS, PIXELS_PER_UNIT);
+
+					let saved = if out.ends_with(".svg") {
+						let mut recorder = Recorder::default();
+						recorder.draw_all(&commands);
+						fs::write(out, recorder.svg(DIMENSIONS, PIXELS_PER_UNIT))
+							.map_err(|e| e.to_string())
+					} else {
+						let mut canvas = Canvas::new(DIMENSIONS, PIXELS_PER_UNIT);
+						canvas.draw_all(&commands);
+						canvas.save(out).map_err(|e| e.to_string())
+					};
+
+					if let Err(e) = saved {
+						eprintln!("Couldn't save {}: {}", out, e);
+					}
+				}
+				Err(e) => eprintln!("{}: {}", script, e),
+			},
+			_ => eprintln!("usage: notsure snapshot <script> <image.png or drawing.svg>"),
 		}
+		return;
+	}
 
-		if smitty.is_key_down(Key::W) {
-			us.offset((0.0, speed));
-		} else if smitty.is_key_down(Key::S) {
-			us.offset((0.0, -speed));
+	// notsure record <recording>
+	let record_path = match args.get(1).map(String::as_str) {
+		Some("record") => args.get(2).cloned(),
+		_ => None,
+	};
+
+	let mut smitty = Smitten::new(DIMENSIONS, "Square", PIXELS_PER_UNIT);
+	smitty.texture_coloring(false);
+
+	let mut textures = Textures::new();
+	let sq = match textures.load(SQUARE_PATH, |p| smitty.make_texture(p)) {
+		Ok(sq) => sq,
+		Err(e) => {
+			eprintln!("Couldn't load {}: {}", SQUARE_PATH, e);
+			return;
+		}
+	};
+
+	let atlas = match Atlas::load(ATLAS_PATH) {
+		Ok(atlas) => match textures.load(&atlas.image, |p| smitty.make_texture(p)) {
+			Ok(sheet) => Some((atlas, sheet)),
+			Err(e) => {
+				eprintln!("Not loading {}: {}", atlas.image.display(), e);
+				None
+			}
+		},
+		Err(e) => {
+			eprintln!("Not loading {}: {}", ATLAS_PATH, e);
+			None
+		}
+	};
+	let region = |name: &str| atlas.as_ref().and_then(|(a, _)| a.id(name));
+
+	// Variants are optional, anything that isn't drawn yet uses the square.
+	// They're looked for in the atlas first and then as loose images. Blob
+	// tiles are used if there are any, they just take more drawing.
+	let (mode, prefix) = if region("blob_0").is_some() || Path::new("images/blob").exists() {
+		(Mode::Blob, "blob")
+	} else {
+		(Mode::FourBit, "autotile")
+	};
+
+	let mut tileset = Tileset::new(mode, Sprite::from(sq));
+	for variant in 0..mode.variants() {
+		let path = format!("images/{}/{}.png", prefix, variant);
+
+		if let Some(id) = region(&format!("{}_{}", prefix, variant)) {
+			tileset.set(variant, id.into());
+		} else if Path::new(&path).exists() {
+			match textures.load(&path, |p| smitty.make_texture(p)) {
+				Ok(texture) => tileset.set(variant, texture.into()),
+				Err(e) => eprintln!("Not loading {}: {}", path, e),
+			}
 		}
+	}
 
-		if smitty.is_key_down(Key::A) {
-			us.offset((-speed, 0.0));
-		} else if smitty.is_key_down(Key::D) {
-			us.offset((speed, 0.0));
+	// The player only animates if the atlas has at least an idle clip
+	let animator = atlas.as_ref().and_then(|(atlas, _)| {
+		let clip = |name, playback| Clip::from_atlas(atlas, name, 0.15, playback);
+
+		let mut animator = Animator::new(Motion::Idle, clip("idle", Playback::PingPong)?);
+		if let Some(walk) = clip("walk", Playback::Loop) {
+			animator.add(Motion::Walk, walk.with_event(0, "step"));
+		}
+		if let Some(place) = clip("place", Playback::Once) {
+			animator.add(Motion::Place, place);
 		}
 
-		if smitty.is_key_down(Key::E) {
-			if let Some(coords) = grid.get_coords(us.center) {
-				grid.set_tile(coords, Some(Tile::Solid));
-			}
+		Some(animator)
+	});
+
+	let grid = match level::load(LEVEL_PATH) {
+		Ok(grid) => grid,
+		Err(e) => {
+			eprintln!("Not loading {}: {}", LEVEL_PATH, e);
+			level::empty()
 		}
+	};
 
-		// Drawing
-		smitty.clear();
+	let mut world = World::new(grid, (-3.0, -3.0));
+	if let Some(id) = region("player") {
+		world.entities.looks[world.player].sprite = id.into();
+	}
+	let assets = Assets { atlas, tileset };
 
-		for line in &gridlines {
-			smitty.sdf(*line);
+	let bindings = match Bindings::load(BINDINGS_PATH) {
+		Ok(bindings) => bindings,
+		Err(e) => {
+			eprintln!("Not loading {}: {}", BINDINGS_PATH, e);
+			Bindings::default()
 		}
+	};
+	let input = ActionState::new(bindings);
+
+	let mut game = Game::new(
+		world,
+		assets,
+		input,
+		LEVEL_PATH,
+		DIMENSIONS,
+		PIXELS_PER_UNIT,
+	);
+	game.animator = animator;
+	game.recording = record_path
+		.as_ref()
+		.map(|_| Recording::new(&game.world, &game.input.bindings));
+
+	let mut states: Stack<Game> = Stack::new(Box::new(Title::new(&mut game)));
+	let mut ui = Ui::new(DIMENSIONS, PIXELS_PER_UNIT);
+	let mut keyboard = Keyboard::default();
+
+	let mut watcher = Watcher::new(WATCH_INTERVAL);
+	for path in textures.paths() {
+		watcher.watch(path);
+	}
+	watcher.watch(ATLAS_PATH);
+	let mut watched_level = game.level_path.clone();
+	watcher.watch(&watched_level);
+
+	let mut last_frame = Instant::now();
+	let mut accumulator = 0.0;
+
+	'running: loop {
+		let _events = smitty.events();
 
-		for (tl, p) in grid.tiles_and_position() {
-			match tl {
-				Some(Tile::Solid) => smitty.rect(p, (grid.side_length, grid.side_length), sq),
-				_ => (),
+		// Don't try to catch up on more than a quarter second at once, a long
+		// hitch would otherwise turn into a burst of steps.
+		let frame_time = last_frame.elapsed().as_secs_f32();
+		accumulator += frame_time.min(0.25);
+		last_frame = Instant::now();
+		game.hud.frame(frame_time);
+
+		// The editor can point the level somewhere else
+		if game.level_path != watched_level {
+			watcher.unwatch(&watched_level);
+			watched_level = game.level_path.clone();
+			watcher.watch(&watched_level);
+		}
+		for path in watcher.poll(frame_time) {
+			if path == Path::new(&game.level_path) {
+				game.reload_level();
+			} else if path == Path::new(ATLAS_PATH) {
+				let atlas = match Atlas::load(ATLAS_PATH) {
+					Ok(atlas) => atlas,
+					Err(e) => {
+						game.hud.set_error(ATLAS_PATH, e);
+						continue;
+					}
+				};
+
+				// The sheet might've moved along with the regions
+				match textures.load(&atlas.image, |p| smitty.make_texture(p)) {
+					Ok(sheet) => {
+						watcher.watch(&atlas.image);
+						game.replace_atlas(atlas, sheet);
+						game.hud.clear_error(ATLAS_PATH);
+					}
+					Err(e) => game
+						.hud
+						.set_error(ATLAS_PATH, format!("{}: {}", atlas.image.display(), e)),
+				}
+			} else if let Some(texture) = textures.get(&path) {
+				let name = path.display().to_string();
+
+				match textures.load(&path, |p| smitty.make_texture(p)) {
+					Ok(reloaded) => {
+						game.replace_texture(texture, reloaded);
+						game.hud.clear_error(&name);
+					}
+					Err(e) => game.hud.set_error(&name, e),
+				}
 			}
 		}
 
-		if let Some(p) = grid.coordinate_center(2, 2) {
-			smitty.sdf(SignedDistance::Circle {
-				center: p,
-				radius: 2,
-				color: Color::YELLOW,
-			})
+		// The world moves in fixed steps, however long the frame took
+		while accumulator >= TIMESTEP {
+			accumulator -= TIMESTEP;
+
+			// Whatever the UI is using, the game doesn't get
+			game.input.update(|i| match i {
+				Input::Key(key) => !ui.wants_keyboard() && smitty.is_key_down(key),
+				Input::Mouse(button) => !ui.wants_mouse() && smitty.is_mouse_down(button),
+			});
+
+			// Keyboard Control
+			if game.input.held(Action::Quit) {
+				break 'running;
+			}
+
+			if game.input.pressed(Action::ToggleDebug) {
+				debug::toggle();
+			}
+
+			if game.input.pressed(Action::ToggleHud) {
+				game.hud.visible = !game.hud.visible;
+			}
+
+			game.cursor = game
+				.world
+				.grid
+				.get_coords(game.camera.screen_to_world(smitty.mouse_position()));
+
+			game.step(&mut states);
+			if states.finished() {
+				break 'running;
+			}
 		}
 
-		smitty.rect(us.center, us.size, us.draw);
+		game.cursor = game
+			.world
+			.grid
+			.get_coords(game.camera.screen_to_world(smitty.mouse_position()));
 
+		// Drawing
+		game.hud.set("ZOOM", format!("{:.2}", game.camera.zoom));
+		game.hud.set("PARTICLES", game.world.particles.len());
+
+		let mut commands = game.draw(&states);
+
+		let (keys, text) = keyboard.update(|key| smitty.is_key_down(key));
+		ui.begin(UiInput {
+			mouse: Some(ui.mouse_from_pixels(smitty.mouse_position())),
+			mouse_down: smitty.is_mouse_down(MouseButton::Left),
+			keys,
+			text,
+		});
+		states.ui(&mut game, &mut ui);
+		commands.extend(ui.end());
+
+		if states.finished() {
+			break 'running;
+		}
+
+		smitty.draw_all(&commands);
 		smitty.swap();
 	}
-}
 
-fn sdf_seg(seg: LineSegment, thickness: u32, color: Color) -> SignedDistance {
-	SignedDistance::LineSegment {
-		start: seg.start,
-		end: seg.end,
-		thickness,
-		color,
+	if let (Some(mut recording), Some(path)) = (game.recording.take(), record_path) {
+		recording.finish(&game.world);
+
+		match recording.save(&path) {
+			Ok(()) => println!("Recorded {} steps to {}", recording.frames.len(), path),
+			Err(e) => eprintln!("Couldn't save {}: {}", path, e),
+		}
 	}
 }
 
@@ -87,7 +393,7 @@ fn do_full_collision(dynamic: &mut Thing, stuck: &Thing, movement: Vec2) -> bool
 	}
 
 	// We're colliding
-	let previous = Thing::new(dynamic.previous_center, dynamic.size, dynamic.draw);
+	let previous = Thing::new(dynamic.previous_center, dynamic.half_size * 2.0);
 
 	let tr = LineSegment::new(previous.topright(), dynamic.topright());
 	let tl = LineSegment::new(previous.topleft(), dynamic.topleft());
@@ -114,170 +420,27 @@ fn do_full_collision(dynamic: &mut Thing, stuck: &Thing, movement: Vec2) -> bool
 	interdist.sort_unstable_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
 
 	if let Some((side, Intersection::Point(mut p), dist)) = interdist.first() {
-		match side {
-			thing::Side::Top => dynamic.center.y = p.y + dynamic.half_size.y,
-			thing::Side::Right => dynamic.center.x = p.x - dynamic.half_size.x,
-			thing::Side::Bottom => dynamic.center.y = p.y - dynamic.half_size.y,
-			thing::Side::Left => dynamic.center.x = p.x + dynamic.half_size.x,
-		}
-		println!("{:?}", p);
+		let normal = match side {
+			thing::Side::Top => {
+				dynamic.center.y = p.y + dynamic.half_size.y;
+				Vec2::new(0.0, 1.0)
+			}
+			thing::Side::Right => {
+				dynamic.center.x = p.x - dynamic.half_size.x;
+				Vec2::new(-1.0, 0.0)
+			}
+			thing::Side::Bottom => {
+				dynamic.center.y = p.y - dynamic.half_size.y;
+				Vec2::new(0.0, -1.0)
+			}
+			thing::Side::Left => {
+				dynamic.center.x = p.x + dynamic.half_size.x;
+				Vec2::new(1.0, 0.0)
+			}
+		};
+		debug::contact(p, normal);
 		//dynamic.put(p);
 		return true;
 	}
 	false
 }
-
-#[derive(Copy, Clone, Debug)]
-struct Size {
-	width: u32,
-	height: u32,
-}
-
-impl Size {
-	pub fn stride(&self) -> usize {
-		self.width as usize * self.height as usize
-	}
-
-	pub fn half(&self) -> Vec2 {
-		Vec2::new(self.width as f32 / 2.0, self.height as f32 / 2.0)
-	}
-}
-
-impl From<(u32, u32)> for Size {
-	fn from(t: (u32, u32)) -> Self {
-		Size {
-			width: t.0,
-			height: t.1,
-		}
-	}
-}
-
-impl From<Size> for Vec2 {
-	fn from(s: Size) -> Self {
-		Vec2 {
-			x: s.width as f32,
-			y: s.width as f32,
-		}
-	}
-}
-
-struct Grid {
-	size: Size,
-	position: Vec2,
-
-	side_length: f32,
-	grid: Vec<Option<Tile>>,
-}
-
-impl Grid {
-	pub fn new<S: Into<Size>, P: Into<Vec2>>(size: S, position: P, side_length: f32) -> Self {
-		let size = size.into();
-
-		Self {
-			size,
-			position: position.into(),
-			side_length,
-			grid: vec![None; size.stride()],
-		}
-	}
-
-	pub fn gridlines(&self) -> Vec<SignedDistance> {
-		let mut lines = vec![];
-
-		let half_size = self.size.half();
-
-		let x_start = self.position.x - half_size.x;
-		let x_end = self.position.x + half_size.x;
-
-		let y_start = self.position.y - half_size.y;
-		let y_end = self.position.y + half_size.y;
-
-		for x in 0..=self.size.width {
-			let x_position = (x_start + x as f32) * self.side_length;
-
-			lines.push(SignedDistance::LineSegment {
-				start: Vec2::new(x_position, y_start),
-				end: Vec2::new(x_position, y_end),
-				thickness: 2,
-				color: Color::rgb(0.5, 0.3, 0.0),
-			});
-		}
-
-		for y in 0..=self.size.height {
-			let y_position = (y_start + y as f32) * self.side_length;
-
-			lines.push(SignedDistance::LineSegment {
-				start: Vec2::new(x_start, y_position),
-				end: Vec2::new(x_end, y_position),
-				thickness: 2,
-				color: Color::rgb(0.5, 0.3, 0.0),
-			});
-		}
-
-		lines
-	}
-
-	pub fn coordinate_center(&self, x: u32, y: u32) -> Option<Vec2> {
-		if x >= self.size.width && y >= self.size.height {
-			return None;
-		}
-
-		let half_size = self.size.half();
-		Some(Vec2 {
-			x: (x as f32 - half_size.x) * self.side_length
-				+ (self.side_length / 2.0)
-				+ self.position.x,
-			y: (y as f32 - half_size.y) * self.side_length
-				+ (self.side_length / 2.0)
-				+ self.position.y,
-		})
-	}
-
-	//TODO: gen- function name?
-	pub fn get_coords(&self, loc: Vec2) -> Option<Size> {
-		let offset_to_center = loc - self.position;
-
-		//TODO: gen- Why uh, why is it half_size PLUS offset?
-		let half_size = self.size.half();
-		let coords = (half_size + offset_to_center) / self.side_length;
-
-		if coords.x < 0.0
-			|| coords.y < 0.0
-			|| coords.x > self.size.width as f32
-			|| coords.y > self.size.height as f32
-		{
-			None
-		} else {
-			Some(Size {
-				width: coords.x as u32,
-				height: coords.y as u32,
-			})
-		}
-	}
-
-	pub fn set_tile(&mut self, coords: Size, tile: Option<Tile>) {
-		//TODO: gen- check coordinates valid
-		self.grid[coords.height as usize * self.size.width as usize + coords.width as usize] = tile;
-	}
-
-	pub fn tiles_and_position(&self) -> Vec<(Option<&Tile>, Vec2)> {
-		let mut ret = vec![];
-
-		for (idx, tile) in self.grid.iter().enumerate() {
-			let y = idx / self.size.width as usize;
-			let x = idx % self.size.width as usize;
-
-			ret.push((
-				tile.as_ref(),
-				self.coordinate_center(x as u32, y as u32).unwrap(),
-			))
-		}
-
-		ret
-	}
-}
-
-#[derive(Clone, Copy, Debug)]
-enum Tile {
-	Solid,
-}
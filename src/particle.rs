@@ -0,0 +1,494 @@
+use smitten::{Color, SignedDistance, Vec2};
+
+use crate::{
+	camera::Camera,
+	entity::{Entities, Entity},
+	grid::Grid,
+	render::Command,
+	scene,
+};
+
+/// How much speed a particle keeps, and turns around, when it hits a tile
+const BOUNCE: f32 = 0.3;
+
+/// How a particle is drawn
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+	Square,
+	Circle,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Particle {
+	pub position: Vec2,
+	/// Units per second
+	pub velocity: Vec2,
+	/// Seconds since it was spawned, it's gone once that's `lifetime`
+	pub age: f32,
+	pub lifetime: f32,
+}
+
+/// Something that gives off particles. Everything about a particle is picked
+/// when it's spawned, except color and size which go from start to end over
+/// its life.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+	pub position: Vec2,
+	/// Particles per second, on top of any bursts
+	pub rate: f32,
+	/// Seconds to keep emitting for, or forever if None. Once it's run out
+	/// and its particles are gone, the emitter is removed.
+	pub duration: Option<f32>,
+	/// Something to stay on. When it's despawned the emitter stops.
+	pub follow: Option<Entity>,
+
+	/// Seconds, picked between the two
+	pub lifetime: (f32, f32),
+	/// Radians counterclockwise from the right that particles leave in
+	pub direction: f32,
+	/// How far either side of `direction` they can go, in radians
+	pub spread: f32,
+	/// Units per second, picked between the two
+	pub speed: (f32, f32),
+	/// Units per second per second
+	pub gravity: Vec2,
+
+	pub color: (Color, Color),
+	/// Width in units
+	pub size: (f32, f32),
+	pub shape: Shape,
+	/// Whether particles bounce off solid tiles
+	pub collide: bool,
+
+	particles: Vec<Particle>,
+	// Particles we should've spawned but couldn't, because they're fractional
+	owed: f32,
+}
+
+impl Emitter {
+	pub fn new<P: Into<Vec2>>(position: P) -> Self {
+		Self {
+			position: position.into(),
+			rate: 0.0,
+			duration: None,
+			follow: None,
+
+			lifetime: (1.0, 1.0),
+			direction: std::f32::consts::FRAC_PI_2,
+			spread: std::f32::consts::PI,
+			speed: (1.0, 1.0),
+			gravity: Vec2::ZERO,
+
+			color: (Color::WHITE, Color::rgba(1.0, 1.0, 1.0, 0.0)),
+			size: (0.1, 0.1),
+			shape: Shape::Square,
+			collide: false,
+
+			particles: vec![],
+			owed: 0.0,
+		}
+	}
+
+	/// A puff kicked up off the ground, for landing
+	pub fn dust<P: Into<Vec2>>(position: P) -> Self {
+		Self {
+			duration: Some(0.0),
+			lifetime: (0.3, 0.6),
+			spread: 1.2,
+			speed: (0.5, 1.5),
+			gravity: Vec2::new(0.0, -2.0),
+			color: (
+				Color::rgba(0.6, 0.55, 0.5, 0.8),
+				Color::rgba(0.6, 0.55, 0.5, 0.0),
+			),
+			size: (0.1, 0.25),
+			collide: true,
+			..Self::new(position)
+		}
+	}
+
+	/// A shower of sparks flying off in `direction`, for hits
+	pub fn sparks<P: Into<Vec2>>(position: P, direction: f32) -> Self {
+		Self {
+			duration: Some(0.0),
+			lifetime: (0.2, 0.5),
+			direction,
+			spread: 0.5,
+			speed: (3.0, 6.0),
+			gravity: Vec2::new(0.0, -9.8),
+			color: (Color::rgb(1.0, 0.9, 0.4), Color::rgba(1.0, 0.3, 0.1, 0.0)),
+			size: (0.08, 0.02),
+			shape: Shape::Circle,
+			collide: true,
+			..Self::new(position)
+		}
+	}
+
+	/// A slow trickle drifting up off `entity`, for as long as it's around
+	pub fn embers<P: Into<Vec2>>(position: P, entity: Entity) -> Self {
+		Self {
+			rate: 6.0,
+			follow: Some(entity),
+			lifetime: (0.6, 1.2),
+			spread: 0.4,
+			speed: (0.2, 0.5),
+			color: (Color::rgb(1.0, 0.8, 0.3), Color::rgba(1.0, 0.4, 0.1, 0.0)),
+			size: (0.08, 0.02),
+			shape: Shape::Circle,
+			..Self::new(position)
+		}
+	}
+
+	pub fn particles(&self) -> &[Particle] {
+		&self.particles
+	}
+
+	/// How much of a particle it's built up toward spawning
+	pub fn owed(&self) -> f32 {
+		self.owed
+	}
+
+	/// Pick up where `particles` and `owed` left off
+	pub fn resume(&mut self, particles: Vec<Particle>, owed: f32) {
+		self.particles = particles;
+		self.owed = owed;
+	}
+
+	/// Whether it's done emitting and everything it emitted is gone
+	pub fn finished(&self) -> bool {
+		matches!(self.duration, Some(d) if d <= 0.0) && self.particles.is_empty()
+	}
+
+	fn spawn(&mut self, count: usize, rng: &mut Rng) {
+		for _ in 0..count {
+			let angle = self.direction + rng.between(-self.spread, self.spread);
+			let speed = rng.between(self.speed.0, self.speed.1);
+
+			self.particles.push(Particle {
+				position: self.position,
+				velocity: scene::rotate(Vec2::new(speed, 0.0), angle),
+				age: 0.0,
+				lifetime: rng.between(self.lifetime.0, self.lifetime.1),
+			});
+		}
+	}
+
+	fn step(&mut self, dt: f32, grid: &Grid, rng: &mut Rng) {
+		let emitting = match &mut self.duration {
+			Some(left) if *left <= 0.0 => false,
+			Some(left) => {
+				*left -= dt;
+				true
+			}
+			None => true,
+		};
+
+		if emitting {
+			self.owed += self.rate * dt;
+			let count = self.owed.floor();
+			self.owed -= count;
+			self.spawn(count as usize, rng);
+		}
+
+		for particle in &mut self.particles {
+			particle.age += dt;
+			particle.velocity += self.gravity * dt;
+
+			if self.collide {
+				bounce(particle, dt, grid);
+			} else {
+				particle.position += particle.velocity * dt;
+			}
+		}
+
+		self.particles.retain(|p| p.age < p.lifetime);
+	}
+}
+
+/// Move one axis at a time so a particle hitting a wall keeps sliding along
+/// it, and turns around on the axis it hit
+fn bounce(particle: &mut Particle, dt: f32, grid: &Grid) {
+	let solid = |p: Vec2| matches!(grid.get_coords(p), Some(coords) if grid.is_solid(coords));
+
+	let x = particle.position + Vec2::new(particle.velocity.x * dt, 0.0);
+	if solid(x) {
+		particle.velocity.x *= -BOUNCE;
+		particle.velocity.y *= BOUNCE;
+	} else {
+		particle.position = x;
+	}
+
+	let y = particle.position + Vec2::new(0.0, particle.velocity.y * dt);
+	if solid(y) {
+		particle.velocity.y *= -BOUNCE;
+		particle.velocity.x *= BOUNCE;
+	} else {
+		particle.position = y;
+	}
+}
+
+/// Every emitter in the world. They're stepped with the world so they come
+/// out the same every time, but nothing else looks at them.
+pub struct Particles {
+	emitters: Vec<Emitter>,
+	rng: Rng,
+}
+
+impl Particles {
+	pub fn new(seed: u32) -> Self {
+		Self {
+			emitters: vec![],
+			rng: Rng::new(seed),
+		}
+	}
+
+	pub fn emitters(&self) -> &[Emitter] {
+		&self.emitters
+	}
+
+	/// Where the random numbers are up to. Passing it to `new` goes on from
+	/// here.
+	pub fn seed(&self) -> u32 {
+		self.rng.0
+	}
+
+	/// Start an emitter going
+	pub fn add(&mut self, emitter: Emitter) {
+		self.emitters.push(emitter);
+	}
+
+	/// Let off `count` particles all at once, and nothing after
+	pub fn burst(&mut self, mut emitter: Emitter, count: usize) {
+		emitter.duration = Some(0.0);
+		emitter.spawn(count, &mut self.rng);
+		self.emitters.push(emitter);
+	}
+
+	/// Stop every emitter and get rid of its particles
+	pub fn clear(&mut self) {
+		self.emitters.clear();
+	}
+
+	/// Move emitters onto whatever they follow, and stop the ones whose
+	/// entity is gone. Particles already out stay where they are.
+	pub fn follow(&mut self, entities: &Entities) {
+		for emitter in &mut self.emitters {
+			let Some(entity) = emitter.follow else {
+				continue;
+			};
+
+			match entities.transforms.get(entity) {
+				Some(transform) => emitter.position = transform.position,
+				None => {
+					emitter.follow = None;
+					emitter.duration = Some(0.0);
+				}
+			}
+		}
+	}
+
+	/// How many particles are alive
+	pub fn len(&self) -> usize {
+		self.emitters.iter().map(|e| e.particles.len()).sum()
+	}
+
+	pub fn step(&mut self, dt: f32, grid: &Grid) {
+		for emitter in &mut self.emitters {
+			emitter.step(dt, grid, &mut self.rng);
+		}
+
+		self.emitters.retain(|e| !e.finished());
+	}
+
+	pub fn draw(&self, camera: &Camera) -> Vec<Command> {
+		let mut commands = vec![];
+
+		for emitter in &self.emitters {
+			for particle in &emitter.particles {
+				let t = (particle.age / particle.lifetime).clamp(0.0, 1.0);
+				let color = lerp_color(emitter.color.0, emitter.color.1, t);
+				let size = emitter.size.0 + (emitter.size.1 - emitter.size.0) * t;
+
+				commands.push(match emitter.shape {
+					Shape::Square => Command::Rect {
+						position: camera.view(particle.position),
+						size: camera.scale((size, size)),
+						draw: color.into(),
+					},
+					Shape::Circle => Command::Sdf(SignedDistance::Circle {
+						center: camera.view(particle.position),
+						radius: camera.pixels(size / 2.0).max(1),
+						color,
+					}),
+				});
+			}
+		}
+
+		commands
+	}
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+	let lerp = |a: f32, b: f32| a + (b - a) * t;
+
+	Color::rgba(
+		lerp(a.r, b.r),
+		lerp(a.g, b.g),
+		lerp(a.b, b.b),
+		lerp(a.a, b.a),
+	)
+}
+
+/// xorshift, so particles are random looking but the same every run
+#[derive(Clone, Debug)]
+struct Rng(u32);
+
+impl Rng {
+	fn new(seed: u32) -> Self {
+		// Zero would only ever give zero
+		Self(seed.max(1))
+	}
+
+	fn next(&mut self) -> u32 {
+		let mut x = self.0;
+		x ^= x << 13;
+		x ^= x >> 17;
+		x ^= x << 5;
+		self.0 = x;
+		x
+	}
+
+	/// Somewhere from `min` up to `max`
+	fn between(&mut self, min: f32, max: f32) -> f32 {
+		let t = (self.next() >> 8) as f32 / (1 << 24) as f32;
+		min + (max - min) * t
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::Vec2;
+
+	use super::{Emitter, Particles};
+	use crate::{
+		entity::{Entities, Transform},
+		grid::{Grid, Size, Tile},
+	};
+
+	#[test]
+	fn rate_and_lifetime() {
+		let grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		let mut particles = Particles::new(7);
+		particles.add(Emitter {
+			rate: 4.0,
+			duration: Some(2.0),
+			lifetime: (1.0, 1.0),
+			..Emitter::new((0.0, 0.0))
+		});
+
+		for _ in 0..3 {
+			particles.step(0.25, &grid);
+		}
+		assert_eq!(particles.len(), 3);
+
+		// A second in the oldest start dying off as fast as new ones come
+		for _ in 0..5 {
+			particles.step(0.25, &grid);
+		}
+		assert_eq!(particles.len(), 3);
+
+		// Spawning stops after two seconds, then the last ones die off
+		for _ in 0..4 {
+			particles.step(0.25, &grid);
+		}
+		assert_eq!(particles.len(), 0);
+		assert!(particles.emitters.is_empty());
+	}
+
+	#[test]
+	fn lands_on_solid_tiles() {
+		let mut grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		for x in 0..5 {
+			grid.set_tile(
+				Size {
+					width: x,
+					height: 0,
+				},
+				Some(Tile::Solid),
+			);
+		}
+
+		let mut particles = Particles::new(7);
+		particles.burst(
+			Emitter {
+				gravity: Vec2::new(0.0, -9.8),
+				lifetime: (2.0, 2.0),
+				collide: true,
+				..Emitter::new((0.0, 0.0))
+			},
+			20,
+		);
+
+		for _ in 0..100 {
+			particles.step(0.01, &grid);
+		}
+
+		// Fell, but not through the bottom row
+		let (bottom, _) = grid.bounds();
+		for particle in &particles.emitters[0].particles {
+			assert!(particle.position.y < 0.0);
+			assert!(particle.position.y >= bottom.y + 1.0);
+		}
+	}
+
+	#[test]
+	fn bursts_dont_keep_going() {
+		let grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		let mut particles = Particles::new(7);
+		particles.burst(
+			Emitter {
+				rate: 10.0,
+				lifetime: (0.5, 0.5),
+				..Emitter::new((0.0, 0.0))
+			},
+			5,
+		);
+
+		for _ in 0..3 {
+			particles.step(0.25, &grid);
+		}
+		assert_eq!(particles.len(), 0);
+		assert!(particles.emitters.is_empty());
+	}
+
+	#[test]
+	fn embers_follow_until_their_entity_goes() {
+		let grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		let mut entities = Entities::default();
+		let beacon = entities.spawn();
+		entities
+			.transforms
+			.insert(beacon, Transform::new((1.0, 0.0)));
+
+		let mut particles = Particles::new(7);
+		particles.add(Emitter::embers((0.0, 0.0), beacon));
+		particles.follow(&entities);
+		assert_eq!(particles.emitters[0].position, Vec2::new(1.0, 0.0));
+
+		for _ in 0..20 {
+			particles.follow(&entities);
+			particles.step(0.1, &grid);
+		}
+		assert!(particles.len() > 0);
+
+		// Whatever's out fades, then it's gone
+		entities.despawn(beacon);
+		particles.follow(&entities);
+		particles.step(0.1, &grid);
+		assert!(particles.len() > 0);
+		for _ in 0..20 {
+			particles.step(0.1, &grid);
+		}
+		assert!(particles.emitters.is_empty());
+	}
+}
@@ -25,6 +25,100 @@ where
 	a_bl.x < b_tr.x && a_tr.x > b_bl.x && a_bl.y < b_tr.y && a_tr.y > b_bl.y
 }
 
+/// How far to move `b` so it's no longer inside of `a`, or None if they
+/// don't overlap. It's pushed along whichever axis gets it out quickest.
+pub fn aabb_push_out<A, B>(a: &A, b: &B) -> Option<Vec2>
+where
+	A: AxisAlignedBoundingBox,
+	B: AxisAlignedBoundingBox,
+{
+	if !aabb_check(a, b) {
+		return None;
+	}
+
+	let a_bl = a.bottom_left();
+	let a_tr = a.top_right();
+
+	let b_bl = b.bottom_left();
+	let b_tr = b.top_right();
+
+	// Distance to move b in each direction to clear a
+	let right = a_tr.x - b_bl.x;
+	let left = b_tr.x - a_bl.x;
+	let up = a_tr.y - b_bl.y;
+	let down = b_tr.y - a_bl.y;
+
+	let x = if right < left { right } else { -left };
+	let y = if up < down { up } else { -down };
+
+	if x.abs() < y.abs() {
+		Some(Vec2::new(x, 0.0))
+	} else {
+		Some(Vec2::new(0.0, y))
+	}
+}
+
+/// Separating axis test for two convex shapes, given as their corners in
+/// order. Like `aabb_push_out` it's how far to move `b` out of `a`, along
+/// whichever edge normal gets it out quickest.
+pub fn sat_push_out(a: &[Vec2], b: &[Vec2]) -> Option<Vec2> {
+	let mut shortest: Option<(f32, Vec2)> = None;
+
+	for axis in normals(a).chain(normals(b)) {
+		let (a_min, a_max) = project(a, axis);
+		let (b_min, b_max) = project(b, axis);
+
+		let overlap = a_max.min(b_max) - a_min.max(b_min);
+		if overlap <= 0.0 {
+			return None;
+		}
+
+		let shallower = match shortest {
+			Some((depth, _)) => overlap < depth,
+			None => true,
+		};
+		if shallower {
+			shortest = Some((overlap, axis));
+		}
+	}
+
+	let (depth, axis) = shortest?;
+
+	// Push away from a's middle, not into it
+	let between = centroid(b) - centroid(a);
+	let sign = if dot(between, axis) < 0.0 { -1.0 } else { 1.0 };
+
+	Some(axis * (depth * sign))
+}
+
+/// The unit normal of every edge
+fn normals(corners: &[Vec2]) -> impl Iterator<Item = Vec2> + '_ {
+	corners.iter().enumerate().filter_map(move |(i, start)| {
+		let edge = corners[(i + 1) % corners.len()] - *start;
+		let length = (edge.x * edge.x + edge.y * edge.y).sqrt();
+
+		(length > TOLERANCE).then(|| Vec2::new(-edge.y / length, edge.x / length))
+	})
+}
+
+fn project(corners: &[Vec2], axis: Vec2) -> (f32, f32) {
+	corners
+		.iter()
+		.map(|c| dot(*c, axis))
+		.fold((f32::MAX, f32::MIN), |(min, max), d| {
+			(min.min(d), max.max(d))
+		})
+}
+
+fn centroid(corners: &[Vec2]) -> Vec2 {
+	let sum = corners.iter().fold(Vec2::ZERO, |sum, c| sum + *c);
+	sum / corners.len() as f32
+}
+
+fn dot(a: Vec2, b: Vec2) -> f32 {
+	a.x * b.x + a.y * b.y
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct LineSegment {
 	pub start: Vec2,
@@ -274,7 +368,7 @@ pub enum Intersection {
 mod test {
 	use smitten::Vec2;
 
-	use crate::physics::aabb_check;
+	use crate::physics::{aabb_check, aabb_push_out, sat_push_out};
 
 	use super::{AxisAlignedBoundingBox, LineSegment};
 
@@ -349,6 +443,58 @@ mod test {
 		assert!(!aabb_check(&a, &b))
 	}
 
+	#[test]
+	fn push_out_takes_the_short_way() {
+		let a = Thing {
+			center: Vec2::new(0.0, 0.0),
+			half_size: Vec2::new(2.0, 0.5),
+		};
+
+		// Sunk a little into the top
+		let b = Thing {
+			center: Vec2::new(1.0, 0.75),
+			half_size: Vec2::new(0.5, 0.5),
+		};
+
+		let push = aabb_push_out(&a, &b).unwrap();
+		assert_eq!((push.x, push.y), (0.0, 0.25));
+
+		// Poking out the left side
+		let c = Thing {
+			center: Vec2::new(-2.25, 0.0),
+			half_size: Vec2::new(0.5, 0.5),
+		};
+
+		let push = aabb_push_out(&a, &c).unwrap();
+		assert_eq!((push.x, push.y), (-0.25, 0.0));
+	}
+
+	#[test]
+	fn sat_sees_the_gap_aabbs_miss() {
+		let square = [
+			Vec2::new(0.0, 0.0),
+			Vec2::new(1.0, 0.0),
+			Vec2::new(1.0, 1.0),
+			Vec2::new(0.0, 1.0),
+		];
+
+		// A diamond whose bounding box overlaps the square's top right corner,
+		// but whose edge doesn't reach it
+		let diamond = [
+			Vec2::new(1.3, 0.8),
+			Vec2::new(1.8, 1.3),
+			Vec2::new(1.3, 1.8),
+			Vec2::new(0.8, 1.3),
+		];
+		assert!(sat_push_out(&square, &diamond).is_none());
+
+		// Moved down onto the corner it's pushed back out along its own edge
+		let sunk: Vec<Vec2> = diamond.iter().map(|c| *c - Vec2::new(0.3, 0.3)).collect();
+		let push = sat_push_out(&square, &sunk).unwrap();
+		assert!(push.x > 0.0 && push.y > 0.0);
+		assert!((push.x - push.y).abs() < 0.0001);
+	}
+
 	#[test]
 	fn slope_is_correct() {
 		let a = LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0));
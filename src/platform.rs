@@ -0,0 +1,341 @@
+use smitten::Vec2;
+
+use crate::{
+	debug,
+	entity::{Entities, Entity},
+	physics::AxisAlignedBoundingBox,
+	tween::Ease,
+};
+
+/// How close, in units, the bottom of something has to be to the top of a
+/// platform to count as standing on it.
+const RIDE_TOLERANCE: f32 = 0.01;
+
+/// What happens at the end of the waypoints
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Route {
+	/// Stop at the last waypoint
+	Once,
+	/// Turn around and go back, forever
+	PingPong,
+	/// Go from the last waypoint back to the first, forever
+	Loop,
+}
+
+/// How far along its path a `Kinematic` is
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Progress {
+	/// The waypoint it's coming from
+	pub from: usize,
+	/// The waypoint it's heading to
+	pub to: usize,
+	/// Units past `from`
+	pub distance: f32,
+	/// Whether a `Route::PingPong` is on its way out rather than back
+	pub forward: bool,
+}
+
+/// Something that moves along a path no matter what's in its way, like a
+/// platform. Anything else is pushed out of the way or carried along.
+#[derive(Clone, Debug)]
+pub struct Kinematic {
+	pub waypoints: Vec<Vec2>,
+	pub route: Route,
+	/// How speed changes between two waypoints
+	pub easing: Ease,
+	/// Units per second
+	pub speed: f32,
+
+	// We're `distance` units along the way from waypoint `from` to `to`
+	from: usize,
+	to: usize,
+	distance: f32,
+	forward: bool,
+}
+
+impl Kinematic {
+	/// Starts at the first waypoint, there has to be at least one
+	pub fn new(waypoints: Vec<Vec2>, route: Route, speed: f32) -> Self {
+		assert!(!waypoints.is_empty(), "a path needs a waypoint");
+
+		Self {
+			to: 1.min(waypoints.len() - 1),
+			waypoints,
+			route,
+			easing: Ease::Linear,
+			speed,
+
+			from: 0,
+			distance: 0.0,
+			forward: true,
+		}
+	}
+
+	pub fn with_easing(mut self, easing: Ease) -> Self {
+		self.easing = easing;
+		self
+	}
+
+	pub fn progress(&self) -> Progress {
+		Progress {
+			from: self.from,
+			to: self.to,
+			distance: self.distance,
+			forward: self.forward,
+		}
+	}
+
+	/// Pick up where `progress` left off. Waypoints that aren't on the path
+	/// are clamped to the last one.
+	pub fn set_progress(&mut self, progress: Progress) {
+		let last = self.waypoints.len() - 1;
+
+		self.from = progress.from.min(last);
+		self.to = progress.to.min(last);
+		self.distance = progress.distance;
+		self.forward = progress.forward;
+	}
+
+	fn segment_length(&self) -> f32 {
+		self.waypoints[self.from].distance_with(self.waypoints[self.to])
+	}
+
+	/// Where on the path we are
+	pub fn position(&self) -> Vec2 {
+		let (a, b) = (self.waypoints[self.from], self.waypoints[self.to]);
+		let length = self.segment_length();
+
+		if length == 0.0 {
+			return b;
+		}
+
+		let t = self.easing.apply((self.distance / length).clamp(0.0, 1.0));
+		a + (b - a) * t
+	}
+
+	/// Whether a `Route::Once` path has reached its end
+	pub fn finished(&self) -> bool {
+		self.route == Route::Once && self.to == self.from
+	}
+
+	/// Move `dt` seconds along the path
+	pub fn advance(&mut self, dt: f32) {
+		self.distance += self.speed * dt;
+
+		// Every waypoint gets one chance, so paths of nothing but the same
+		// point can't spin
+		for _ in 0..=self.waypoints.len() {
+			let length = self.segment_length();
+			if self.distance < length || self.finished() {
+				return;
+			}
+
+			self.distance -= length;
+			self.next_segment();
+		}
+	}
+
+	fn next_segment(&mut self) {
+		let last = self.waypoints.len() - 1;
+		self.from = self.to;
+
+		self.to = match self.route {
+			Route::Once => (self.from + 1).min(last),
+			Route::Loop => (self.from + 1) % self.waypoints.len(),
+			Route::PingPong if last == 0 => 0,
+			Route::PingPong => {
+				if self.forward && self.from == last {
+					self.forward = false;
+				} else if !self.forward && self.from == 0 {
+					self.forward = true;
+				}
+
+				if self.forward {
+					self.from + 1
+				} else {
+					self.from - 1
+				}
+			}
+		};
+
+		if self.finished() {
+			self.distance = 0.0;
+		}
+	}
+}
+
+/// Move every kinematic body along its path, taking along anything standing
+/// on it.
+pub fn move_kinematics(entities: &mut Entities, dt: f32) {
+	let platforms: Vec<Entity> = entities.kinematics.iter().map(|(e, _)| e).collect();
+
+	for platform in platforms {
+		let before = match entities.transforms.get(platform) {
+			Some(transform) => transform.position,
+			None => continue,
+		};
+		let riders = riders(entities, platform);
+
+		let kinematic = &mut entities.kinematics[platform];
+		kinematic.advance(dt);
+		let delta = kinematic.position() - before;
+
+		entities.transforms[platform].position += delta;
+		for rider in riders {
+			entities.transforms[rider].position += delta;
+		}
+	}
+}
+
+/// Push everything that's been moved into a kinematic body back out of it.
+/// Returns what was pushed and how far.
+pub fn push_out(entities: &mut Entities) -> Vec<(Entity, Vec2)> {
+	let mut pushed = vec![];
+	let platforms: Vec<Entity> = entities.kinematics.iter().map(|(e, _)| e).collect();
+
+	for platform in platforms {
+		let solid = match entities.thing(platform) {
+			Some(thing) => thing,
+			None => continue,
+		};
+
+		for dynamic in dynamics(entities) {
+			let thing = match entities.thing(dynamic) {
+				Some(thing) => thing,
+				None => continue,
+			};
+
+			if let Some(push) = solid.push_out(&thing) {
+				debug::contact(thing.center, push);
+				entities.transforms[dynamic].position += push;
+				pushed.push((dynamic, push));
+			}
+		}
+	}
+
+	pushed
+}
+
+/// Everything with a collider that moves by itself, not along a path or
+/// stuck to something else
+fn dynamics(entities: &Entities) -> Vec<Entity> {
+	entities
+		.colliders
+		.iter()
+		.map(|(e, _)| e)
+		.filter(|e| !entities.kinematics.contains(*e) && !entities.attachments.contains(*e))
+		.collect()
+}
+
+/// What's standing on top of `platform`
+fn riders(entities: &Entities, platform: Entity) -> Vec<Entity> {
+	let solid = match entities.thing(platform) {
+		Some(thing) => thing,
+		None => return vec![],
+	};
+	let (bl, tr) = (solid.bottom_left(), solid.top_right());
+
+	dynamics(entities)
+		.into_iter()
+		.filter(|rider| match entities.thing(*rider) {
+			Some(thing) => {
+				let (r_bl, r_tr) = (thing.bottom_left(), thing.top_right());
+
+				(r_bl.y - tr.y).abs() <= RIDE_TOLERANCE && r_bl.x < tr.x && r_tr.x > bl.x
+			}
+			None => false,
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::Vec2;
+
+	use super::{move_kinematics, push_out, Kinematic, Route};
+	use crate::{
+		entity::{Collider, Entities, Entity, Transform},
+		tween::Ease,
+	};
+
+	fn path(route: Route) -> Kinematic {
+		let waypoints = vec![
+			Vec2::new(0.0, 0.0),
+			Vec2::new(1.0, 0.0),
+			Vec2::new(1.0, 1.0),
+		];
+		Kinematic::new(waypoints, route, 1.0)
+	}
+
+	fn positions(route: Route, steps: usize) -> Vec<(f32, f32)> {
+		let mut path = path(route);
+
+		(0..steps)
+			.map(|_| {
+				path.advance(0.5);
+				(path.position().x, path.position().y)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn routes() {
+		assert_eq!(
+			positions(Route::Once, 5),
+			vec![(0.5, 0.0), (1.0, 0.0), (1.0, 0.5), (1.0, 1.0), (1.0, 1.0)]
+		);
+		assert_eq!(
+			positions(Route::PingPong, 7)[3..],
+			[(1.0, 1.0), (1.0, 0.5), (1.0, 0.0), (0.5, 0.0)]
+		);
+
+		// From the end back to the start is a segment too
+		let mut looped = path(Route::Loop);
+		looped.advance(2.0 + 2f32.sqrt());
+		assert!(looped.position().distance_with(Vec2::ZERO) < 0.0001);
+		looped.advance(0.5);
+		assert!(looped.position().distance_with(Vec2::new(0.5, 0.0)) < 0.0001);
+
+		let mut eased = path(Route::Once).with_easing(Ease::Smooth);
+		eased.advance(0.25);
+		assert!(eased.position().x < 0.25);
+	}
+
+	fn spawn(entities: &mut Entities, position: (f32, f32), size: (f32, f32)) -> Entity {
+		let e = entities.spawn();
+		entities.transforms.insert(e, Transform::new(position));
+		entities.colliders.insert(
+			e,
+			Collider {
+				size: Vec2::new(size.0, size.1),
+			},
+		);
+		e
+	}
+
+	#[test]
+	fn carries_and_pushes() {
+		let mut entities = Entities::default();
+		let platform = spawn(&mut entities, (0.0, 0.0), (3.0, 1.0));
+		entities.kinematics.insert(
+			platform,
+			Kinematic::new(
+				vec![Vec2::new(0.0, 0.0), Vec2::new(0.0, 10.0)],
+				Route::Once,
+				1.0,
+			),
+		);
+
+		let rider = spawn(&mut entities, (1.0, 1.0), (1.0, 1.0));
+		// Right above, but not quite standing on it
+		let above = spawn(&mut entities, (-1.0, 1.25), (1.0, 1.0));
+
+		move_kinematics(&mut entities, 0.5);
+		push_out(&mut entities);
+
+		assert_eq!(entities.transforms[rider].position.y, 1.5);
+		assert_eq!(entities.transforms[rider].position.x, 1.0);
+		// Wasn't carried, but got pushed up out of the way
+		assert_eq!(entities.transforms[above].position.y, 1.5);
+	}
+}
@@ -0,0 +1,673 @@
+use smitten::{Color, Draw, SignedDistance, Smitten, TextureId, Vec2};
+
+use crate::{
+	atlas::{Atlas, Sprite},
+	autotile::Tileset,
+	canvas::Canvas,
+	font,
+	thing::Thing,
+};
+
+/// What a sprite that can't be found is drawn with
+pub const MISSING: Color = Color {
+	r: 1.0,
+	g: 0.0,
+	b: 1.0,
+	a: 1.0,
+};
+
+/// One thing to draw. Positions are already where they go on screen, in units
+/// from the middle of it, like smitten takes them.
+#[derive(Clone, Debug)]
+pub enum Command {
+	Clear,
+	Rect {
+		position: Vec2,
+		size: Vec2,
+		draw: Draw,
+	},
+	/// Part of a texture, see `Atlas::uv`
+	RectUv {
+		position: Vec2,
+		size: Vec2,
+		texture: TextureId,
+		uv: (Vec2, Vec2),
+	},
+	/// Turned `rotation` radians counterclockwise around its center
+	RotatedRect {
+		position: Vec2,
+		size: Vec2,
+		rotation: f32,
+		draw: Draw,
+	},
+	/// Part of a texture, turned like `RotatedRect`
+	RotatedRectUv {
+		position: Vec2,
+		size: Vec2,
+		rotation: f32,
+		texture: TextureId,
+		uv: (Vec2, Vec2),
+	},
+	Sdf(SignedDistance),
+	/// `position` is the top left of the first line and `size` is the height
+	/// of a line, in units
+	Text {
+		position: Vec2,
+		size: f32,
+		text: String,
+		color: Color,
+	},
+}
+
+/// Something that can draw. The game only ever draws through this so it can
+/// be pointed at a window, an image or a list.
+pub trait Renderer {
+	fn clear(&mut self);
+	fn rect(&mut self, position: Vec2, size: Vec2, draw: Draw);
+	fn rect_uv(&mut self, position: Vec2, size: Vec2, texture: TextureId, uv: (Vec2, Vec2));
+	fn sdf(&mut self, sdf: SignedDistance);
+
+	/// Turned `rotation` radians counterclockwise around its center
+	fn rect_rotated(&mut self, position: Vec2, size: Vec2, rotation: f32, draw: Draw);
+
+	/// Part of a texture, turned like `rect_rotated`
+	fn rect_uv_rotated(
+		&mut self,
+		position: Vec2,
+		size: Vec2,
+		rotation: f32,
+		texture: TextureId,
+		uv: (Vec2, Vec2),
+	);
+
+	/// Drawn with the bitmap font, a rect for every run of pixels
+	fn text(&mut self, position: Vec2, size: f32, text: &str, color: Color) {
+		for (center, rect) in font::layout(position, size, text) {
+			self.rect(center, rect, color.into());
+		}
+	}
+
+	fn draw(&mut self, command: &Command) {
+		match command {
+			Command::Clear => self.clear(),
+			Command::Rect {
+				position,
+				size,
+				draw,
+			} => self.rect(*position, *size, *draw),
+			Command::RectUv {
+				position,
+				size,
+				texture,
+				uv,
+			} => self.rect_uv(*position, *size, *texture, *uv),
+			Command::RotatedRect {
+				position,
+				size,
+				rotation,
+				draw,
+			} => self.rect_rotated(*position, *size, *rotation, *draw),
+			Command::RotatedRectUv {
+				position,
+				size,
+				rotation,
+				texture,
+				uv,
+			} => self.rect_uv_rotated(*position, *size, *rotation, *texture, *uv),
+			Command::Sdf(sdf) => self.sdf(*sdf),
+			Command::Text {
+				position,
+				size,
+				text,
+				color,
+			} => self.text(*position, *size, text, *color),
+		}
+	}
+
+	fn draw_all(&mut self, commands: &[Command]) {
+		for command in commands {
+			self.draw(command);
+		}
+	}
+}
+
+impl Renderer for Smitten {
+	fn clear(&mut self) {
+		Smitten::clear(self);
+	}
+
+	fn rect(&mut self, position: Vec2, size: Vec2, draw: Draw) {
+		Smitten::rect(self, position, size, draw);
+	}
+
+	fn rect_uv(&mut self, position: Vec2, size: Vec2, texture: TextureId, uv: (Vec2, Vec2)) {
+		Smitten::rect_uv(self, position, size, texture, uv);
+	}
+
+	fn sdf(&mut self, sdf: SignedDistance) {
+		Smitten::sdf(self, sdf);
+	}
+
+	/// Smitten can't turn rects, but it can draw a line at any angle
+	fn rect_rotated(&mut self, position: Vec2, size: Vec2, rotation: f32, draw: Draw) {
+		match draw {
+			Draw::Color(color) => {
+				let bar = bar(position, size, rotation, color, crate::PIXELS_PER_UNIT);
+				Smitten::sdf(self, bar);
+			}
+			Draw::Texture(texture) => {
+				let full = (Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0));
+				self.rect_uv_rotated(position, size, rotation, texture, full);
+			}
+		}
+	}
+
+	/// A line can't carry a texture though, so these are drawn straight
+	fn rect_uv_rotated(
+		&mut self,
+		position: Vec2,
+		size: Vec2,
+		_rotation: f32,
+		texture: TextureId,
+		uv: (Vec2, Vec2),
+	) {
+		Smitten::rect_uv(self, position, size, texture, uv);
+	}
+}
+
+impl Renderer for Canvas {
+	fn clear(&mut self) {
+		Canvas::clear(self);
+	}
+
+	fn rect(&mut self, position: Vec2, size: Vec2, draw: Draw) {
+		Canvas::rect(self, position, size, draw);
+	}
+
+	fn rect_uv(&mut self, position: Vec2, size: Vec2, texture: TextureId, uv: (Vec2, Vec2)) {
+		Canvas::rect_uv(self, position, size, texture, uv);
+	}
+
+	fn sdf(&mut self, sdf: SignedDistance) {
+		Canvas::sdf(self, sdf);
+	}
+
+	fn rect_rotated(&mut self, position: Vec2, size: Vec2, rotation: f32, draw: Draw) {
+		Canvas::rect_rotated(self, position, size, rotation, draw);
+	}
+
+	fn rect_uv_rotated(
+		&mut self,
+		position: Vec2,
+		size: Vec2,
+		rotation: f32,
+		texture: TextureId,
+		uv: (Vec2, Vec2),
+	) {
+		Canvas::rect_uv_rotated(self, position, size, rotation, texture, uv);
+	}
+}
+
+/// A turned rect as one line down its middle, as thick as the rect is
+/// narrow. The ends are pulled in so the round caps stay inside, which only
+/// rounds off the corners.
+fn bar(
+	position: Vec2,
+	size: Vec2,
+	rotation: f32,
+	color: Color,
+	pixels_per_unit: u32,
+) -> SignedDistance {
+	let thing = Thing {
+		rotation,
+		..Thing::new(position, size)
+	};
+	let [bl, br, tr, tl] = thing.corners();
+	let middle = |a: Vec2, b: Vec2| (a + b) / 2.0;
+
+	let (start, end, width) = if size.x >= size.y {
+		(middle(bl, tl), middle(br, tr), size.y)
+	} else {
+		(middle(bl, br), middle(tl, tr), size.x)
+	};
+	let length = start.distance_with(end);
+	let inset = if length > 0.0 {
+		(end - start) * (width.min(length) / 2.0 / length)
+	} else {
+		Vec2::ZERO
+	};
+
+	SignedDistance::LineSegment {
+		start: start + inset,
+		end: end - inset,
+		thickness: ((width * pixels_per_unit as f32).round() as u32).max(1),
+		color,
+	}
+}
+
+/// Keeps everything it's asked to draw instead of drawing it, for tests and
+/// for writing frames out as SVG.
+#[derive(Default)]
+pub struct Recorder {
+	pub commands: Vec<Command>,
+}
+
+impl Renderer for Recorder {
+	fn clear(&mut self) {
+		self.commands.clear();
+		self.commands.push(Command::Clear);
+	}
+
+	fn rect(&mut self, position: Vec2, size: Vec2, draw: Draw) {
+		self.commands.push(Command::Rect {
+			position,
+			size,
+			draw,
+		});
+	}
+
+	fn rect_uv(&mut self, position: Vec2, size: Vec2, texture: TextureId, uv: (Vec2, Vec2)) {
+		self.commands.push(Command::RectUv {
+			position,
+			size,
+			texture,
+			uv,
+		});
+	}
+
+	fn sdf(&mut self, sdf: SignedDistance) {
+		self.commands.push(Command::Sdf(sdf));
+	}
+
+	fn rect_rotated(&mut self, position: Vec2, size: Vec2, rotation: f32, draw: Draw) {
+		self.commands.push(Command::RotatedRect {
+			position,
+			size,
+			rotation,
+			draw,
+		});
+	}
+
+	fn rect_uv_rotated(
+		&mut self,
+		position: Vec2,
+		size: Vec2,
+		rotation: f32,
+		texture: TextureId,
+		uv: (Vec2, Vec2),
+	) {
+		self.commands.push(Command::RotatedRectUv {
+			position,
+			size,
+			rotation,
+			texture,
+			uv,
+		});
+	}
+
+	fn text(&mut self, position: Vec2, size: f32, text: &str, color: Color) {
+		self.commands.push(Command::Text {
+			position,
+			size,
+			text: text.to_owned(),
+			color,
+		});
+	}
+}
+
+impl Recorder {
+	/// Write what was recorded as an SVG the size of the window. Textures
+	/// aren't embedded, they show up as grey boxes.
+	pub fn svg(&self, dimensions: (u32, u32), pixels_per_unit: u32) -> String {
+		let ppu = pixels_per_unit as f32;
+		let (width, height) = (dimensions.0 as f32, dimensions.1 as f32);
+		let x = |v: f32| v * ppu + width / 2.0;
+		let y = |v: f32| height / 2.0 - v * ppu;
+
+		let mut svg = format!(
+			"<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+			dimensions.0, dimensions.1
+		);
+
+		let rect = |position: Vec2, size: Vec2, rotation: f32, fill: String| {
+			// SVG turns clockwise, with y going down
+			let transform = if rotation == 0.0 {
+				String::new()
+			} else {
+				format!(
+					" transform=\"rotate({} {} {})\"",
+					-rotation.to_degrees(),
+					x(position.x),
+					y(position.y)
+				)
+			};
+
+			format!(
+				"<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"{}/>\n",
+				x(position.x - size.x / 2.0),
+				y(position.y + size.y / 2.0),
+				size.x * ppu,
+				size.y * ppu,
+				fill,
+				transform
+			)
+		};
+
+		for command in &self.commands {
+			let element = match command {
+				Command::Clear => rect(
+					Vec2::ZERO,
+					Vec2::new(width / ppu, height / ppu),
+					0.0,
+					svg_color(Color::rgb(0.0, 0.0, 0.0)),
+				),
+				Command::Rect {
+					position,
+					size,
+					draw: Draw::Color(color),
+				} => rect(*position, *size, 0.0, svg_color(*color)),
+				Command::Rect { position, size, .. } | Command::RectUv { position, size, .. } => {
+					rect(*position, *size, 0.0, svg_color(Color::rgb(0.5, 0.5, 0.5)))
+				}
+				Command::RotatedRect {
+					position,
+					size,
+					rotation,
+					draw,
+				} => {
+					let fill = match draw {
+						Draw::Color(color) => *color,
+						Draw::Texture(_) => Color::rgb(0.5, 0.5, 0.5),
+					};
+
+					rect(*position, *size, *rotation, svg_color(fill))
+				}
+				Command::RotatedRectUv {
+					position,
+					size,
+					rotation,
+					..
+				} => rect(
+					*position,
+					*size,
+					*rotation,
+					svg_color(Color::rgb(0.5, 0.5, 0.5)),
+				),
+				Command::Sdf(SignedDistance::LineSegment {
+					start,
+					end,
+					thickness,
+					color,
+				}) => format!(
+					"<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+					x(start.x),
+					y(start.y),
+					x(end.x),
+					y(end.y),
+					svg_color(*color),
+					thickness
+				),
+				Command::Sdf(SignedDistance::Circle {
+					center,
+					radius,
+					color,
+				}) => format!(
+					"<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+					x(center.x),
+					y(center.y),
+					radius,
+					svg_color(*color)
+				),
+				Command::Text {
+					position,
+					size,
+					text,
+					color,
+				} => format!(
+					"<text x=\"{}\" y=\"{}\" font-size=\"{}\" fill=\"{}\">{}</text>\n",
+					x(position.x),
+					// SVG puts text on its baseline, not its top
+					y(position.y - size * font::HEIGHT as f32 / (font::HEIGHT + 1) as f32),
+					size * ppu,
+					svg_color(*color),
+					text.replace('&', "&amp;").replace('<', "&lt;")
+				),
+			};
+
+			svg.push_str(&element);
+		}
+
+		svg.push_str("</svg>\n");
+		svg
+	}
+}
+
+fn svg_color(color: Color) -> String {
+	let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+	format!(
+		"rgba({}, {}, {}, {})",
+		byte(color.r),
+		byte(color.g),
+		byte(color.b),
+		color.a
+	)
+}
+
+/// What the world is drawn with
+pub struct Assets {
+	pub atlas: Option<(Atlas, TextureId)>,
+	pub tileset: Tileset<Sprite>,
+}
+
+impl Assets {
+	/// Use `new` wherever `old` was, after a texture's been loaded again
+	pub fn replace_texture(&mut self, old: TextureId, new: TextureId) {
+		if let Some((_, sheet)) = self.atlas.as_mut() {
+			if *sheet == old {
+				*sheet = new;
+			}
+		}
+
+		self.tileset.map(|sprite| sprite.replace_texture(old, new));
+	}
+
+	/// Draw a sprite, looking it up in the atlas if it's a region. A region
+	/// the atlas doesn't have, or with no atlas at all, is drawn bright pink.
+	pub fn sprite(&self, position: Vec2, size: Vec2, sprite: Sprite) -> Command {
+		let region = |id| {
+			let (atlas, sheet) = self.atlas.as_ref()?;
+			Some((*sheet, atlas.uv(id)?))
+		};
+
+		match sprite {
+			Sprite::Draw(draw) => Command::Rect {
+				position,
+				size,
+				draw,
+			},
+			Sprite::Region(id) => match region(id) {
+				Some((texture, uv)) => Command::RectUv {
+					position,
+					size,
+					texture,
+					uv,
+				},
+				None => Command::Rect {
+					position,
+					size,
+					draw: MISSING.into(),
+				},
+			},
+		}
+	}
+
+	/// Draw a sprite turned around its center
+	pub fn rotated_sprite(
+		&self,
+		position: Vec2,
+		size: Vec2,
+		rotation: f32,
+		sprite: Sprite,
+	) -> Command {
+		if rotation == 0.0 {
+			return self.sprite(position, size, sprite);
+		}
+
+		match self.sprite(position, size, sprite) {
+			Command::Rect {
+				position,
+				size,
+				draw,
+			} => Command::RotatedRect {
+				position,
+				size,
+				rotation,
+				draw,
+			},
+			Command::RectUv {
+				position,
+				size,
+				texture,
+				uv,
+			} => Command::RotatedRectUv {
+				position,
+				size,
+				rotation,
+				texture,
+				uv,
+			},
+			command => command,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::{Color, Draw, Vec2};
+
+	use smitten::SignedDistance;
+
+	use super::{bar, Assets, Command, Recorder, Renderer};
+	use crate::{
+		atlas::{RegionId, Sprite},
+		autotile::{Mode, Tileset},
+		camera::Camera,
+		canvas::Canvas,
+		grid::{Grid, Size, Tile},
+		world::World,
+	};
+
+	fn world() -> (World, Camera, Assets) {
+		let mut grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		for x in 0..3 {
+			grid.set_tile(
+				Size {
+					width: x,
+					height: 0,
+				},
+				Some(Tile::Solid),
+			);
+		}
+
+		let world = World::new(grid, (-0.5, 1.25));
+		let camera = Camera::new((120, 90), 16);
+		let assets = Assets {
+			atlas: None,
+			tileset: Tileset::new(Mode::FourBit, Sprite::from(Color::rgb(0.6, 0.6, 0.7))),
+		};
+
+		(world, camera, assets)
+	}
+
+	#[test]
+	fn world_draws_back_to_front() {
+		let (world, camera, assets) = world();
+		let mut recorder = Recorder::default();
+		recorder.draw_all(&world.draw(&camera, &assets, None));
+
+		assert!(matches!(recorder.commands.first(), Some(Command::Clear)));
+		// The player is on top of everything
+		assert!(matches!(
+			recorder.commands.last(),
+			Some(Command::Rect {
+				draw: Draw::Color(_),
+				..
+			})
+		));
+
+		// A tile and its light overlay each, and a line for every cell edge
+		let svg = recorder.svg((120, 90), 16);
+		assert_eq!(svg.matches("<rect").count(), 1 + 3 * 2 + 1);
+		assert_eq!(svg.matches("<line").count(), 12);
+		assert_eq!(svg.matches("<circle").count(), 1);
+	}
+
+	#[test]
+	fn canvas_draws_the_world() {
+		let (world, camera, assets) = world();
+		let mut canvas = Canvas::new((120, 90), 16);
+		canvas.draw_all(&world.draw(&camera, &assets, None));
+
+		// The middle of the bottom left tile, shaded by its light overlay so
+		// it's darker than the tile color but not black
+		let pixel = canvas.image.get_pixel(28, 77);
+		assert!(pixel.0[2] > 0 && pixel.0[2] < 178);
+	}
+
+	#[test]
+	fn turned_rects_are_one_bar() {
+		// Two wide and turned a quarter, so it's two tall
+		let white = Color::rgb(1.0, 1.0, 1.0);
+		let bar = bar(
+			Vec2::ZERO,
+			Vec2::new(2.0, 0.5),
+			std::f32::consts::FRAC_PI_2,
+			white,
+			16,
+		);
+		match bar {
+			SignedDistance::LineSegment {
+				start,
+				end,
+				thickness,
+				..
+			} => {
+				assert!(start.x.abs() < 0.0001 && (start.y + 0.75).abs() < 0.0001);
+				assert!(end.x.abs() < 0.0001 && (end.y - 0.75).abs() < 0.0001);
+				assert_eq!(thickness, 8);
+			}
+			other => panic!("expected a line, got {:?}", other),
+		}
+
+		// Eight pixels wide and thirty two tall around the middle. Every edge
+		// goes right up to where it should and no further.
+		let mut canvas = Canvas::new((120, 90), 16);
+		canvas.clear();
+		canvas.sdf(bar);
+		let lit = |x: u32, y: u32| canvas.image.get_pixel(x, y).0[0] == 255;
+		assert!(lit(56, 45) && !lit(55, 45));
+		assert!(lit(63, 45) && !lit(64, 45));
+		assert!(lit(60, 29) && !lit(60, 28));
+		assert!(lit(60, 60) && !lit(60, 61));
+	}
+
+	#[test]
+	fn missing_regions_are_pink() {
+		let (_, _, assets) = world();
+		let region = Sprite::Region(RegionId::from_index(3));
+
+		assert!(matches!(
+			assets.sprite(Vec2::ZERO, Vec2::new(1.0, 1.0), region),
+			Command::Rect {
+				draw: Draw::Color(Color {
+					r: 1.0,
+					g: 0.0,
+					b: 1.0,
+					..
+				}),
+				..
+			}
+		));
+	}
+}
@@ -0,0 +1,430 @@
+use std::{collections::HashSet, fmt, fs, io, path::Path};
+
+use smitten::Vec2;
+
+use crate::{
+	grid::Size,
+	input::{Action, ActionState, Axis, Bindings, Opposing},
+	level::{self, LevelError},
+	world::World,
+};
+
+/// The input of a single step
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+	pub down: Vec<Action>,
+	pub cursor: Option<Size>,
+	/// Analog axes that weren't at zero
+	pub analog: Vec<(Axis, f32)>,
+	/// The world didn't step, like under the pause menu. The input still
+	/// counts, what was pressed then isn't pressed again afterwards.
+	pub paused: bool,
+}
+
+/// Every step's input from the moment a world was made, along with the level
+/// it started in, so it can be played back exactly. They're saved like this:
+///
+/// ```text
+/// start -3 -3
+/// end -1.5 -3
+/// axis Horizontal MoveLeft MoveRight
+/// axis Vertical MoveDown MoveUp
+/// opposing LastPressed
+/// frames
+/// MoveRight
+/// MoveRight PlaceTile
+/// .
+/// ~ Pause
+/// Horizontal=0.5
+/// EditorPlace @2,3
+/// level
+/// size 5 5
+/// ...
+/// ```
+///
+/// Each frame is one line of the actions that were held, or a `.` if there
+/// were none, then any analog axes that were pushed, and the cursor's cell if
+/// it was over the grid. Frames where the world didn't step start with `~`.
+/// The axes and `opposing` are the bindings' at the time,
+/// since they change which way the actions move. Everything after
+/// `level` is the level as `level::serialize` writes it.
+pub struct Recording {
+	pub level: String,
+	pub start: Vec2,
+	/// Where the player was when recording stopped
+	pub end: Option<Vec2>,
+	/// Only the axes and `opposing` are used, which inputs were bound
+	/// doesn't matter once we know the actions
+	pub bindings: Bindings,
+	pub frames: Vec<Frame>,
+}
+
+impl Recording {
+	pub fn new(world: &World, bindings: &Bindings) -> Self {
+		Self {
+			level: level::serialize(&world.grid),
+			start: world.player_position(),
+			end: None,
+			bindings: bindings.clone(),
+			frames: vec![],
+		}
+	}
+
+	/// Remember this step's input. Call it every fixed step, whether or not
+	/// the world steps, then `stepped` if it does.
+	pub fn record(&mut self, input: &ActionState, cursor: Option<Size>) {
+		let mut down: Vec<Action> = input.down().collect();
+		down.sort_by_key(|action| action.name());
+
+		let analog = Axis::all()
+			.map(|axis| (axis, input.analog(axis)))
+			.filter(|(_, value)| *value != 0.0)
+			.collect();
+
+		self.frames.push(Frame {
+			down,
+			cursor,
+			analog,
+			paused: true,
+		});
+	}
+
+	/// The world stepped with the input that was just recorded
+	pub fn stepped(&mut self) {
+		if let Some(frame) = self.frames.last_mut() {
+			frame.paused = false;
+		}
+	}
+
+	pub fn finish(&mut self, world: &World) {
+		self.end = Some(world.player_position());
+	}
+
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError> {
+		Self::parse(&fs::read_to_string(path)?)
+	}
+
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+		fs::write(path, self.serialize())
+	}
+
+	pub fn serialize(&self) -> String {
+		// f32's Display is the shortest string that parses back to the exact
+		// same value, which is what keeps the positions comparable.
+		let mut string = format!("start {} {}\n", self.start.x, self.start.y);
+		if let Some(end) = self.end {
+			string.push_str(&format!("end {} {}\n", end.x, end.y));
+		}
+		for axis in Axis::all() {
+			let (negative, positive) = self.bindings.axis(axis);
+			string.push_str(&format!(
+				"axis {} {} {}\n",
+				axis.name(),
+				negative.name(),
+				positive.name()
+			));
+		}
+		string.push_str(&format!("opposing {}\n", self.bindings.opposing.name()));
+
+		string.push_str("frames\n");
+		for frame in &self.frames {
+			let mut words: Vec<String> = frame.paused.then(|| "~".to_owned()).into_iter().collect();
+			words.extend(frame.down.iter().map(|a| a.name().to_owned()));
+			for (axis, value) in &frame.analog {
+				words.push(format!("{}={}", axis.name(), value));
+			}
+			if let Some(cell) = frame.cursor {
+				words.push(format!("@{},{}", cell.width, cell.height));
+			}
+
+			if words.is_empty() {
+				string.push_str(".\n");
+			} else {
+				string.push_str(&words.join(" "));
+				string.push('\n');
+			}
+		}
+
+		string.push_str("level\n");
+		string.push_str(&self.level);
+		string
+	}
+
+	pub fn parse(string: &str) -> Result<Self, ReplayError> {
+		let mut start = None;
+		let mut end = None;
+		let mut bindings = Bindings::default();
+		let mut frames = None;
+		let mut level = None;
+
+		let mut lines = string.lines().enumerate();
+		while let Some((idx, line)) = lines.next() {
+			let line_number = idx + 1;
+			let mut words = line.split_whitespace();
+
+			match words.next() {
+				None => (),
+				Some("start") => start = Some(parse_position(words, line_number)?),
+				Some("end") => end = Some(parse_position(words, line_number)?),
+				Some("axis") => {
+					let axis = words.next().and_then(Axis::from_name);
+					let actions: Option<Vec<Action>> = words.map(Action::from_name).collect();
+
+					match (axis, actions.as_deref()) {
+						(Some(axis), Some(&[negative, positive])) => {
+							bindings.bind_axis(axis, negative, positive)
+						}
+						_ => return Err(ReplayError::Syntax(line_number)),
+					}
+				}
+				Some("opposing") => {
+					bindings.opposing = words
+						.next()
+						.and_then(Opposing::from_name)
+						.ok_or(ReplayError::Syntax(line_number))?;
+				}
+				Some("frames") => frames = Some(vec![]),
+				Some("level") => {
+					let rest: Vec<&str> = lines.by_ref().map(|(_, l)| l).collect();
+					level = Some(rest.join("\n") + "\n");
+				}
+				Some(_) => match frames.as_mut() {
+					Some(frames) => frames.push(parse_frame(line, line_number)?),
+					None => return Err(ReplayError::Syntax(line_number)),
+				},
+			}
+		}
+
+		let level = level.ok_or(ReplayError::Missing("level"))?;
+		// Make sure it loads now instead of when it's replayed
+		level::parse(&level)?;
+
+		Ok(Self {
+			level,
+			start: start.ok_or(ReplayError::Missing("start"))?,
+			end,
+			bindings,
+			frames: frames.ok_or(ReplayError::Missing("frames"))?,
+		})
+	}
+
+	/// Step a fresh world through every frame
+	pub fn replay(&self) -> Result<World, ReplayError> {
+		let mut world = World::new(level::parse(&self.level)?, self.start);
+		let mut input = ActionState::new(self.bindings.clone());
+
+		for frame in &self.frames {
+			input.set_down(frame.down.iter().copied().collect::<HashSet<Action>>());
+			for axis in Axis::all() {
+				let value = frame.analog.iter().find(|(a, _)| *a == axis);
+				input.set_analog(axis, value.map_or(0.0, |(_, v)| *v));
+			}
+			if !frame.paused {
+				world.step(&input, frame.cursor);
+			}
+		}
+
+		Ok(world)
+	}
+
+	/// Replay and make sure the player ends up exactly where they did when
+	/// this was recorded. Returns where that is.
+	pub fn check(&self) -> Result<Vec2, ReplayError> {
+		let expected = self.end.ok_or(ReplayError::Missing("end"))?;
+		let actual = self.replay()?.player_position();
+
+		if actual.x == expected.x && actual.y == expected.y {
+			Ok(actual)
+		} else {
+			Err(ReplayError::Diverged { expected, actual })
+		}
+	}
+}
+
+fn parse_position<'a, I: Iterator<Item = &'a str>>(
+	words: I,
+	line: usize,
+) -> Result<Vec2, ReplayError> {
+	let values = words
+		.map(|w| w.parse())
+		.collect::<Result<Vec<f32>, _>>()
+		.map_err(|_| ReplayError::Syntax(line))?;
+
+	match values[..] {
+		[x, y] => Ok(Vec2::new(x, y)),
+		_ => Err(ReplayError::Syntax(line)),
+	}
+}
+
+fn parse_frame(string: &str, line: usize) -> Result<Frame, ReplayError> {
+	let mut frame = Frame::default();
+
+	for word in string.split_whitespace() {
+		if word == "." {
+			continue;
+		} else if word == "~" {
+			frame.paused = true;
+		} else if let Some((axis, value)) = word.split_once('=') {
+			let axis = Axis::from_name(axis).ok_or(ReplayError::Syntax(line))?;
+			let value = value.parse().map_err(|_| ReplayError::Syntax(line))?;
+
+			frame.analog.push((axis, value));
+		} else if let Some(cell) = word.strip_prefix('@') {
+			let (x, y) = cell
+				.split_once(',')
+				.and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+				.ok_or(ReplayError::Syntax(line))?;
+
+			frame.cursor = Some(Size {
+				width: x,
+				height: y,
+			});
+		} else {
+			let action = Action::from_name(word).ok_or(ReplayError::UnknownAction(line))?;
+			frame.down.push(action);
+		}
+	}
+
+	Ok(frame)
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+	Io(io::Error),
+	Level(LevelError),
+	Missing(&'static str),
+	Syntax(usize),
+	UnknownAction(usize),
+	Diverged { expected: Vec2, actual: Vec2 },
+}
+
+impl From<io::Error> for ReplayError {
+	fn from(e: io::Error) -> Self {
+		ReplayError::Io(e)
+	}
+}
+
+impl From<LevelError> for ReplayError {
+	fn from(e: LevelError) -> Self {
+		ReplayError::Level(e)
+	}
+}
+
+impl fmt::Display for ReplayError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ReplayError::Io(e) => write!(f, "couldn't read recording: {}", e),
+			ReplayError::Level(e) => write!(f, "bad level: {}", e),
+			ReplayError::Missing(what) => write!(f, "expected a '{}' line", what),
+			ReplayError::Syntax(line) => write!(f, "line {}: couldn't parse", line),
+			ReplayError::UnknownAction(line) => write!(f, "line {}: unknown action", line),
+			ReplayError::Diverged { expected, actual } => write!(
+				f,
+				"ended at {} {} instead of {} {}",
+				actual.x, actual.y, expected.x, expected.y
+			),
+		}
+	}
+}
+
+impl std::error::Error for ReplayError {}
+
+#[cfg(test)]
+mod test {
+	use smitten::Vec2;
+
+	use super::{Frame, Recording, ReplayError};
+	use crate::{
+		grid::{Grid, Size},
+		input::{Action, ActionState, Axis, Bindings},
+		world::World,
+	};
+
+	fn recording() -> Recording {
+		let world = World::new(Grid::new((5, 5), (0.0, 0.0), 1.0), (-2.0, -2.0));
+		let mut recording = Recording::new(&world, &Bindings::default());
+
+		let walk = Frame {
+			down: vec![Action::MoveRight, Action::MoveUp],
+			..Frame::default()
+		};
+		recording.frames.extend(vec![walk; 20]);
+		// A stick pushed halfway, on top of the keys
+		let creep = Frame {
+			down: vec![Action::MoveRight],
+			analog: vec![(Axis::Horizontal, -0.5)],
+			..Frame::default()
+		};
+		recording.frames.extend(vec![creep; 4]);
+		recording.frames.push(Frame {
+			down: vec![Action::PlaceTile],
+			cursor: Some(Size {
+				width: 1,
+				height: 1,
+			}),
+			..Frame::default()
+		});
+		recording.frames.push(Frame {
+			down: vec![Action::Pause],
+			paused: true,
+			..Frame::default()
+		});
+		recording.frames.push(Frame::default());
+
+		recording
+	}
+
+	#[test]
+	fn round_trip() {
+		let recording = recording();
+		let parsed = Recording::parse(&recording.serialize()).unwrap();
+
+		assert_eq!(parsed.frames, recording.frames);
+		assert_eq!(parsed.level, recording.level);
+		assert_eq!(parsed.serialize(), recording.serialize());
+	}
+
+	#[test]
+	fn records_analog() {
+		let world = World::new(Grid::new((5, 5), (0.0, 0.0), 1.0), (-2.0, -2.0));
+		let mut recording = Recording::new(&world, &Bindings::default());
+		let mut input = ActionState::new(Bindings::default());
+
+		input.set_analog(Axis::Vertical, 0.25);
+		recording.record(&input, None);
+		assert_eq!(recording.frames[0].analog, vec![(Axis::Vertical, 0.25)]);
+		assert!(recording.frames[0].paused);
+
+		recording.stepped();
+		assert!(!recording.frames[0].paused);
+	}
+
+	#[test]
+	fn replays_exactly() {
+		let mut recording = recording();
+		let world = recording.replay().unwrap();
+		assert!(world
+			.grid
+			.is_solid(world.grid.get_coords(world.player_position()).unwrap()));
+
+		// Going through a file doesn't lose anything either
+		recording.finish(&world);
+		let recording = Recording::parse(&recording.serialize()).unwrap();
+		assert!(recording.check().is_ok());
+
+		// Flipping the axis afterwards would send the player the other way
+		let mut flipped = Recording::parse(&recording.serialize()).unwrap();
+		flipped
+			.bindings
+			.bind_axis(Axis::Horizontal, Action::MoveRight, Action::MoveLeft);
+		let flipped = Recording::parse(&flipped.serialize()).unwrap();
+		assert!(flipped.check().is_err());
+
+		let mut recording = recording;
+		recording.end = Some(Vec2::new(0.0, 0.0));
+		assert!(matches!(
+			recording.check(),
+			Err(ReplayError::Diverged { .. })
+		));
+	}
+}
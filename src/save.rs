@@ -0,0 +1,1125 @@
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+use std::collections::HashSet;
+
+use smitten::{Color, Draw, Vec2};
+
+use crate::{
+	animation::{Motion, Playhead},
+	atlas::Sprite,
+	camera::Shake,
+	editor::Tool,
+	entity::{Body, Collider, Controller, Entities, Entity, Look, Pickup, Transform},
+	level::{self, LevelError},
+	particle::{Emitter, Particle, Particles, Shape},
+	platform::{Kinematic, Progress, Route},
+	render::{Assets, MISSING},
+	scene::Attachment,
+	tween::{Curve, Ease},
+	world::World,
+};
+
+/// Turns the lines of a save written by one version into what the next
+/// version expects
+pub type Migration = fn(&mut Vec<Line>);
+
+/// Every change to the format gets a migration here, the first one takes
+/// version 1 saves to version 2 and so on. Saves are always written in the
+/// newest version.
+const MIGRATIONS: &[Migration] = &[looks_by_name];
+
+pub const VERSION: u32 = 1 + MIGRATIONS.len() as u32;
+
+/// A line of a save, split into words
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+	pub number: usize,
+	pub key: String,
+	pub values: Vec<String>,
+}
+
+/// What the game keeps outside of the world. It's saved along with it so
+/// nothing on screen jumps when a snapshot's loaded.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct View {
+	pub camera: Vec2,
+	pub zoom: f32,
+	pub shake: Shake,
+	/// Where the zoom's headed
+	pub zoom_to: f32,
+	/// Where the zoom started easing from and how many seconds ago, if it
+	/// isn't there yet
+	pub zooming: Option<(f32, f32)>,
+	pub animation: Option<(Motion, Playhead)>,
+}
+
+/// Everything needed to pick a world back up exactly where it was. Where
+/// levels only have the grid, snapshots have every entity and all their
+/// components too. They look like this:
+///
+/// ```text
+/// version 2
+/// generations 0 1
+/// free
+/// player 0
+/// editor false brush 1 0
+/// camera 0 0 1 0.12 3.5
+/// zoom 1.25 1 0.1
+/// animation walk 1 0.05 true true false
+/// entity 0
+/// transform -3 -3 -3.075 -3 0 1
+/// collider 1 1
+/// look 1 1 color 0.1 0.3 0.5 1
+/// controller 0.075
+/// entity 1
+/// transform 2 0 1.98 0 0 1
+/// path pingpong smooth 1.5 2 0 -2 0
+/// progress 0 1 0.02 true
+/// particles 2749203
+/// emitter 0 -1.5 0 0 none 0
+/// spray 0.3 0.6 1.5707964 1.2 0.5 1.5 0 -2
+/// fade 0.6 0.55 0.5 0.8 0.6 0.55 0.5 0 0.1 0.25 square true
+/// particle 0.2 -1.4 0.3 0.9 0.1 0.45
+/// level
+/// size 5 5
+/// ...
+/// ```
+///
+/// Components follow the `entity` they belong to, and particles follow the
+/// `emitter` they came out of. Atlas regions are saved by name. Texture ids
+/// are different every run, so those looks are saved as `missing`, same as
+/// regions the atlas doesn't have, and come back pink. Undo history isn't
+/// saved, it starts over.
+pub fn save<P: AsRef<Path>>(
+	world: &World,
+	view: &View,
+	assets: &Assets,
+	path: P,
+) -> io::Result<()> {
+	if let Some(parent) = path.as_ref().parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	fs::write(path, serialize(world, Some(view), assets))
+}
+
+/// The world, and the view if the save has one. Older ones don't.
+pub fn load<P: AsRef<Path>>(path: P, assets: &Assets) -> Result<(World, Option<View>), SaveError> {
+	parse(&fs::read_to_string(path)?, assets)
+}
+
+pub fn serialize(world: &World, view: Option<&View>, assets: &Assets) -> String {
+	let entities = &world.entities;
+	let (generations, free) = entities.allocator();
+	let editor = &world.editor;
+
+	let mut lines = vec![
+		format!("version {}", VERSION),
+		format!("generations{}", join(generations)),
+		format!("free{}", join(free)),
+		format!("player {}", world.player.index()),
+		format!(
+			"editor {} {} {} {}",
+			editor.enabled,
+			tool_name(editor.tool),
+			editor.brush_size,
+			editor.selected
+		),
+	];
+
+	if let Some(view) = view {
+		let (camera, shake) = (view.camera, view.shake);
+		lines.push(format!(
+			"camera {} {} {} {} {}",
+			camera.x, camera.y, view.zoom, shake.trauma, shake.time
+		));
+
+		lines.push(match view.zooming {
+			Some((from, elapsed)) => format!("zoom {} {} {}", view.zoom_to, from, elapsed),
+			None => format!("zoom {}", view.zoom_to),
+		});
+
+		if let Some((state, p)) = view.animation {
+			lines.push(format!(
+				"animation {} {} {} {} {} {}",
+				motion_name(state),
+				p.frame,
+				p.elapsed,
+				p.forward,
+				p.started,
+				p.finished
+			));
+		}
+	}
+
+	for index in 0..generations.len() as u32 {
+		let entity = match entities.at(index) {
+			Some(entity) => entity,
+			None => continue,
+		};
+		lines.push(format!("entity {}", index));
+
+		if let Some(t) = entities.transforms.get(entity) {
+			lines.push(format!(
+				"transform {} {} {} {} {} {}",
+				t.position.x, t.position.y, t.previous.x, t.previous.y, t.rotation, t.scale
+			));
+		}
+		if let Some(c) = entities.colliders.get(entity) {
+			lines.push(format!("collider {} {}", c.size.x, c.size.y));
+		}
+		if let Some(b) = entities.bodies.get(entity) {
+			lines.push(format!("body {} {}", b.velocity.x, b.velocity.y));
+		}
+		if let Some(l) = entities.looks.get(entity) {
+			let region = |id| assets.atlas.as_ref().and_then(|(atlas, _)| atlas.name(id));
+			let sprite = match l.sprite {
+				Sprite::Draw(Draw::Color(c)) => format!("color {} {} {} {}", c.r, c.g, c.b, c.a),
+				Sprite::Region(id) => match region(id) {
+					Some(name) => format!("region {}", name),
+					None => "missing".to_owned(),
+				},
+				Sprite::Draw(Draw::Texture(_)) => "missing".to_owned(),
+			};
+			lines.push(format!("look {} {} {}", l.size.x, l.size.y, sprite));
+		}
+		if let Some(c) = entities.controllers.get(entity) {
+			lines.push(format!("controller {}", c.speed));
+		}
+		if entities.pickups.contains(entity) {
+			lines.push("pickup".to_owned());
+		}
+		if let Some(a) = entities.attachments.get(entity) {
+			lines.push(format!(
+				"attachment {} {} {} {} {}",
+				a.parent.index(),
+				a.offset.x,
+				a.offset.y,
+				a.rotation,
+				a.scale
+			));
+		}
+		if let Some(k) = entities.kinematics.get(entity) {
+			let waypoints: Vec<String> = k
+				.waypoints
+				.iter()
+				.map(|w| format!("{} {}", w.x, w.y))
+				.collect();
+			lines.push(format!(
+				"path {} {} {} {}",
+				route_name(k.route),
+				ease_name(k.easing),
+				k.speed,
+				waypoints.join(" ")
+			));
+
+			let p = k.progress();
+			lines.push(format!(
+				"progress {} {} {} {}",
+				p.from, p.to, p.distance, p.forward
+			));
+		}
+	}
+
+	lines.extend(particles(&world.particles));
+
+	lines.push("level".to_owned());
+	let mut string = lines.join("\n") + "\n";
+	string.push_str(&level::serialize(&world.grid));
+	string
+}
+
+/// Every emitter and where it's up to
+fn particles(particles: &Particles) -> Vec<String> {
+	let mut lines = vec![format!("particles {}", particles.seed())];
+	let rgba = |c: Color| format!("{} {} {} {}", c.r, c.g, c.b, c.a);
+
+	for e in particles.emitters() {
+		let duration = match e.duration {
+			Some(duration) => duration.to_string(),
+			None => "forever".to_owned(),
+		};
+		let follow = match e.follow {
+			Some(entity) => entity.index().to_string(),
+			None => "none".to_owned(),
+		};
+
+		lines.push(format!(
+			"emitter {} {} {} {} {} {}",
+			e.position.x,
+			e.position.y,
+			e.rate,
+			duration,
+			follow,
+			e.owed()
+		));
+		lines.push(format!(
+			"spray {} {} {} {} {} {} {} {}",
+			e.lifetime.0,
+			e.lifetime.1,
+			e.direction,
+			e.spread,
+			e.speed.0,
+			e.speed.1,
+			e.gravity.x,
+			e.gravity.y
+		));
+		lines.push(format!(
+			"fade {} {} {} {} {} {}",
+			rgba(e.color.0),
+			rgba(e.color.1),
+			e.size.0,
+			e.size.1,
+			shape_name(e.shape),
+			e.collide
+		));
+
+		for p in e.particles() {
+			lines.push(format!(
+				"particle {} {} {} {} {} {}",
+				p.position.x, p.position.y, p.velocity.x, p.velocity.y, p.age, p.lifetime
+			));
+		}
+	}
+
+	lines
+}
+
+pub fn parse(string: &str, assets: &Assets) -> Result<(World, Option<View>), SaveError> {
+	let (mut lines, level) = split(string);
+
+	let version = match lines.first() {
+		Some(line) if line.key == "version" => {
+			let [version]: [u32; 1] = values(line)?;
+			version
+		}
+		_ => return Err(SaveError::Missing("version")),
+	};
+	if version == 0 {
+		return Err(bad_value(&lines[0]));
+	}
+	migrate(&mut lines, version, MIGRATIONS)?;
+
+	let grid = level::parse(&level.ok_or(SaveError::Missing("level"))?)?;
+	let mut world = World::new(grid, Vec2::ZERO);
+
+	let mut entities = None;
+	let mut generations = None;
+	let mut player = None;
+	let mut current: Option<Entity> = None;
+	let mut view: Option<View> = None;
+	let mut seed = None;
+	// Each with its particles and how much it owes
+	let mut emitters: Vec<(Emitter, Vec<Particle>, f32)> = vec![];
+
+	for line in &lines[1..] {
+		match line.key.as_str() {
+			"generations" => generations = Some(list::<u32>(line)?),
+			"free" => {
+				let generations = generations
+					.take()
+					.ok_or(SaveError::Missing("generations"))?;
+				let free = list::<u32>(line)?;
+
+				let mut seen = HashSet::new();
+				if free
+					.iter()
+					.any(|i| *i as usize >= generations.len() || !seen.insert(*i))
+				{
+					return Err(bad_value(line));
+				}
+				entities = Some(Entities::with_allocator(generations, free));
+			}
+			"player" => {
+				let [index]: [u32; 1] = values(line)?;
+				player = Some((index, line));
+			}
+			"editor" => {
+				let [enabled, tool, size, selected] = words::<4>(line)?;
+				let editor = &mut world.editor;
+
+				editor.enabled = parse_word(line, enabled)?;
+				editor.tool = tool_from_name(tool).ok_or_else(|| bad_value(line))?;
+				editor.brush_size = parse_word(line, size)?;
+				editor.select(parse_word(line, selected)?);
+			}
+			"camera" => {
+				let [x, y, zoom, trauma, time]: [f32; 5] = values(line)?;
+				view = Some(View {
+					camera: Vec2::new(x, y),
+					zoom,
+					shake: Shake { trauma, time },
+					zoom_to: zoom,
+					zooming: None,
+					animation: None,
+				});
+			}
+			"zoom" => {
+				let view = view.as_mut().ok_or(SaveError::Missing("camera"))?;
+
+				match list::<f32>(line)?[..] {
+					[to] => view.zoom_to = to,
+					[to, from, elapsed] => {
+						view.zoom_to = to;
+						view.zooming = Some((from, elapsed));
+					}
+					_ => return Err(bad_value(line)),
+				}
+			}
+			"animation" => {
+				let view = view.as_mut().ok_or(SaveError::Missing("camera"))?;
+				let [state, frame, elapsed, forward, started, finished] = words::<6>(line)?;
+
+				let playhead = Playhead {
+					frame: parse_word(line, frame)?,
+					elapsed: parse_word(line, elapsed)?,
+					forward: parse_word(line, forward)?,
+					started: parse_word(line, started)?,
+					finished: parse_word(line, finished)?,
+				};
+				let state = motion_from_name(state).ok_or_else(|| bad_value(line))?;
+				view.animation = Some((state, playhead));
+			}
+			"particles" => {
+				let [rng]: [u32; 1] = values(line)?;
+				seed = Some(rng);
+			}
+			"emitter" => {
+				if seed.is_none() {
+					return Err(SaveError::Missing("particles"));
+				}
+				let [x, y, rate, duration, follow, owed] = words::<6>(line)?;
+
+				let follow = match follow {
+					"none" => None,
+					index => {
+						let entities = entities.as_ref().ok_or(SaveError::Missing("free"))?;
+						let index = parse_word(line, index)?;
+						Some(entities.at(index).ok_or_else(|| bad_value(line))?)
+					}
+				};
+				let duration = match duration {
+					"forever" => None,
+					seconds => Some(parse_word(line, seconds)?),
+				};
+
+				let mut emitter = Emitter::new((parse_word(line, x)?, parse_word::<f32>(line, y)?));
+				emitter.rate = parse_word(line, rate)?;
+				emitter.duration = duration;
+				emitter.follow = follow;
+				emitters.push((emitter, vec![], parse_word(line, owed)?));
+			}
+			"spray" => {
+				let (emitter, _, _) = emitters.last_mut().ok_or(SaveError::Missing("emitter"))?;
+				let [lifetime0, lifetime1, direction, spread, speed0, speed1, gx, gy]: [f32; 8] =
+					values(line)?;
+
+				emitter.lifetime = (lifetime0, lifetime1);
+				emitter.direction = direction;
+				emitter.spread = spread;
+				emitter.speed = (speed0, speed1);
+				emitter.gravity = Vec2::new(gx, gy);
+			}
+			"fade" => {
+				let (emitter, _, _) = emitters.last_mut().ok_or(SaveError::Missing("emitter"))?;
+				let words = words::<12>(line)?;
+
+				let mut color = [0.0; 8];
+				for (c, word) in color.iter_mut().zip(&words[..8]) {
+					*c = parse_word(line, word)?;
+				}
+				emitter.color = (
+					Color::rgba(color[0], color[1], color[2], color[3]),
+					Color::rgba(color[4], color[5], color[6], color[7]),
+				);
+				emitter.size = (parse_word(line, words[8])?, parse_word(line, words[9])?);
+				emitter.shape = shape_from_name(words[10]).ok_or_else(|| bad_value(line))?;
+				emitter.collide = parse_word(line, words[11])?;
+			}
+			"particle" => {
+				let (_, particles, _) = emitters.last_mut().ok_or(SaveError::Missing("emitter"))?;
+				let [x, y, vx, vy, age, lifetime]: [f32; 6] = values(line)?;
+
+				particles.push(Particle {
+					position: Vec2::new(x, y),
+					velocity: Vec2::new(vx, vy),
+					age,
+					lifetime,
+				});
+			}
+			"entity" => {
+				let entities = entities.as_ref().ok_or(SaveError::Missing("free"))?;
+				let [index]: [u32; 1] = values(line)?;
+				current = Some(entities.at(index).ok_or_else(|| bad_value(line))?);
+			}
+			_ => {
+				let entities = entities.as_mut().ok_or(SaveError::Missing("free"))?;
+				let entity = current.ok_or(SaveError::Syntax(line.number))?;
+				component(entities, entity, line, assets)?;
+			}
+		}
+	}
+
+	let entities = entities.ok_or(SaveError::Missing("free"))?;
+	let (player, line) = player.ok_or(SaveError::Missing("player"))?;
+	world.player = entities.at(player).ok_or(SaveError::Missing("player"))?;
+
+	// Everything that follows the player needs to know where it is
+	if entities.transforms.get(world.player).is_none() {
+		return Err(bad_value(line));
+	}
+	world.entities = entities;
+
+	match seed {
+		Some(seed) => {
+			world.particles = Particles::new(seed);
+			for (mut emitter, particles, owed) in emitters {
+				emitter.resume(particles, owed);
+				world.particles.add(emitter);
+			}
+		}
+		// Saves from before particles were kept. The level's embers were lit
+		// for entities that aren't there anymore.
+		None => {
+			world.particles.clear();
+			for (beacon, _) in world.entities.pickups.iter() {
+				let position = world.entities.transforms.get(beacon).map(|t| t.position);
+				world
+					.particles
+					.add(Emitter::embers(position.unwrap_or(Vec2::ZERO), beacon));
+			}
+		}
+	}
+
+	Ok((world, view))
+}
+
+/// Bring `lines`, written by `version`, up to date
+fn migrate(lines: &mut Vec<Line>, version: u32, migrations: &[Migration]) -> Result<(), SaveError> {
+	let newest = 1 + migrations.len() as u32;
+	if version > newest {
+		return Err(SaveError::TooNew(version));
+	}
+
+	for migration in &migrations[version as usize - 1..] {
+		migration(lines);
+	}
+
+	if let Some(line) = lines.first_mut() {
+		line.values = vec![newest.to_string()];
+	}
+
+	Ok(())
+}
+
+/// The lines before `level`, and the level after it if there is one
+fn split(string: &str) -> (Vec<Line>, Option<String>) {
+	let mut lines = vec![];
+	let mut iter = string.lines().enumerate();
+
+	while let Some((idx, text)) = iter.next() {
+		let mut words = text.split_whitespace();
+
+		match words.next() {
+			None => (),
+			Some("level") => {
+				let rest: Vec<&str> = iter.map(|(_, l)| l).collect();
+				return (lines, Some(rest.join("\n") + "\n"));
+			}
+			Some(key) => lines.push(Line {
+				number: idx + 1,
+				key: key.to_owned(),
+				values: words.map(str::to_owned).collect(),
+			}),
+		}
+	}
+
+	(lines, None)
+}
+
+/// Version 2 saves regions by name and doesn't save textures, ids weren't the
+/// same from one run to the next
+#[allow(clippy::ptr_arg)] // Has to be a `Migration`
+fn looks_by_name(lines: &mut Vec<Line>) {
+	for line in lines.iter_mut().filter(|l| l.key == "look") {
+		if let [_, _, "texture" | "region", _] = words_of(line).as_slice() {
+			line.values.truncate(2);
+			line.values.push("missing".to_owned());
+		}
+	}
+}
+
+fn component(
+	entities: &mut Entities,
+	entity: Entity,
+	line: &Line,
+	assets: &Assets,
+) -> Result<(), SaveError> {
+	match line.key.as_str() {
+		"transform" => {
+			let [x, y, px, py, rotation, scale]: [f32; 6] = values(line)?;
+			entities.transforms.insert(
+				entity,
+				Transform {
+					position: Vec2::new(x, y),
+					previous: Vec2::new(px, py),
+					rotation,
+					scale,
+				},
+			);
+		}
+		"collider" => {
+			let [w, h]: [f32; 2] = values(line)?;
+			entities.colliders.insert(
+				entity,
+				Collider {
+					size: Vec2::new(w, h),
+				},
+			);
+		}
+		"body" => {
+			let [x, y]: [f32; 2] = values(line)?;
+			entities.bodies.insert(
+				entity,
+				Body {
+					velocity: Vec2::new(x, y),
+				},
+			);
+		}
+		"look" => {
+			let words = words_of(line);
+			let number = |word: &str| parse_word::<f32>(line, word);
+
+			let sprite = match words.as_slice() {
+				[_, _, "color", r, g, b, a] => {
+					Color::rgba(number(r)?, number(g)?, number(b)?, number(a)?).into()
+				}
+				// Could be a save from before a region was renamed
+				[_, _, "region", name] => assets
+					.atlas
+					.as_ref()
+					.and_then(|(atlas, _)| atlas.id(name))
+					.map(Sprite::Region)
+					.unwrap_or_else(|| MISSING.into()),
+				[_, _, "missing"] => MISSING.into(),
+				_ => return Err(bad_value(line)),
+			};
+
+			entities.looks.insert(
+				entity,
+				Look {
+					sprite,
+					size: Vec2::new(number(words[0])?, number(words[1])?),
+				},
+			);
+		}
+		"controller" => {
+			let [speed]: [f32; 1] = values(line)?;
+			entities.controllers.insert(entity, Controller { speed });
+		}
+		"pickup" => {
+			words::<0>(line)?;
+			entities.pickups.insert(entity, Pickup);
+		}
+		"attachment" => {
+			let [parent, x, y, rotation, scale] = words::<5>(line)?;
+			let number = |word: &str| parse_word::<f32>(line, word);
+
+			let parent = entities
+				.at(parse_word(line, parent)?)
+				.ok_or_else(|| bad_value(line))?;
+			entities.attachments.insert(
+				entity,
+				Attachment {
+					parent,
+					offset: Vec2::new(number(x)?, number(y)?),
+					rotation: number(rotation)?,
+					scale: number(scale)?,
+				},
+			);
+		}
+		"path" => {
+			let (route, ease, speed, points) = match line.values.as_slice() {
+				[route, ease, speed, points @ ..]
+					if !points.is_empty() && points.len() % 2 == 0 =>
+				{
+					(route, ease, speed, points)
+				}
+				_ => return Err(bad_value(line)),
+			};
+
+			let mut waypoints = vec![];
+			for pair in points.chunks(2) {
+				waypoints.push(Vec2::new(
+					parse_word(line, &pair[0])?,
+					parse_word(line, &pair[1])?,
+				));
+			}
+
+			let kinematic = Kinematic::new(
+				waypoints,
+				route_from_name(route).ok_or_else(|| bad_value(line))?,
+				parse_word(line, speed)?,
+			)
+			.with_easing(ease_from_name(ease).ok_or_else(|| bad_value(line))?);
+			entities.kinematics.insert(entity, kinematic);
+		}
+		"progress" => {
+			let [from, to, distance, forward] = words::<4>(line)?;
+			let progress = Progress {
+				from: parse_word(line, from)?,
+				to: parse_word(line, to)?,
+				distance: parse_word(line, distance)?,
+				forward: parse_word(line, forward)?,
+			};
+
+			entities
+				.kinematics
+				.get_mut(entity)
+				.ok_or(SaveError::Syntax(line.number))?
+				.set_progress(progress);
+		}
+		_ => return Err(SaveError::Syntax(line.number)),
+	}
+
+	Ok(())
+}
+
+/// Every value with a space before it, so nothing trails an empty list
+fn join(values: &[u32]) -> String {
+	values.iter().map(|v| format!(" {}", v)).collect()
+}
+
+fn bad_value(line: &Line) -> SaveError {
+	SaveError::BadValue {
+		line: line.number,
+		key: line.key.clone(),
+	}
+}
+
+fn parse_word<T: FromStr>(line: &Line, word: &str) -> Result<T, SaveError> {
+	word.parse().map_err(|_| bad_value(line))
+}
+
+fn words_of(line: &Line) -> Vec<&str> {
+	line.values.iter().map(String::as_str).collect()
+}
+
+/// Exactly `N` words
+fn words<const N: usize>(line: &Line) -> Result<[&str; N], SaveError> {
+	words_of(line).try_into().map_err(|_| bad_value(line))
+}
+
+/// Exactly `N` values of the same type
+fn values<T: FromStr + Copy + Default, const N: usize>(line: &Line) -> Result<[T; N], SaveError> {
+	let words = words::<N>(line)?;
+
+	let mut values = [T::default(); N];
+	for (value, word) in values.iter_mut().zip(words) {
+		*value = parse_word(line, word)?;
+	}
+
+	Ok(values)
+}
+
+/// Any number of values of the same type
+fn list<T: FromStr>(line: &Line) -> Result<Vec<T>, SaveError> {
+	line.values
+		.iter()
+		.map(|word| parse_word(line, word))
+		.collect()
+}
+
+fn tool_name(tool: Tool) -> &'static str {
+	match tool {
+		Tool::Brush => "brush",
+		Tool::Rectangle => "rectangle",
+		Tool::Fill => "fill",
+	}
+}
+
+fn tool_from_name(name: &str) -> Option<Tool> {
+	[Tool::Brush, Tool::Rectangle, Tool::Fill]
+		.into_iter()
+		.find(|t| tool_name(*t) == name)
+}
+
+fn motion_name(motion: Motion) -> &'static str {
+	match motion {
+		Motion::Idle => "idle",
+		Motion::Walk => "walk",
+		Motion::Place => "place",
+	}
+}
+
+fn motion_from_name(name: &str) -> Option<Motion> {
+	[Motion::Idle, Motion::Walk, Motion::Place]
+		.into_iter()
+		.find(|m| motion_name(*m) == name)
+}
+
+fn shape_name(shape: Shape) -> &'static str {
+	match shape {
+		Shape::Square => "square",
+		Shape::Circle => "circle",
+	}
+}
+
+fn shape_from_name(name: &str) -> Option<Shape> {
+	[Shape::Square, Shape::Circle]
+		.into_iter()
+		.find(|s| shape_name(*s) == name)
+}
+
+fn route_name(route: Route) -> &'static str {
+	match route {
+		Route::Once => "once",
+		Route::PingPong => "pingpong",
+		Route::Loop => "loop",
+	}
+}
+
+fn route_from_name(name: &str) -> Option<Route> {
+	[Route::Once, Route::PingPong, Route::Loop]
+		.into_iter()
+		.find(|r| route_name(*r) == name)
+}
+
+const CURVES: [(Curve, &str); 4] = [
+	(Curve::Quad, "quad"),
+	(Curve::Cubic, "cubic"),
+	(Curve::Elastic, "elastic"),
+	(Curve::Bounce, "bounce"),
+];
+
+/// Like `in-quad` or `smooth`
+fn ease_name(ease: Ease) -> String {
+	let curve = |c: Curve| CURVES.iter().find(|(curve, _)| *curve == c).unwrap().1;
+
+	match ease {
+		Ease::Linear => "linear".to_owned(),
+		Ease::Smooth => "smooth".to_owned(),
+		Ease::In(c) => format!("in-{}", curve(c)),
+		Ease::Out(c) => format!("out-{}", curve(c)),
+		Ease::InOut(c) => format!("inout-{}", curve(c)),
+	}
+}
+
+fn ease_from_name(name: &str) -> Option<Ease> {
+	match name {
+		"linear" => return Some(Ease::Linear),
+		"smooth" => return Some(Ease::Smooth),
+		_ => (),
+	}
+
+	let (kind, curve) = name.split_once('-')?;
+	let curve = CURVES.iter().find(|(_, n)| *n == curve)?.0;
+
+	match kind {
+		"in" => Some(Ease::In(curve)),
+		"out" => Some(Ease::Out(curve)),
+		"inout" => Some(Ease::InOut(curve)),
+		_ => None,
+	}
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+	Io(io::Error),
+	Level(LevelError),
+	Missing(&'static str),
+	/// Written by a newer version than this one, we can't know what changed
+	TooNew(u32),
+	BadValue {
+		line: usize,
+		key: String,
+	},
+	Syntax(usize),
+}
+
+impl From<io::Error> for SaveError {
+	fn from(e: io::Error) -> Self {
+		SaveError::Io(e)
+	}
+}
+
+impl From<LevelError> for SaveError {
+	fn from(e: LevelError) -> Self {
+		SaveError::Level(e)
+	}
+}
+
+impl fmt::Display for SaveError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SaveError::Io(e) => write!(f, "couldn't read save: {}", e),
+			SaveError::Level(e) => write!(f, "the save's level is broken: {}", e),
+			SaveError::Missing(key) => write!(f, "expected a '{}' line", key),
+			SaveError::TooNew(version) => write!(
+				f,
+				"save is version {} but we only know up to {}",
+				version, VERSION
+			),
+			SaveError::BadValue { line, key } => {
+				write!(f, "line {}: couldn't parse the values of '{}'", line, key)
+			}
+			SaveError::Syntax(line) => write!(f, "line {}: didn't expect that here", line),
+		}
+	}
+}
+
+impl std::error::Error for SaveError {}
+
+#[cfg(test)]
+mod test {
+	use std::collections::HashSet;
+
+	use smitten::{Color, Draw, TextureId, Vec2};
+
+	use super::{migrate, parse, serialize, split, Migration, SaveError, View};
+	use crate::{
+		animation::{Motion, Playhead},
+		atlas::{Atlas, Sprite},
+		autotile::{Mode, Tileset},
+		camera::Shake,
+		entity::{Body, Collider, Look, Transform},
+		input::{Action, ActionState, Bindings},
+		level,
+		particle::Emitter,
+		platform::{Kinematic, Progress, Route},
+		render::{Assets, MISSING},
+		scene,
+		tween::{Curve, Ease},
+		world::World,
+	};
+
+	fn assets() -> Assets {
+		let atlas = Atlas::parse(
+			"image = \"sheet.png\"\nsize = [32, 16]\n\n[regions]\nplayer = [0, 0, 16, 16]\nhanger = [17, 0, 8, 8]\n",
+		)
+		.unwrap();
+
+		Assets {
+			atlas: Some((atlas, TextureId(0))),
+			tileset: Tileset::new(Mode::FourBit, Color::rgb(0.6, 0.6, 0.7).into()),
+		}
+	}
+
+	fn is_missing(sprite: Sprite) -> bool {
+		matches!(sprite, Sprite::Draw(Draw::Color(c)) if c == MISSING)
+	}
+
+	#[test]
+	fn picks_up_exactly_where_it_left_off() {
+		let mut world = World::new(level::empty(), (0.5, -1.0));
+		let entities = &mut world.entities;
+
+		// A gap in the indices, so handles have to come back the same
+		let gone = entities.spawn();
+		entities.despawn(gone);
+
+		let platform = entities.spawn();
+		entities
+			.transforms
+			.insert(platform, Transform::new((2.0, 0.0)));
+		entities.colliders.insert(
+			platform,
+			Collider {
+				size: Vec2::new(2.0, 0.5),
+			},
+		);
+		entities.kinematics.insert(
+			platform,
+			Kinematic::new(
+				vec![Vec2::new(2.0, 0.0), Vec2::new(2.0, -2.0)],
+				Route::PingPong,
+				1.5,
+			)
+			.with_easing(Ease::InOut(Curve::Cubic)),
+		);
+
+		let hanger = entities.spawn();
+		entities
+			.transforms
+			.insert(hanger, Transform::new((2.0, 1.0)));
+		entities.bodies.insert(
+			hanger,
+			Body {
+				velocity: Vec2::new(0.1, 0.0),
+			},
+		);
+		entities.looks.insert(
+			hanger,
+			Look {
+				sprite: assets().atlas.unwrap().0.id("hanger").unwrap().into(),
+				size: Vec2::new(0.5, 0.5),
+			},
+		);
+		scene::attach(entities, hanger, platform);
+		world.particles.add(Emitter::embers((2.0, 1.0), hanger));
+
+		let mut input = ActionState::new(Bindings::default());
+		let mut step = |world: &mut World| {
+			input.set_down([Action::MoveRight].into_iter().collect::<HashSet<_>>());
+			world.step(&input, None);
+		};
+
+		for _ in 0..37 {
+			step(&mut world);
+		}
+
+		let assets = assets();
+		let view = View {
+			camera: Vec2::new(0.25, -0.5),
+			zoom: 1.1,
+			shake: Shake {
+				trauma: 0.3,
+				time: 2.5,
+			},
+			zoom_to: 1.25,
+			zooming: Some((1.0, 0.1)),
+			animation: Some((
+				Motion::Walk,
+				Playhead {
+					frame: 1,
+					elapsed: 0.05,
+					forward: true,
+					started: true,
+					finished: false,
+				},
+			)),
+		};
+		let string = serialize(&world, Some(&view), &assets);
+		assert!(string.contains("look 0.5 0.5 region hanger"));
+		let (mut loaded, loaded_view) = parse(&string, &assets).unwrap();
+		assert_eq!(loaded_view, Some(view));
+		assert_eq!(serialize(&loaded, Some(&view), &assets), string);
+		assert!(world.particles.len() > 0);
+
+		// Both carry on the same way
+		for _ in 0..50 {
+			step(&mut world);
+			step(&mut loaded);
+		}
+
+		for index in 0..3 {
+			let entity = world.entities.at(index);
+			assert_eq!(loaded.entities.at(index), entity);
+
+			if let Some(entity) = entity {
+				let moved = |world: &World| {
+					let t = world.entities.transforms.get(entity);
+					let b = world.entities.bodies.get(entity);
+					(t.map(|t| (t.position, t.previous)), b.map(|b| b.velocity))
+				};
+				assert_eq!(moved(&loaded), moved(&world));
+			}
+		}
+		assert_eq!(loaded.player_position(), world.player_position());
+		// Particles and all
+		assert_eq!(
+			serialize(&loaded, None, &assets),
+			serialize(&world, None, &assets)
+		);
+
+		// The next spawn reuses the same slot either way
+		assert_eq!(world.entities.spawn(), loaded.entities.spawn());
+	}
+
+	#[test]
+	fn version_1_saves_still_load() {
+		let (world, view) = parse(include_str!("../snapshots/save_v1.txt"), &assets()).unwrap();
+		let entities = &world.entities;
+		assert_eq!(view, None);
+
+		assert_eq!(world.player_position(), Vec2::new(0.9999999, 0.0));
+
+		let platform = entities.at(1).unwrap();
+		assert_eq!(
+			entities.transforms[platform].position,
+			Vec2::new(1.0, -0.87499994)
+		);
+		assert_eq!(
+			entities.kinematics.get(platform).unwrap().progress(),
+			Progress {
+				from: 0,
+				to: 1,
+				distance: 0.50000006,
+				forward: true,
+			}
+		);
+
+		let hanger = entities.at(2).unwrap();
+		assert_eq!(
+			entities.bodies.get(hanger).unwrap().velocity,
+			Vec2::new(0.1, 0.0)
+		);
+		assert_eq!(entities.attachments.get(hanger).unwrap().parent, platform);
+
+		// Version 1 saved looks by id, which can't be trusted
+		assert!(is_missing(entities.looks.get(platform).unwrap().sprite));
+		assert!(is_missing(entities.looks.get(hanger).unwrap().sprite));
+	}
+
+	#[test]
+	fn rejects_what_would_break_later() {
+		let level = level::serialize(&level::empty());
+		let save = |lines: &str| {
+			parse(&format!("version 2\n{}level\n{}", lines, level), &assets())
+				.map(|(world, _)| world)
+		};
+
+		assert!(save("generations 0\nfree\nplayer 0\nentity 0\ntransform 0 0 0 0 0 1\n").is_ok());
+
+		// Handing out the same slot twice
+		assert!(matches!(
+			save("generations 0 0\nfree 1 1\nplayer 0\nentity 0\ntransform 0 0 0 0 0 1\n"),
+			Err(SaveError::BadValue { line: 3, .. })
+		));
+		// Nowhere for the camera to follow
+		assert!(matches!(
+			save("generations 0\nfree\nplayer 0\nentity 0\ncollider 1 1\n"),
+			Err(SaveError::BadValue { line: 4, .. })
+		));
+		// Regions that aren't in the atlas any more
+		let world = save("generations 0\nfree\nplayer 0\nentity 0\ntransform 0 0 0 0 0 1\nlook 1 1 region gone\n").unwrap();
+		assert!(is_missing(
+			world.entities.looks.get(world.player).unwrap().sprite
+		));
+
+		// Particles following someone who isn't there
+		assert!(matches!(
+			save("generations 0\nfree\nplayer 0\nentity 0\ntransform 0 0 0 0 0 1\nparticles 7\nemitter 0 0 1 forever 3 0\n"),
+			Err(SaveError::BadValue { line: 8, .. })
+		));
+		// or that didn't come out of anything
+		assert!(matches!(
+			save("generations 0\nfree\nplayer 0\nentity 0\ntransform 0 0 0 0 0 1\nparticles 7\nparticle 0 0 0 0 0 1\n"),
+			Err(SaveError::Missing("emitter"))
+		));
+	}
+
+	#[test]
+	fn old_saves_are_migrated() {
+		// Say bodies were called velocities in version 1
+		let migrations: &[Migration] = &[|lines| {
+			for line in lines.iter_mut().filter(|l| l.key == "velocity") {
+				line.key = "body".to_owned();
+			}
+		}];
+
+		let (mut lines, _) = split("version 1\nentity 0\nvelocity 1 2\n");
+		migrate(&mut lines, 1, migrations).unwrap();
+		assert_eq!(lines[0].values, vec!["2"]);
+		assert_eq!(lines[2].key, "body");
+
+		// Already up to date, nothing happens
+		migrate(&mut lines, 2, migrations).unwrap();
+		assert_eq!(lines[2].key, "body");
+
+		assert!(matches!(
+			migrate(&mut lines, 3, migrations),
+			Err(SaveError::TooNew(3))
+		));
+	}
+}
@@ -0,0 +1,217 @@
+use smitten::Vec2;
+
+use crate::entity::{Entities, Entity, Transform};
+
+/// Chains of attachments deeper than this are assumed to be a mistake
+const MAX_DEPTH: usize = 32;
+
+/// Hangs an entity off of another one. Its transform is worked out from the
+/// parent's every step, so anything else moving it is overwritten.
+#[derive(Copy, Clone, Debug)]
+pub struct Attachment {
+	pub parent: Entity,
+	/// Relative to the parent, turned and scaled along with it
+	pub offset: Vec2,
+	/// Radians on top of the parent's
+	pub rotation: f32,
+	/// Multiplied with the parent's
+	pub scale: f32,
+}
+
+impl Attachment {
+	pub fn new<O: Into<Vec2>>(parent: Entity, offset: O) -> Self {
+		Self {
+			parent,
+			offset: offset.into(),
+			rotation: 0.0,
+			scale: 1.0,
+		}
+	}
+}
+
+/// Attach `child` to `parent` without moving it, working out the offset from
+/// where they both are now. Returns false, and does nothing, if either of
+/// them doesn't have a transform or if it'd make a loop.
+pub fn attach(entities: &mut Entities, child: Entity, parent: Entity) -> bool {
+	if child == parent || ancestors(entities, parent).any(|e| e == child) {
+		return false;
+	}
+
+	let (c, p) = match (
+		entities.transforms.get(child),
+		entities.transforms.get(parent),
+	) {
+		(Some(c), Some(p)) => (*c, *p),
+		_ => return false,
+	};
+
+	let offset = rotate(c.position - p.position, -p.rotation) / p.scale;
+	entities.attachments.insert(
+		child,
+		Attachment {
+			parent,
+			offset,
+			rotation: c.rotation - p.rotation,
+			scale: c.scale / p.scale,
+		},
+	);
+
+	true
+}
+
+/// Let go of the parent and stay where we are
+pub fn detach(entities: &mut Entities, child: Entity) {
+	if let Some(transform) = world_transform(entities, child, 0) {
+		entities.transforms.insert(child, transform);
+	}
+
+	entities.attachments.remove(child);
+}
+
+/// Put every attached entity where its parent says it should be. Children of
+/// despawned entities are let go of where they were.
+pub fn resolve(entities: &mut Entities) {
+	let attached: Vec<Entity> = entities.attachments.iter().map(|(e, _)| e).collect();
+
+	let mut orphans = vec![];
+	let mut resolved = vec![];
+	for child in attached {
+		match world_transform(entities, child, 0) {
+			Some(transform) => resolved.push((child, transform)),
+			None => orphans.push(child),
+		}
+	}
+
+	for (child, transform) in resolved {
+		if let Some(t) = entities.transforms.get_mut(child) {
+			t.position = transform.position;
+			t.rotation = transform.rotation;
+			t.scale = transform.scale;
+		}
+	}
+
+	for child in orphans {
+		entities.attachments.remove(child);
+	}
+}
+
+/// Where an entity is in the world, following its attachments up to an
+/// entity that isn't attached to anything.
+fn world_transform(entities: &Entities, entity: Entity, depth: usize) -> Option<Transform> {
+	let own = *entities.transforms.get(entity)?;
+
+	let attachment = match entities.attachments.get(entity) {
+		Some(attachment) if depth < MAX_DEPTH => attachment,
+		Some(_) => return None,
+		None => return Some(own),
+	};
+
+	if !entities.is_alive(attachment.parent) {
+		return None;
+	}
+
+	let parent = world_transform(entities, attachment.parent, depth + 1)?;
+	Some(Transform {
+		position: parent.position + rotate(attachment.offset * parent.scale, parent.rotation),
+		previous: own.previous,
+		rotation: parent.rotation + attachment.rotation,
+		scale: parent.scale * attachment.scale,
+	})
+}
+
+/// The parent, grandparent, and so on
+fn ancestors(entities: &Entities, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+	std::iter::successors(entities.attachments.get(entity), move |a| {
+		entities.attachments.get(a.parent)
+	})
+	.map(|a| a.parent)
+	.take(MAX_DEPTH)
+}
+
+/// Turn `v` counterclockwise by `angle` radians
+pub fn rotate(v: Vec2, angle: f32) -> Vec2 {
+	let (sin, cos) = angle.sin_cos();
+	Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+#[cfg(test)]
+mod test {
+	use std::f32::consts::FRAC_PI_2;
+
+	use smitten::Vec2;
+
+	use super::{attach, detach, resolve, Attachment};
+	use crate::entity::{Entities, Entity, Transform};
+
+	fn close(a: Vec2, b: Vec2) -> bool {
+		(a.x - b.x).abs() < 0.0001 && (a.y - b.y).abs() < 0.0001
+	}
+
+	fn spawn(entities: &mut Entities, position: (f32, f32)) -> Entity {
+		let e = entities.spawn();
+		entities.transforms.insert(e, Transform::new(position));
+		e
+	}
+
+	#[test]
+	fn children_follow_turns_and_scale() {
+		let mut entities = Entities::default();
+		let platform = spawn(&mut entities, (2.0, 0.0));
+		let turret = spawn(&mut entities, (0.0, 0.0));
+		let barrel = spawn(&mut entities, (0.0, 0.0));
+
+		entities
+			.attachments
+			.insert(turret, Attachment::new(platform, (1.0, 0.0)));
+		entities
+			.attachments
+			.insert(barrel, Attachment::new(turret, (0.0, 1.0)));
+
+		// A quarter turn and double size on the platform carries down
+		let t = &mut entities.transforms[platform];
+		t.rotation = FRAC_PI_2;
+		t.scale = 2.0;
+		resolve(&mut entities);
+
+		assert!(close(
+			entities.transforms[turret].position,
+			Vec2::new(2.0, 2.0)
+		));
+		assert!(close(
+			entities.transforms[barrel].position,
+			Vec2::new(0.0, 2.0)
+		));
+		assert_eq!(entities.transforms[barrel].scale, 2.0);
+	}
+
+	#[test]
+	fn attaching_and_detaching_dont_move() {
+		let mut entities = Entities::default();
+		let player = spawn(&mut entities, (1.0, 1.0));
+		let sword = spawn(&mut entities, (1.5, 1.0));
+		entities.transforms[player].rotation = 0.3;
+
+		assert!(attach(&mut entities, sword, player));
+		resolve(&mut entities);
+		assert!(close(
+			entities.transforms[sword].position,
+			Vec2::new(1.5, 1.0)
+		));
+
+		entities.transforms[player].position = Vec2::new(3.0, 1.0);
+		resolve(&mut entities);
+		detach(&mut entities, sword);
+
+		// Moving the player after letting go leaves the sword behind
+		entities.transforms[player].position = Vec2::new(10.0, 10.0);
+		resolve(&mut entities);
+		assert!(close(
+			entities.transforms[sword].position,
+			Vec2::new(3.5, 1.0)
+		));
+
+		// And no loops
+		assert!(attach(&mut entities, sword, player));
+		assert!(!attach(&mut entities, player, sword));
+	}
+}
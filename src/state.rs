@@ -0,0 +1,188 @@
+use crate::{render::Command, ui::Ui};
+
+/// What a state wants done with the stack after it's run
+pub enum Transition<C> {
+	None,
+	/// Put a state on top, this one waits under it until it's popped
+	Push(Box<dyn State<C>>),
+	/// Go back to whatever's under this
+	Pop,
+	/// Swap this state for another
+	Replace(Box<dyn State<C>>),
+	Quit,
+}
+
+/// One screen of the game, like the title menu or the pause overlay. `C` is
+/// everything the states share, so they don't each need their own world.
+pub trait State<C> {
+	/// One fixed step. Only the state on top is stepped.
+	fn step(&mut self, _context: &mut C) -> Transition<C> {
+		Transition::None
+	}
+
+	/// Widgets for this frame. Only the state on top gets to use the UI.
+	fn ui(&mut self, _context: &mut C, _ui: &mut Ui) -> Transition<C> {
+		Transition::None
+	}
+
+	fn draw(&self, context: &C, commands: &mut Vec<Command>);
+
+	/// Whether the state under this one is still drawn, like a menu over the
+	/// paused game
+	fn overlay(&self) -> bool {
+		false
+	}
+}
+
+/// The states that are open, the top one being the one that's running
+pub struct Stack<C> {
+	states: Vec<Box<dyn State<C>>>,
+	quit: bool,
+}
+
+impl<C> Stack<C> {
+	pub fn new(first: Box<dyn State<C>>) -> Self {
+		Self {
+			states: vec![first],
+			quit: false,
+		}
+	}
+
+	/// Whether everything's been popped or something asked to quit
+	pub fn finished(&self) -> bool {
+		self.quit || self.states.is_empty()
+	}
+
+	#[cfg(test)]
+	pub fn len(&self) -> usize {
+		self.states.len()
+	}
+
+	pub fn step(&mut self, context: &mut C) {
+		if let Some(top) = self.states.last_mut() {
+			let transition = top.step(context);
+			self.apply(transition);
+		}
+	}
+
+	pub fn ui(&mut self, context: &mut C, ui: &mut Ui) {
+		if let Some(top) = self.states.last_mut() {
+			let transition = top.ui(context, ui);
+			self.apply(transition);
+		}
+	}
+
+	/// The top state and every one showing through it, from the bottom up
+	pub fn draw(&self, context: &C) -> Vec<Command> {
+		let bottom = self
+			.states
+			.iter()
+			.rposition(|state| !state.overlay())
+			.unwrap_or(0);
+
+		let mut commands = vec![];
+		for state in &self.states[bottom..] {
+			state.draw(context, &mut commands);
+		}
+
+		commands
+	}
+
+	fn apply(&mut self, transition: Transition<C>) {
+		match transition {
+			Transition::None => (),
+			Transition::Push(state) => self.states.push(state),
+			Transition::Pop => {
+				self.states.pop();
+			}
+			Transition::Replace(state) => {
+				self.states.pop();
+				self.states.push(state);
+			}
+			Transition::Quit => self.quit = true,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Stack, State, Transition};
+	use crate::render::Command;
+
+	/// Counts down its steps and does `then` once it gets to the last one
+	struct Counter {
+		name: &'static str,
+		at: u32,
+		then: fn() -> Transition<Vec<&'static str>>,
+		overlay: bool,
+	}
+
+	impl State<Vec<&'static str>> for Counter {
+		fn step(&mut self, log: &mut Vec<&'static str>) -> Transition<Vec<&'static str>> {
+			log.push(self.name);
+
+			match self.at {
+				0 => Transition::None,
+				1 => {
+					self.at = 0;
+					(self.then)()
+				}
+				_ => {
+					self.at -= 1;
+					Transition::None
+				}
+			}
+		}
+
+		fn draw(&self, _log: &Vec<&'static str>, commands: &mut Vec<Command>) {
+			commands.push(Command::Clear);
+		}
+
+		fn overlay(&self) -> bool {
+			self.overlay
+		}
+	}
+
+	fn pause() -> Transition<Vec<&'static str>> {
+		Transition::Push(Box::new(Counter {
+			name: "pause",
+			at: 2,
+			then: || Transition::Pop,
+			overlay: true,
+		}))
+	}
+
+	#[test]
+	fn push_pop_replace() {
+		let mut log = vec![];
+		let mut stack = Stack::new(Box::new(Counter {
+			name: "title",
+			at: 1,
+			then: || {
+				Transition::Replace(Box::new(Counter {
+					name: "game",
+					at: 2,
+					then: pause,
+					overlay: false,
+				}))
+			},
+			overlay: false,
+		}));
+
+		for _ in 0..4 {
+			stack.step(&mut log);
+		}
+
+		// The game's paused, and drawn under the pause menu
+		assert_eq!(log, vec!["title", "game", "game", "pause"]);
+		assert_eq!(stack.len(), 2);
+		assert_eq!(stack.draw(&log).len(), 2);
+
+		// Popping goes back to the game where it was
+		stack.step(&mut log);
+		stack.step(&mut log);
+		assert_eq!(log[4..], ["pause", "game"]);
+		assert_eq!(stack.draw(&log).len(), 1);
+		assert!(!stack.finished());
+	}
+}
@@ -0,0 +1,115 @@
+use std::{
+	collections::{hash_map::DefaultHasher, HashMap},
+	fs,
+	hash::{Hash, Hasher},
+	path::{Path, PathBuf},
+};
+
+use image::ImageError;
+use smitten::TextureId;
+
+/// Every texture loaded from a file, so they can be loaded again when the
+/// file changes. smitten can't free a texture, so every version of an image
+/// that's been uploaded is kept by what's in it. Saving without changing
+/// anything, or going back to how it was, reuses the old one. Files are kept
+/// apart even when they're the same, so replacing one doesn't touch the
+/// other.
+pub struct Textures {
+	files: Vec<(PathBuf, TextureId)>,
+	uploaded: HashMap<(PathBuf, u64), TextureId>,
+}
+
+impl Textures {
+	pub fn new() -> Self {
+		Self {
+			files: vec![],
+			uploaded: HashMap::new(),
+		}
+	}
+
+	/// Load `path` with `upload`, unless an image just like it's already
+	/// been. It has to decode first, a half written image shouldn't take the
+	/// game down.
+	pub fn load<P: AsRef<Path>, U: FnOnce(&Path) -> TextureId>(
+		&mut self,
+		path: P,
+		upload: U,
+	) -> Result<TextureId, ImageError> {
+		let path = path.as_ref();
+		let bytes = fs::read(path)?;
+		image::load_from_memory(&bytes)?;
+
+		let mut hasher = DefaultHasher::new();
+		bytes.hash(&mut hasher);
+		let texture = *self
+			.uploaded
+			.entry((path.to_owned(), hasher.finish()))
+			.or_insert_with(|| upload(path));
+
+		match self.files.iter_mut().find(|(p, _)| p == path) {
+			Some((_, t)) => *t = texture,
+			None => self.files.push((path.to_owned(), texture)),
+		}
+
+		Ok(texture)
+	}
+
+	/// What `path` was last loaded as
+	pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<TextureId> {
+		self.files
+			.iter()
+			.find(|(p, _)| p == path.as_ref())
+			.map(|(_, t)| *t)
+	}
+
+	pub fn paths(&self) -> impl Iterator<Item = &Path> {
+		self.files.iter().map(|(p, _)| p.as_path())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::fs;
+
+	use image::{Rgba, RgbaImage};
+	use smitten::TextureId;
+
+	use super::Textures;
+
+	#[test]
+	fn only_new_images_are_uploaded() {
+		let dir = std::env::temp_dir().join(format!("notsure-textures-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let (a, b) = (dir.join("a.png"), dir.join("b.png"));
+		let red = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]));
+		let blue = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 255, 255]));
+
+		let mut textures = Textures::new();
+		let mut uploads = 0;
+		let mut load = |textures: &mut Textures, path| {
+			textures.load(path, |_| {
+				uploads += 1;
+				TextureId(uploads)
+			})
+		};
+
+		red.save(&a).unwrap();
+		red.save(&b).unwrap();
+		assert_eq!(load(&mut textures, &a).unwrap(), TextureId(1));
+		assert_eq!(load(&mut textures, &b).unwrap(), TextureId(2));
+
+		blue.save(&a).unwrap();
+		assert_eq!(load(&mut textures, &a).unwrap(), TextureId(3));
+		assert_eq!(load(&mut textures, &a).unwrap(), TextureId(3));
+		red.save(&a).unwrap();
+		assert_eq!(load(&mut textures, &a).unwrap(), TextureId(1));
+
+		// Broken images keep the last good one
+		fs::write(&a, "not a png").unwrap();
+		assert!(load(&mut textures, &a).is_err());
+		assert_eq!(textures.get(&a), Some(TextureId(1)));
+		assert_eq!(textures.paths().count(), 2);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
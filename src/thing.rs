@@ -1,29 +1,30 @@
-use smitten::{Draw, Vec2};
+use smitten::Vec2;
 
-use crate::physics::{self, Intersection, LineSegment};
+use crate::{
+	physics::{self, Intersection, LineSegment},
+	scene,
+};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Thing {
 	pub center: Vec2,
 	pub previous_center: Vec2,
 
-	pub size: Vec2,
 	pub half_size: Vec2,
-	pub draw: Draw,
+	/// Radians, counterclockwise around the center
+	pub rotation: f32,
 }
 
 impl Thing {
-	pub fn new<C: Into<Vec2>, S: Into<Vec2>, D: Into<Draw>>(center: C, size: S, draw: D) -> Self {
-		let size = size.into();
+	pub fn new<C: Into<Vec2>, S: Into<Vec2>>(center: C, size: S) -> Self {
 		let center = center.into();
 
 		Self {
 			center,
 			previous_center: center,
 
-			size,
-			half_size: size / 2,
-			draw: draw.into(),
+			half_size: size.into() / 2,
+			rotation: 0.0,
 		}
 	}
 
@@ -72,102 +73,108 @@ impl Thing {
 			.collect()
 	}
 
+	/// A corner, or any other point, given relative to the center as if we
+	/// weren't rotated
+	fn corner(&self, x: f32, y: f32) -> Vec2 {
+		self.center + scene::rotate(Vec2::new(x, y), self.rotation)
+	}
+
 	pub fn top(&self) -> LineSegment {
-		LineSegment::new(
-			(
-				self.center.x - self.half_size.x,
-				self.center.y + self.half_size.y,
-			),
-			(
-				self.center.x + self.half_size.x,
-				self.center.y + self.half_size.y,
-			),
-		)
+		let (hx, hy) = (self.half_size.x, self.half_size.y);
+		LineSegment::new(self.corner(-hx, hy), self.corner(hx, hy))
 	}
 
 	pub fn topright(&self) -> Vec2 {
-		Vec2::new(
-			self.center.x - self.half_size.x,
-			self.center.y + self.half_size.y,
-		)
+		self.corner(-self.half_size.x, self.half_size.y)
 	}
 
 	pub fn topleft(&self) -> Vec2 {
-		Vec2::new(
-			self.center.x + self.half_size.x,
-			self.center.y + self.half_size.y,
-		)
+		self.corner(self.half_size.x, self.half_size.y)
 	}
 
 	pub fn right(&self) -> LineSegment {
-		LineSegment::new(
-			(
-				self.center.x - self.half_size.x,
-				self.center.y + self.half_size.y,
-			),
-			(
-				self.center.x - self.half_size.x,
-				self.center.y - self.half_size.y,
-			),
-		)
+		let (hx, hy) = (self.half_size.x, self.half_size.y);
+		LineSegment::new(self.corner(-hx, hy), self.corner(-hx, -hy))
 	}
 
 	pub fn bottom(&self) -> LineSegment {
-		LineSegment::new(
-			(
-				self.center.x - self.half_size.x,
-				self.center.y - self.half_size.y,
-			),
-			(
-				self.center.x + self.half_size.x,
-				self.center.y - self.half_size.y,
-			),
-		)
+		let (hx, hy) = (self.half_size.x, self.half_size.y);
+		LineSegment::new(self.corner(-hx, -hy), self.corner(hx, -hy))
 	}
 
 	pub fn bottomright(&self) -> Vec2 {
-		Vec2::new(
-			self.center.x + self.half_size.x,
-			self.center.y - self.half_size.y,
-		)
+		self.corner(self.half_size.x, -self.half_size.y)
 	}
 
 	pub fn bottomleft(&self) -> Vec2 {
-		Vec2::new(
-			self.center.x - self.half_size.x,
-			self.center.y - self.half_size.y,
-		)
+		self.corner(-self.half_size.x, -self.half_size.y)
 	}
 
 	pub fn left(&self) -> LineSegment {
-		LineSegment::new(
-			(
-				self.center.x + self.half_size.x,
-				self.center.y + self.half_size.y,
-			),
-			(
-				self.center.x + self.half_size.x,
-				self.center.y - self.half_size.y,
-			),
+		let (hx, hy) = (self.half_size.x, self.half_size.y);
+		LineSegment::new(self.corner(hx, hy), self.corner(hx, -hy))
+	}
+
+	/// Every corner, counterclockwise from the bottom left when we aren't
+	/// rotated
+	pub fn corners(&self) -> [Vec2; 4] {
+		let (hx, hy) = (self.half_size.x, self.half_size.y);
+
+		[
+			self.corner(-hx, -hy),
+			self.corner(hx, -hy),
+			self.corner(hx, hy),
+			self.corner(-hx, hy),
+		]
+	}
+
+	pub fn rotated(&self) -> bool {
+		self.rotation != 0.0
+	}
+
+	/// How far to move `other` so it's no longer inside of us. Boxes that
+	/// are both straight use the cheap check, anything turned falls back to
+	/// separating axes.
+	pub fn push_out(&self, other: &Thing) -> Option<Vec2> {
+		if self.rotated() || other.rotated() {
+			physics::sat_push_out(&self.corners(), &other.corners())
+		} else {
+			physics::aabb_push_out(self, other)
+		}
+	}
+
+	/// Half the size of the box around us, rotation and all
+	fn extent(&self) -> Vec2 {
+		if !self.rotated() {
+			return self.half_size;
+		}
+
+		let (sin, cos) = self.rotation.sin_cos();
+		let (sin, cos) = (sin.abs(), cos.abs());
+
+		Vec2::new(
+			self.half_size.x * cos + self.half_size.y * sin,
+			self.half_size.x * sin + self.half_size.y * cos,
 		)
 	}
 }
 
+/// Rotated things use the box around all of their corners
 impl physics::AxisAlignedBoundingBox for Thing {
 	fn bottom_left(&self) -> Vec2 {
-		self.center - self.half_size
+		self.center - self.extent()
 	}
 
 	fn top_right(&self) -> Vec2 {
-		self.center + self.half_size
+		self.center + self.extent()
 	}
 
 	fn previous_bottom_left(&self) -> Vec2 {
-		self.previous_center - self.half_size
+		self.previous_center - self.extent()
 	}
 
 	fn previous_top_rght(&self) -> Vec2 {
-		self.previous_center + self.half_size
+		self.previous_center + self.extent()
 	}
 }
 
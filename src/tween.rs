@@ -0,0 +1,394 @@
+use smitten::{Color, Vec2};
+
+/// The shape of the curve between the start and end, see `Ease`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Curve {
+	Quad,
+	Cubic,
+	/// Overshoots and wobbles like it's on a spring
+	Elastic,
+	/// Hits the end and bounces off it a few times
+	Bounce,
+}
+
+/// How to get from 0 to 1. `In` starts slow, `Out` ends slow and `InOut`
+/// does both.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Ease {
+	Linear,
+	/// Smoothstep, slow in and out of every end without overshooting
+	Smooth,
+	In(Curve),
+	Out(Curve),
+	InOut(Curve),
+}
+
+impl Ease {
+	/// How far along we are `t` of the way through, from 0 to 1
+	pub fn apply(&self, t: f32) -> f32 {
+		let t = t.clamp(0.0, 1.0);
+
+		match self {
+			Ease::Linear => t,
+			Ease::Smooth => t * t * (3.0 - 2.0 * t),
+			Ease::In(curve) => curve.ease_in(t),
+			Ease::Out(curve) => 1.0 - curve.ease_in(1.0 - t),
+			Ease::InOut(curve) if t < 0.5 => curve.ease_in(t * 2.0) / 2.0,
+			Ease::InOut(curve) => 1.0 - curve.ease_in(2.0 - t * 2.0) / 2.0,
+		}
+	}
+}
+
+impl Curve {
+	fn ease_in(&self, t: f32) -> f32 {
+		match self {
+			Curve::Quad => t * t,
+			Curve::Cubic => t * t * t,
+			Curve::Elastic if t <= 0.0 || t >= 1.0 => t,
+			Curve::Elastic => {
+				let period = std::f32::consts::TAU / 3.0;
+				-(2f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * period).sin()
+			}
+			Curve::Bounce => 1.0 - bounce_out(1.0 - t),
+		}
+	}
+}
+
+/// Four parabolas, each a bit lower than the last
+fn bounce_out(t: f32) -> f32 {
+	const N: f32 = 7.5625;
+	const D: f32 = 2.75;
+
+	let (t, top) = if t < 1.0 / D {
+		(t, 0.0)
+	} else if t < 2.0 / D {
+		(t - 1.5 / D, 0.75)
+	} else if t < 2.5 / D {
+		(t - 2.25 / D, 0.9375)
+	} else {
+		(t - 2.625 / D, 0.984375)
+	};
+
+	N * t * t + top
+}
+
+/// Anything that can be animated between two values
+pub trait Lerp: Copy {
+	fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		self + (to - self) * t
+	}
+}
+
+impl Lerp for Vec2 {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		self + (to - self) * t
+	}
+}
+
+impl Lerp for Color {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		Color::rgba(
+			self.r.lerp(to.r, t),
+			self.g.lerp(to.g, t),
+			self.b.lerp(to.b, t),
+			self.a.lerp(to.a, t),
+		)
+	}
+}
+
+/// Something that changes a part of an `S` over time, like the camera's zoom
+/// or a button's color. Animations can be strung together one after another
+/// with `sequence`, or run side by side with `parallel`.
+///
+/// ```ignore
+/// let zoom = Animation::tween(1.0, 2.0, 0.5, Ease::Out(Curve::Quad), Camera::set_zoom)
+///     .on_complete(|camera| camera.shake(0.2));
+/// let pan = Animation::tween(start, end, 0.5, Ease::Smooth, |c: &mut Camera, p| c.position = p);
+///
+/// tweens.play(Animation::sequence(vec![Animation::parallel(vec![zoom, pan]), Animation::wait(1.0)]));
+/// ```
+pub struct Animation<S> {
+	kind: Kind<S>,
+	on_complete: Option<Callback<S>>,
+	finished: bool,
+}
+
+type Callback<S> = Box<dyn FnOnce(&mut S)>;
+/// Gets the eased progress, and knows what to set with it
+type Setter<S> = Box<dyn FnMut(&mut S, f32)>;
+
+enum Kind<S> {
+	Tween {
+		elapsed: f32,
+		duration: f32,
+		ease: Ease,
+		set: Setter<S>,
+	},
+	Wait {
+		elapsed: f32,
+		duration: f32,
+	},
+	Sequence {
+		parts: Vec<Animation<S>>,
+		current: usize,
+	},
+	Parallel(Vec<Animation<S>>),
+}
+
+impl<S> Animation<S> {
+	fn new(kind: Kind<S>) -> Self {
+		Self {
+			kind,
+			on_complete: None,
+			finished: false,
+		}
+	}
+
+	/// Go from `from` to `to` over `duration` seconds, handing every value
+	/// along the way to `set`
+	pub fn tween<T, F>(from: T, to: T, duration: f32, ease: Ease, mut set: F) -> Self
+	where
+		T: Lerp + 'static,
+		F: FnMut(&mut S, T) + 'static,
+	{
+		Self::new(Kind::Tween {
+			elapsed: 0.0,
+			duration,
+			ease,
+			set: Box::new(move |target, t| set(target, from.lerp(to, t))),
+		})
+	}
+
+	/// Do nothing for `duration` seconds, for gaps in a sequence
+	pub fn wait(duration: f32) -> Self {
+		Self::new(Kind::Wait {
+			elapsed: 0.0,
+			duration,
+		})
+	}
+
+	/// One after the other
+	pub fn sequence(parts: Vec<Animation<S>>) -> Self {
+		Self::new(Kind::Sequence { parts, current: 0 })
+	}
+
+	/// All at once, finished when the longest one is
+	pub fn parallel(parts: Vec<Animation<S>>) -> Self {
+		Self::new(Kind::Parallel(parts))
+	}
+
+	/// Call `f` once, as soon as this finishes
+	pub fn on_complete<F: FnOnce(&mut S) + 'static>(mut self, f: F) -> Self {
+		self.on_complete = Some(Box::new(f));
+		self
+	}
+
+	pub fn finished(&self) -> bool {
+		self.finished
+	}
+
+	/// Seconds it's been running, which is as far along as its longest part
+	/// when things run side by side
+	pub fn elapsed(&self) -> f32 {
+		match &self.kind {
+			Kind::Tween { elapsed, .. } | Kind::Wait { elapsed, .. } => *elapsed,
+			Kind::Sequence { parts, .. } => parts.iter().map(Animation::elapsed).sum(),
+			Kind::Parallel(parts) => parts.iter().map(Animation::elapsed).fold(0.0, f32::max),
+		}
+	}
+
+	/// Move `dt` seconds forward. Returns however much of `dt` was left over
+	/// after finishing, so whatever's next in a sequence can use it.
+	pub fn advance(&mut self, target: &mut S, dt: f32) -> f32 {
+		if self.finished {
+			return dt;
+		}
+
+		let (finished, left) = match &mut self.kind {
+			Kind::Tween {
+				elapsed,
+				duration,
+				ease,
+				set,
+			} => {
+				let (done, left) = run_clock(elapsed, *duration, dt);
+				let t = if *duration > 0.0 {
+					*elapsed / *duration
+				} else {
+					1.0
+				};
+
+				set(target, ease.apply(t));
+				(done, left)
+			}
+			Kind::Wait { elapsed, duration } => run_clock(elapsed, *duration, dt),
+			Kind::Sequence { parts, current } => {
+				let mut left = dt;
+
+				// Anything that finishes passes what's left of the step on, so
+				// short parts don't waste time
+				while let Some(part) = parts.get_mut(*current) {
+					left = part.advance(target, left);
+
+					if !part.finished() {
+						break;
+					}
+					*current += 1;
+				}
+
+				(*current >= parts.len(), left)
+			}
+			Kind::Parallel(parts) => {
+				let left = parts
+					.iter_mut()
+					.map(|part| part.advance(target, dt))
+					.fold(dt, f32::min);
+
+				(parts.iter().all(|p| p.finished()), left)
+			}
+		};
+
+		if finished {
+			self.finished = true;
+
+			if let Some(f) = self.on_complete.take() {
+				f(target);
+			}
+
+			left
+		} else {
+			0.0
+		}
+	}
+}
+
+/// Move a clock along, returning whether it's run out and by how much
+fn run_clock(elapsed: &mut f32, duration: f32, dt: f32) -> (bool, f32) {
+	*elapsed += dt;
+	let over = *elapsed - duration;
+
+	if over >= 0.0 {
+		*elapsed = duration;
+		(true, over)
+	} else {
+		(false, 0.0)
+	}
+}
+
+/// Every animation running on an `S`, dropped as they finish
+pub struct Tweens<S> {
+	playing: Vec<Animation<S>>,
+}
+
+impl<S> Default for Tweens<S> {
+	fn default() -> Self {
+		Self { playing: vec![] }
+	}
+}
+
+impl<S> Tweens<S> {
+	pub fn play(&mut self, animation: Animation<S>) {
+		self.playing.push(animation);
+	}
+
+	/// Stop everything where it is
+	pub fn clear(&mut self) {
+		self.playing.clear();
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.playing.is_empty()
+	}
+
+	pub fn playing(&self) -> &[Animation<S>] {
+		&self.playing
+	}
+
+	pub fn update(&mut self, target: &mut S, dt: f32) {
+		for animation in &mut self.playing {
+			animation.advance(target, dt);
+		}
+
+		self.playing.retain(|a| !a.finished());
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{cell::RefCell, rc::Rc};
+
+	use smitten::Vec2;
+
+	use super::{Animation, Curve, Ease, Tweens};
+
+	#[test]
+	fn eases_start_and_end_in_place() {
+		let curves = [Curve::Quad, Curve::Cubic, Curve::Elastic, Curve::Bounce];
+		let eases = curves
+			.iter()
+			.flat_map(|c| [Ease::In(*c), Ease::Out(*c), Ease::InOut(*c)])
+			.chain([Ease::Linear, Ease::Smooth]);
+
+		for ease in eases {
+			assert!(ease.apply(0.0).abs() < 0.0001, "{:?} at 0", ease);
+			assert!((ease.apply(1.0) - 1.0).abs() < 0.0001, "{:?} at 1", ease);
+		}
+
+		// Starting slow means being behind halfway through
+		assert!(Ease::In(Curve::Quad).apply(0.5) < 0.5);
+		assert!(Ease::Out(Curve::Cubic).apply(0.5) > 0.5);
+		// Springs overshoot
+		assert!((0..100).any(|i| Ease::Out(Curve::Elastic).apply(i as f32 / 100.0) > 1.0));
+	}
+
+	#[derive(Default)]
+	struct Target {
+		x: f32,
+		position: Vec2,
+	}
+
+	#[test]
+	fn sequences_and_groups() {
+		let log = Rc::new(RefCell::new(vec![]));
+		let (first, both) = (log.clone(), log.clone());
+
+		let slide = Animation::tween(0.0, 10.0, 1.0, Ease::Linear, |t: &mut Target, x| t.x = x)
+			.on_complete(move |_| first.borrow_mut().push("slid"));
+		let group = Animation::parallel(vec![
+			Animation::tween(10.0, 0.0, 0.5, Ease::Linear, |t: &mut Target, x| t.x = x),
+			Animation::tween(
+				Vec2::ZERO,
+				Vec2::new(2.0, 4.0),
+				1.0,
+				Ease::Linear,
+				|t: &mut Target, p| t.position = p,
+			),
+		])
+		.on_complete(move |_| both.borrow_mut().push("both"));
+
+		let mut target = Target::default();
+		let mut tweens = Tweens::default();
+		tweens.play(Animation::sequence(vec![slide, group]));
+
+		tweens.update(&mut target, 0.5);
+		assert_eq!(target.x, 5.0);
+
+		// The quarter second past the slide carries into the group
+		tweens.update(&mut target, 0.75);
+		assert_eq!(*log.borrow(), vec!["slid"]);
+		assert_eq!(target.x, 5.0);
+		assert_eq!(target.position.y, 1.0);
+
+		// The group waits for its longest part
+		tweens.update(&mut target, 0.5);
+		assert!(!tweens.is_empty());
+		tweens.update(&mut target, 0.25);
+		assert_eq!(*log.borrow(), vec!["slid", "both"]);
+		assert_eq!((target.position.x, target.position.y), (2.0, 4.0));
+		assert!(tweens.is_empty());
+	}
+}
@@ -0,0 +1,751 @@
+use std::{
+	collections::{hash_map::DefaultHasher, HashSet},
+	hash::{Hash, Hasher},
+};
+
+use smitten::{Color, Key, SignedDistance, Vec2};
+
+use crate::{font, render::Command};
+
+/// Height of a line of text, in units
+const TEXT_SIZE: f32 = 0.4;
+/// Height of every widget
+const HEIGHT: f32 = 0.7;
+/// Space between a widget's edge and what's in it
+const PADDING: f32 = 0.15;
+/// Space between widgets
+const SPACING: f32 = 0.1;
+const SLIDER_WIDTH: f32 = 4.0;
+const FIELD_WIDTH: f32 = 4.0;
+
+const PANEL: Color = rgba(0.1, 0.1, 0.12, 0.85);
+const IDLE: Color = rgba(0.25, 0.25, 0.3, 1.0);
+const HOVERED: Color = rgba(0.35, 0.35, 0.42, 1.0);
+const HELD: Color = rgba(0.45, 0.45, 0.55, 1.0);
+const ACCENT: Color = rgba(0.3, 0.6, 0.9, 1.0);
+const FOCUS: Color = rgba(1.0, 0.85, 0.3, 1.0);
+const TEXT: Color = rgba(1.0, 1.0, 1.0, 1.0);
+const HINT: Color = rgba(1.0, 1.0, 1.0, 0.4);
+
+// Color::rgba can't be used in a const
+const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+	Color { r, g, b, a }
+}
+
+/// Keys that do something to widgets
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UiKey {
+	/// Focus the next widget
+	Tab,
+	/// Press the focused widget, or finish typing
+	Enter,
+	Backspace,
+	Left,
+	Right,
+}
+
+/// What the mouse and keyboard did this frame. Positions are in units from
+/// the middle of the screen, like everything that's drawn.
+#[derive(Clone, Debug, Default)]
+pub struct UiInput {
+	pub mouse: Option<Vec2>,
+	pub mouse_down: bool,
+	/// Went down this frame
+	pub keys: Vec<UiKey>,
+	/// Typed this frame
+	pub text: String,
+}
+
+/// Turns which keys are down into which were pressed, for `UiInput`
+#[derive(Default)]
+pub struct Keyboard {
+	down: HashSet<Key>,
+}
+
+impl Keyboard {
+	/// Poll the keys, returning the ones that went down since last time as UI
+	/// keys and as typed text.
+	pub fn update<F: Fn(Key) -> bool>(&mut self, is_down: F) -> (Vec<UiKey>, String) {
+		let ui_keys = [
+			(Key::Tab, UiKey::Tab),
+			(Key::Return, UiKey::Enter),
+			(Key::Back, UiKey::Backspace),
+			(Key::Left, UiKey::Left),
+			(Key::Right, UiKey::Right),
+		];
+
+		let down: HashSet<Key> = ui_keys
+			.iter()
+			.map(|(k, _)| *k)
+			.chain(TYPED.iter().map(|(k, _)| *k))
+			.filter(|k| is_down(*k))
+			.collect();
+
+		let pressed = |k: &Key| down.contains(k) && !self.down.contains(k);
+		let keys = ui_keys
+			.iter()
+			.filter(|(k, _)| pressed(k))
+			.map(|(_, u)| *u)
+			.collect();
+		let text = TYPED
+			.iter()
+			.filter(|(k, _)| pressed(k))
+			.map(|(_, c)| *c)
+			.collect();
+
+		self.down = down;
+		(keys, text)
+	}
+}
+
+/// Keys that type something. There's no shift, so letters are lowercase,
+/// which the font draws as uppercase anyway.
+#[rustfmt::skip]
+const TYPED: &[(Key, char)] = &[
+	(Key::A, 'a'), (Key::B, 'b'), (Key::C, 'c'), (Key::D, 'd'), (Key::E, 'e'),
+	(Key::F, 'f'), (Key::G, 'g'), (Key::H, 'h'), (Key::I, 'i'), (Key::J, 'j'),
+	(Key::K, 'k'), (Key::L, 'l'), (Key::M, 'm'), (Key::N, 'n'), (Key::O, 'o'),
+	(Key::P, 'p'), (Key::Q, 'q'), (Key::R, 'r'), (Key::S, 's'), (Key::T, 't'),
+	(Key::U, 'u'), (Key::V, 'v'), (Key::W, 'w'), (Key::X, 'x'), (Key::Y, 'y'),
+	(Key::Z, 'z'),
+	(Key::Key0, '0'), (Key::Key1, '1'), (Key::Key2, '2'), (Key::Key3, '3'), (Key::Key4, '4'),
+	(Key::Key5, '5'), (Key::Key6, '6'), (Key::Key7, '7'), (Key::Key8, '8'), (Key::Key9, '9'),
+	(Key::Space, ' '), (Key::Period, '.'), (Key::Minus, '-'), (Key::Slash, '/'),
+];
+
+type Id = u64;
+
+/// Where the next widget goes. Widgets are placed from the top left down, or
+/// across in a row.
+#[derive(Copy, Clone, Debug)]
+struct Layout {
+	cursor: Vec2,
+	row: bool,
+	/// The bottom right of everything placed so far
+	extent: Vec2,
+}
+
+impl Layout {
+	fn new(top_left: Vec2, row: bool) -> Self {
+		Self {
+			cursor: top_left,
+			row,
+			extent: top_left,
+		}
+	}
+
+	/// Make room for something `size` big, returning its center
+	fn place(&mut self, size: Vec2) -> Vec2 {
+		let center = self.cursor + Vec2::new(size.x / 2.0, -size.y / 2.0);
+		self.extent.x = self.extent.x.max(self.cursor.x + size.x);
+		self.extent.y = self.extent.y.min(self.cursor.y - size.y);
+
+		if self.row {
+			self.cursor.x += size.x + SPACING;
+		} else {
+			self.cursor.y -= size.y + SPACING;
+		}
+
+		center
+	}
+}
+
+/// How the mouse and keyboard are treating a widget this frame
+struct Interaction {
+	hovered: bool,
+	/// The mouse went down on it and is still down
+	held: bool,
+	/// The mouse went down and came back up on it, or Enter was pressed while
+	/// it had focus
+	clicked: bool,
+	focused: bool,
+}
+
+/// An immediate mode UI. Widgets are made fresh every frame between `begin`
+/// and `end`, and whatever happened to them is returned right away:
+///
+/// ```ignore
+/// ui.begin(input);
+/// ui.panel("EDITOR", top_left, |ui| {
+///     if ui.button("FILL") {
+///         editor.tool = Tool::Fill;
+///     }
+///     ui.slider("BRUSH", &mut size, 1.0, 5.0);
+/// });
+/// let commands = ui.end();
+/// ```
+///
+/// Widgets are told apart by their label, so two in the same panel can't
+/// share one.
+pub struct Ui {
+	dimensions: (u32, u32),
+	pixels_per_unit: u32,
+
+	input: UiInput,
+	mouse_was_down: bool,
+
+	/// What the mouse went down on, it's what gets the click when it comes up
+	active: Option<Id>,
+	focus: Option<Id>,
+	/// Focusable widgets in the order they were made, this frame and last
+	order: Vec<Id>,
+	last_order: Vec<Id>,
+
+	/// Labels of the panels we're in, so ids are unique per panel
+	scope: Vec<String>,
+	layouts: Vec<Layout>,
+	commands: Vec<Command>,
+
+	/// Whether the mouse was over any of us, this frame and last
+	under_mouse: bool,
+	last_under_mouse: bool,
+	/// Whether something took this frame's mouse press
+	press_taken: bool,
+	/// Whether a text field has focus
+	typing: bool,
+}
+
+impl Ui {
+	pub fn new(dimensions: (u32, u32), pixels_per_unit: u32) -> Self {
+		Self {
+			dimensions,
+			pixels_per_unit,
+
+			input: UiInput::default(),
+			mouse_was_down: false,
+
+			active: None,
+			focus: None,
+			order: vec![],
+			last_order: vec![],
+
+			scope: vec![],
+			layouts: vec![],
+			commands: vec![],
+
+			under_mouse: false,
+			last_under_mouse: false,
+			press_taken: false,
+			typing: false,
+		}
+	}
+
+	/// From window pixels, with the origin in the top left, to the units
+	/// `UiInput` wants
+	pub fn mouse_from_pixels(&self, p: Vec2) -> Vec2 {
+		let ppu = self.pixels_per_unit as f32;
+
+		Vec2::new(
+			(p.x - self.dimensions.0 as f32 / 2.0) / ppu,
+			(self.dimensions.1 as f32 / 2.0 - p.y) / ppu,
+		)
+	}
+
+	/// The top left of the screen, for placing panels
+	pub fn top_left(&self) -> Vec2 {
+		let ppu = self.pixels_per_unit as f32;
+		Vec2::new(
+			-(self.dimensions.0 as f32) / ppu / 2.0,
+			self.dimensions.1 as f32 / ppu / 2.0,
+		)
+	}
+
+	/// Whether the mouse is busy with us, so the game shouldn't use it
+	pub fn wants_mouse(&self) -> bool {
+		self.last_under_mouse || self.active.is_some()
+	}
+
+	/// Whether something's being typed into, so the game shouldn't use the
+	/// keyboard
+	pub fn wants_keyboard(&self) -> bool {
+		self.typing
+	}
+
+	pub fn begin(&mut self, input: UiInput) {
+		self.input = input;
+		self.commands.clear();
+		self.order.clear();
+		self.scope.clear();
+		self.layouts = vec![Layout::new(self.top_left(), false)];
+		self.under_mouse = false;
+		self.press_taken = false;
+		self.typing = false;
+
+		if self.key(UiKey::Tab) && !self.last_order.is_empty() {
+			let next = match self
+				.focus
+				.and_then(|f| self.last_order.iter().position(|id| *id == f))
+			{
+				Some(idx) => (idx + 1) % self.last_order.len(),
+				None => 0,
+			};
+
+			self.focus = Some(self.last_order[next]);
+		}
+	}
+
+	/// Everything to draw for this frame
+	pub fn end(&mut self) -> Vec<Command> {
+		if self.pressed() && !self.press_taken {
+			self.focus = None;
+		}
+		if !self.input.mouse_down {
+			self.active = None;
+		}
+
+		// Anything focused that wasn't made this frame is gone
+		if matches!(self.focus, Some(f) if !self.order.contains(&f)) {
+			self.focus = None;
+		}
+		if self.focus.is_none() {
+			self.typing = false;
+		}
+
+		self.mouse_was_down = self.input.mouse_down;
+		self.last_order = std::mem::take(&mut self.order);
+		self.last_under_mouse = self.under_mouse;
+
+		std::mem::take(&mut self.commands)
+	}
+
+	/// A box with a title that lays out what `contents` makes in a column
+	pub fn panel<F: FnOnce(&mut Ui)>(&mut self, title: &str, top_left: Vec2, contents: F) {
+		// The background has to be under everything, but we won't know how
+		// big it is until the contents are laid out
+		let background = self.commands.len();
+		self.commands.push(Command::Clear);
+
+		let inner = top_left + Vec2::new(PADDING, -PADDING);
+		self.scope.push(title.to_owned());
+		self.layouts.push(Layout::new(inner, false));
+
+		self.label(title);
+		contents(self);
+
+		let layout = self.layouts.pop().unwrap();
+		self.scope.pop();
+
+		let bottom_right = layout.extent + Vec2::new(PADDING, -PADDING);
+		let size = Vec2::new(bottom_right.x - top_left.x, top_left.y - bottom_right.y);
+		let position = top_left + Vec2::new(size.x / 2.0, -size.y / 2.0);
+
+		self.commands[background] = Command::Rect {
+			position,
+			size,
+			draw: PANEL.into(),
+		};
+		self.covers_mouse(position, size);
+
+		// The panel takes up room where it was put, too
+		self.layout().place(size);
+	}
+
+	/// Lay out what `contents` makes side by side
+	pub fn row<F: FnOnce(&mut Ui)>(&mut self, contents: F) {
+		let cursor = self.layout().cursor;
+		self.layouts.push(Layout::new(cursor, true));
+
+		contents(self);
+
+		let layout = self.layouts.pop().unwrap();
+		self.layout().place(Vec2::new(
+			layout.extent.x - cursor.x,
+			cursor.y - layout.extent.y,
+		));
+	}
+
+	pub fn label(&mut self, text: &str) {
+		let size = Vec2::new(font::width(text, TEXT_SIZE), HEIGHT);
+		let center = self.layout().place(size);
+		self.text(center, size, text, TEXT);
+	}
+
+	/// Returns true the frame it's clicked
+	pub fn button(&mut self, label: &str) -> bool {
+		let size = Vec2::new(font::width(label, TEXT_SIZE) + PADDING * 2.0, HEIGHT);
+		let center = self.layout().place(size);
+		let id = self.id(label);
+		let state = self.interact(id, center, size);
+
+		self.rect(center, size, fill(&state));
+		self.text(center, size, label, TEXT);
+		self.outline(center, size, &state);
+
+		state.clicked
+	}
+
+	/// A box that's ticked or not. Returns true the frame it changes.
+	pub fn toggle(&mut self, label: &str, value: &mut bool) -> bool {
+		let text_width = font::width(label, TEXT_SIZE);
+		let size = Vec2::new(HEIGHT + SPACING + text_width, HEIGHT);
+		let center = self.layout().place(size);
+		let id = self.id(label);
+		let state = self.interact(id, center, size);
+
+		if state.clicked {
+			*value = !*value;
+		}
+
+		let left = center.x - size.x / 2.0;
+		let tick = Vec2::new(left + HEIGHT / 2.0, center.y);
+		self.rect(tick, Vec2::new(HEIGHT, HEIGHT), fill(&state));
+		if *value {
+			let inner = HEIGHT - PADDING * 2.0;
+			self.rect(tick, Vec2::new(inner, inner), ACCENT);
+		}
+
+		let text_center = Vec2::new(left + HEIGHT + SPACING + text_width / 2.0, center.y);
+		self.text(text_center, Vec2::new(text_width, HEIGHT), label, TEXT);
+		self.outline(center, size, &state);
+
+		state.clicked
+	}
+
+	/// Drag, or use left and right while focused, to pick a value between
+	/// `min` and `max`. Returns true the frame it changes.
+	pub fn slider(&mut self, label: &str, value: &mut f32, min: f32, max: f32) -> bool {
+		let size = Vec2::new(SLIDER_WIDTH, HEIGHT);
+		let center = self.layout().place(size);
+		let id = self.id(label);
+		let state = self.interact(id, center, size);
+		let before = *value;
+
+		let left = center.x - size.x / 2.0;
+		if state.held {
+			if let Some(mouse) = self.input.mouse {
+				let t = ((mouse.x - left) / size.x).clamp(0.0, 1.0);
+				*value = min + (max - min) * t;
+			}
+		}
+
+		if state.focused {
+			let step = (max - min) / 20.0;
+			if self.key(UiKey::Left) {
+				*value -= step;
+			}
+			if self.key(UiKey::Right) {
+				*value += step;
+			}
+		}
+		*value = value.clamp(min, max);
+
+		let t = if max > min {
+			(*value - min) / (max - min)
+		} else {
+			0.0
+		};
+		let knob = Vec2::new(left + size.x * t, center.y);
+
+		self.rect(center, size, fill(&state));
+		self.rect(
+			Vec2::new(left + size.x * t / 2.0, center.y),
+			Vec2::new(size.x * t, size.y),
+			ACCENT,
+		);
+		self.commands.push(Command::Sdf(SignedDistance::Circle {
+			center: knob,
+			radius: self.pixels(HEIGHT / 3.0),
+			color: TEXT,
+		}));
+		self.text(center, size, &format!("{} {:.2}", label, value), TEXT);
+		self.outline(center, size, &state);
+
+		*value != before
+	}
+
+	/// Something to type into once it's clicked or tabbed to. Returns true
+	/// the frame Enter finishes it, so half typed text can be left alone.
+	pub fn text_field(&mut self, label: &str, value: &mut String) -> bool {
+		let size = Vec2::new(FIELD_WIDTH, HEIGHT);
+		let center = self.layout().place(size);
+		let id = self.id(label);
+		let state = self.interact(id, center, size);
+		let mut entered = false;
+
+		if state.focused {
+			if self.key(UiKey::Backspace) {
+				value.pop();
+			}
+			value.push_str(&self.input.text);
+
+			if self.key(UiKey::Enter) {
+				self.focus = None;
+				entered = true;
+			}
+		}
+
+		let typing = self.focus == Some(id);
+		self.typing |= typing;
+		self.rect(center, size, fill(&state));
+		if value.is_empty() && !typing {
+			self.text(center, size, label, HINT);
+		} else {
+			let caret = if typing { "_" } else { "" };
+			self.text(center, size, &format!("{}{}", value, caret), TEXT);
+		}
+		self.outline(center, size, &state);
+
+		entered
+	}
+
+	fn layout(&mut self) -> &mut Layout {
+		self.layouts.last_mut().unwrap()
+	}
+
+	fn id(&self, label: &str) -> Id {
+		let mut hasher = DefaultHasher::new();
+		self.scope.hash(&mut hasher);
+		label.hash(&mut hasher);
+		hasher.finish()
+	}
+
+	fn key(&self, key: UiKey) -> bool {
+		self.input.keys.contains(&key)
+	}
+
+	fn pressed(&self) -> bool {
+		self.input.mouse_down && !self.mouse_was_down
+	}
+
+	fn released(&self) -> bool {
+		!self.input.mouse_down && self.mouse_was_down
+	}
+
+	/// Whether the mouse is inside the box, and if so note that we've got it
+	fn covers_mouse(&mut self, center: Vec2, size: Vec2) -> bool {
+		let inside = match self.input.mouse {
+			Some(m) => {
+				(m.x - center.x).abs() <= size.x / 2.0 && (m.y - center.y).abs() <= size.y / 2.0
+			}
+			None => false,
+		};
+
+		self.under_mouse |= inside;
+		inside
+	}
+
+	fn interact(&mut self, id: Id, center: Vec2, size: Vec2) -> Interaction {
+		self.order.push(id);
+		let hovered = self.covers_mouse(center, size);
+
+		if hovered && self.pressed() {
+			self.active = Some(id);
+			self.focus = Some(id);
+			self.press_taken = true;
+		}
+
+		let active = self.active == Some(id);
+		let focused = self.focus == Some(id);
+
+		Interaction {
+			hovered,
+			held: active && self.input.mouse_down,
+			clicked: (active && hovered && self.released()) || (focused && self.key(UiKey::Enter)),
+			focused,
+		}
+	}
+
+	fn pixels(&self, length: f32) -> u32 {
+		(length * self.pixels_per_unit as f32).round().max(1.0) as u32
+	}
+
+	fn rect(&mut self, position: Vec2, size: Vec2, color: Color) {
+		self.commands.push(Command::Rect {
+			position,
+			size,
+			draw: color.into(),
+		});
+	}
+
+	/// Text inside a box, up against its left side with some padding if it
+	/// fits and centered if it doesn't
+	fn text(&mut self, center: Vec2, size: Vec2, text: &str, color: Color) {
+		let width = font::width(text, TEXT_SIZE);
+		let left = if width + PADDING * 2.0 <= size.x {
+			center.x - size.x / 2.0 + PADDING
+		} else {
+			center.x - width / 2.0
+		};
+
+		self.commands.push(Command::Text {
+			position: Vec2::new(left, center.y + TEXT_SIZE / 2.0),
+			size: TEXT_SIZE,
+			text: text.to_owned(),
+			color,
+		});
+	}
+
+	fn outline(&mut self, center: Vec2, size: Vec2, state: &Interaction) {
+		if !state.focused {
+			return;
+		}
+
+		let (hx, hy) = (size.x / 2.0, size.y / 2.0);
+		let corners = [
+			center + Vec2::new(-hx, -hy),
+			center + Vec2::new(hx, -hy),
+			center + Vec2::new(hx, hy),
+			center + Vec2::new(-hx, hy),
+		];
+
+		for (i, start) in corners.iter().enumerate() {
+			self.commands
+				.push(Command::Sdf(SignedDistance::LineSegment {
+					start: *start,
+					end: corners[(i + 1) % corners.len()],
+					thickness: 1,
+					color: FOCUS,
+				}));
+		}
+	}
+}
+
+fn fill(state: &Interaction) -> Color {
+	if state.held {
+		HELD
+	} else if state.hovered {
+		HOVERED
+	} else {
+		IDLE
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use smitten::Vec2;
+
+	use super::{Ui, UiInput, UiKey};
+	use crate::{
+		canvas::Canvas,
+		render::{Command, Recorder, Renderer},
+	};
+
+	struct Form {
+		clicks: u32,
+		sound: bool,
+		volume: f32,
+		name: String,
+		entered: u32,
+	}
+
+	/// One frame of a little form, returning what was drawn
+	fn frame(ui: &mut Ui, form: &mut Form, input: UiInput) -> Vec<Command> {
+		ui.begin(input);
+
+		let top_left = ui.top_left();
+		ui.panel("OPTIONS", top_left, |ui| {
+			if ui.button("OK") {
+				form.clicks += 1;
+			}
+			ui.toggle("SOUND", &mut form.sound);
+			ui.slider("VOLUME", &mut form.volume, 0.0, 1.0);
+			if ui.text_field("NAME", &mut form.name) {
+				form.entered += 1;
+			}
+		});
+
+		ui.end()
+	}
+
+	fn mouse(x: f32, y: f32, down: bool) -> UiInput {
+		UiInput {
+			mouse: Some(Vec2::new(x, y)),
+			mouse_down: down,
+			..UiInput::default()
+		}
+	}
+
+	fn keys(keys: Vec<UiKey>, text: &str) -> UiInput {
+		UiInput {
+			keys,
+			text: text.to_owned(),
+			..UiInput::default()
+		}
+	}
+
+	fn form() -> Form {
+		Form {
+			clicks: 0,
+			sound: false,
+			volume: 0.5,
+			name: String::new(),
+			entered: 0,
+		}
+	}
+
+	/// Where the widget drawn with `text` is
+	fn find(commands: &[Command], text: &str) -> Vec2 {
+		commands
+			.iter()
+			.find_map(|c| match c {
+				Command::Text {
+					position, text: t, ..
+				} if t.starts_with(text) => Some(*position + Vec2::new(0.1, -0.2)),
+				_ => None,
+			})
+			.unwrap()
+	}
+
+	#[test]
+	fn clicks_and_drags() {
+		let mut ui = Ui::new((400, 300), 20);
+		let mut form = form();
+		let drawn = frame(&mut ui, &mut form, UiInput::default());
+		let ok = find(&drawn, "OK");
+
+		// Down and up on the button is a click
+		frame(&mut ui, &mut form, mouse(ok.x, ok.y, true));
+		assert!(ui.wants_mouse());
+		frame(&mut ui, &mut form, mouse(ok.x, ok.y, false));
+		assert_eq!(form.clicks, 1);
+
+		// Down somewhere else and up on it isn't
+		frame(&mut ui, &mut form, mouse(5.0, -5.0, true));
+		frame(&mut ui, &mut form, mouse(ok.x, ok.y, false));
+		assert_eq!(form.clicks, 1);
+
+		let sound = find(&drawn, "SOUND");
+		frame(&mut ui, &mut form, mouse(sound.x, sound.y, true));
+		frame(&mut ui, &mut form, mouse(sound.x, sound.y, false));
+		assert!(form.sound);
+
+		// Dragging the slider all the way left and past it
+		let volume = find(&drawn, "VOLUME");
+		frame(&mut ui, &mut form, mouse(volume.x, volume.y, true));
+		frame(&mut ui, &mut form, mouse(-100.0, volume.y, true));
+		frame(&mut ui, &mut form, mouse(-100.0, volume.y, false));
+		assert_eq!(form.volume, 0.0);
+	}
+
+	#[test]
+	fn tab_and_type() {
+		let mut ui = Ui::new((400, 300), 20);
+		let mut form = form();
+		frame(&mut ui, &mut form, UiInput::default());
+
+		// OK, SOUND, VOLUME, then NAME
+		for _ in 0..4 {
+			frame(&mut ui, &mut form, keys(vec![UiKey::Tab], ""));
+		}
+		assert!(ui.wants_keyboard());
+
+		frame(&mut ui, &mut form, keys(vec![], "AB"));
+		frame(&mut ui, &mut form, keys(vec![UiKey::Backspace], "C"));
+		assert_eq!(form.entered, 0);
+		let drawn = frame(&mut ui, &mut form, keys(vec![UiKey::Enter], ""));
+		assert_eq!(form.name, "AC");
+		assert_eq!(form.entered, 1);
+		assert!(!ui.wants_keyboard());
+
+		// Drawn behind its panel, and with the font
+		assert!(matches!(drawn[0], Command::Rect { .. }));
+		let mut recorder = Recorder::default();
+		recorder.draw_all(&drawn);
+		assert!(recorder.svg((400, 300), 20).contains(">AC</text>"));
+
+		let mut canvas = Canvas::new((400, 300), 20);
+		canvas.clear();
+		canvas.draw_all(&drawn);
+		assert!(canvas.image.pixels().any(|p| p.0 == [255, 255, 255, 255]));
+	}
+}
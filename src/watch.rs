@@ -0,0 +1,117 @@
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
+
+/// Notices files changing by looking at when they were last modified every
+/// so often. Slower to notice than asking the OS, but it works everywhere.
+pub struct Watcher {
+	/// Seconds between looks
+	interval: f32,
+	since_check: f32,
+	files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Watcher {
+	pub fn new(interval: f32) -> Self {
+		Self {
+			interval,
+			since_check: 0.0,
+			files: vec![],
+		}
+	}
+
+	/// Start watching `path`, if we aren't already. It doesn't have to exist
+	/// yet, it counts as changed once it does.
+	pub fn watch<P: AsRef<Path>>(&mut self, path: P) {
+		let path = path.as_ref();
+
+		if !self.files.iter().any(|(p, _)| p == path) {
+			self.files.push((path.to_owned(), modified(path)));
+		}
+	}
+
+	pub fn unwatch<P: AsRef<Path>>(&mut self, path: P) {
+		self.files.retain(|(p, _)| p != path.as_ref());
+	}
+
+	/// Count `dt` seconds, and look at the files if it's been long enough
+	pub fn poll(&mut self, dt: f32) -> Vec<PathBuf> {
+		self.since_check += dt;
+		if self.since_check < self.interval {
+			return vec![];
+		}
+
+		self.since_check = 0.0;
+		self.check()
+	}
+
+	/// Every file that's been modified since we last looked. Files that are
+	/// gone aren't reported until they're back.
+	pub fn check(&mut self) -> Vec<PathBuf> {
+		let mut changed = vec![];
+
+		for (path, last) in &mut self.files {
+			let now = modified(path);
+
+			if now != *last {
+				*last = now;
+
+				if now.is_some() {
+					changed.push(path.clone());
+				}
+			}
+		}
+
+		changed
+	}
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+	fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod test {
+	use std::{
+		fs::{self, File},
+		time::{Duration, SystemTime},
+	};
+
+	use super::Watcher;
+
+	#[test]
+	fn sees_modifications() {
+		let path = std::env::temp_dir().join(format!("notsure-watch-{}.txt", std::process::id()));
+		fs::write(&path, "before").unwrap();
+
+		let mut watcher = Watcher::new(0.5);
+		watcher.watch(&path);
+		watcher.watch(&path);
+		assert!(watcher.check().is_empty());
+
+		// Writes can land in the same tick as the last one, so move the time
+		// along by hand
+		File::options()
+			.write(true)
+			.open(&path)
+			.unwrap()
+			.set_modified(SystemTime::now() + Duration::from_secs(5))
+			.unwrap();
+
+		// Not until the interval's up
+		assert!(watcher.poll(0.25).is_empty());
+		assert_eq!(watcher.poll(0.25), vec![path.clone()]);
+		assert!(watcher.check().is_empty());
+
+		fs::remove_file(&path).unwrap();
+		assert!(watcher.check().is_empty());
+
+		// Not even once it's back
+		watcher.unwatch(&path);
+		fs::write(&path, "after").unwrap();
+		assert!(watcher.check().is_empty());
+		fs::remove_file(&path).unwrap();
+	}
+}
@@ -0,0 +1,686 @@
+use smitten::{Color, SignedDistance, Vec2};
+
+use crate::{
+	camera::Camera,
+	debug,
+	editor::{Editor, Pointer, Tool},
+	entity::{Collider, Controller, Entities, Entity, Look, Pickup, Transform},
+	fov::{Light, LightMap},
+	grid::{Grid, Size, Tile},
+	history::History,
+	input::{Action, ActionState},
+	particle::{Emitter, Particles},
+	physics::{AxisAlignedBoundingBox, Intersection, LineSegment},
+	platform::{self, Kinematic, Route},
+	render::{Assets, Command},
+	scene::{self, Attachment},
+	thing::Thing,
+	tween::Ease,
+};
+
+/// Seconds per simulation step. The world only ever moves forward by this
+/// much at a time so the same input always ends up in the same place, no
+/// matter how fast frames are drawn.
+pub const TIMESTEP: f32 = 1.0 / 60.0;
+
+const HISTORY_LIMIT: usize = 128;
+
+/// Units the player moves per step
+const SPEED: f32 = 0.075;
+
+const BACKDROP: Color = Color {
+	r: 0.25,
+	g: 0.25,
+	b: 0.3,
+	a: 1.0,
+};
+
+/// Any number works, it just has to be the same every run
+const PARTICLE_SEED: u32 = 0x5eed;
+
+/// Units per second
+const PLATFORM_SPEED: f32 = 1.5;
+
+/// How close, in units, the player has to be to something to pick it up
+const GRAB_REACH: f32 = 0.75;
+
+const PLATFORM: Color = Color {
+	r: 0.45,
+	g: 0.35,
+	b: 0.25,
+	a: 1.0,
+};
+
+const BEACON: Color = Color {
+	r: 0.9,
+	g: 0.8,
+	b: 0.3,
+	a: 1.0,
+};
+
+/// Things that happened during a step that the game might want to react to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Event {
+	TilePlaced(Size),
+}
+
+/// Everything the simulation owns. Stepping it only depends on the input
+/// and what's already in here.
+pub struct World {
+	pub grid: Grid,
+	pub entities: Entities,
+	/// The entity the camera follows
+	pub player: Entity,
+	pub history: History,
+	pub editor: Editor,
+	pub particles: Particles,
+}
+
+impl World {
+	pub fn new<P: Into<Vec2>>(grid: Grid, start: P) -> Self {
+		let mut entities = Entities::default();
+		let player = spawn_player(&mut entities, start.into());
+
+		let mut world = Self {
+			grid,
+			entities,
+			player,
+			history: History::new(HISTORY_LIMIT),
+			editor: Editor::new(),
+			particles: Particles::new(PARTICLE_SEED),
+		};
+		world.spawn_platforms();
+		world
+	}
+
+	/// One for each of the level's platforms, starting at its first cell
+	fn spawn_platforms(&mut self) {
+		let grid = &self.grid;
+		let size = Vec2::new(grid.side_length, grid.side_length);
+
+		for cells in &grid.platforms {
+			let waypoints: Vec<Vec2> = cells
+				.iter()
+				.filter_map(|c| grid.coordinate_center(c.width, c.height))
+				.collect();
+
+			if !waypoints.is_empty() {
+				let start = waypoints[0];
+				let (_, beacon) = spawn_platform(&mut self.entities, waypoints, size);
+				self.particles.add(Emitter::embers(start, beacon));
+			}
+		}
+	}
+
+	/// Swap in a different grid. The old level's platforms go and the new
+	/// one's are spawned, every other entity stays where it is. Undo history
+	/// is for the old grid so it's dropped.
+	pub fn set_grid(&mut self, grid: Grid) {
+		// Along with whatever's still sitting on them
+		let entities = &mut self.entities;
+		let platforms: Vec<Entity> = entities.kinematics.iter().map(|(e, _)| e).collect();
+		let riding: Vec<Entity> = entities
+			.attachments
+			.iter()
+			.filter(|(_, a)| platforms.contains(&a.parent))
+			.map(|(e, _)| e)
+			.collect();
+
+		for entity in platforms.into_iter().chain(riding) {
+			entities.despawn(entity);
+		}
+
+		self.grid = grid;
+		self.history = History::new(HISTORY_LIMIT);
+		self.spawn_platforms();
+	}
+
+	/// Where the player is
+	pub fn player_position(&self) -> Vec2 {
+		self.entities.transforms[self.player].position
+	}
+
+	/// Move the world forward by one `TIMESTEP`. `cursor` is the grid cell
+	/// the mouse is over.
+	pub fn step(&mut self, input: &ActionState, cursor: Option<Size>) -> Vec<Event> {
+		let mut events = vec![];
+		debug::clear();
+
+		if input.pressed(Action::ToggleEditor) {
+			self.editor.toggle(&mut self.history);
+		}
+
+		if input.pressed(Action::Undo) {
+			self.history.undo(&mut self.grid);
+		} else if input.pressed(Action::Redo) {
+			self.history.redo(&mut self.grid);
+		}
+
+		if self.editor.enabled {
+			self.step_editor(input, cursor);
+		}
+
+		if !self.editor.enabled && input.pressed(Action::Grab) {
+			self.grab();
+		}
+
+		for (_, transform) in self.entities.transforms.iter_mut() {
+			transform.previous = transform.position;
+		}
+
+		platform::move_kinematics(&mut self.entities, TIMESTEP);
+		self.control(input);
+		self.integrate();
+		// Attached things go with whatever moved them before anything
+		// looks at where they are
+		scene::resolve(&mut self.entities);
+
+		for (entity, push) in platform::push_out(&mut self.entities) {
+			let position = self.entities.transforms[entity].position;
+			self.particles
+				.burst(Emitter::sparks(position, push.y.atan2(push.x)), 8);
+		}
+
+		if !self.editor.enabled && input.held(Action::PlaceTile) {
+			for (entity, _) in self.entities.controllers.iter() {
+				let position = self.entities.transforms[entity].position;
+
+				if let Some(coords) = self.grid.get_coords(position) {
+					if !self.grid.is_solid(coords) {
+						events.push(Event::TilePlaced(coords));
+
+						if let Some(p) = self.grid.coordinate_center(coords.width, coords.height) {
+							self.particles.burst(Emitter::dust(p), 12);
+						}
+					}
+
+					self.history
+						.set_tile(&mut self.grid, coords, Some(Tile::Solid));
+				}
+			}
+		}
+
+		self.particles.follow(&self.entities);
+		self.particles.step(TIMESTEP, &self.grid);
+		self.debug_draw();
+
+		events
+	}
+
+	/// Every collider's box, how it moved, the cells the broad phase would
+	/// check it against, and where its corners ran into them on the way
+	fn debug_draw(&self) {
+		if !debug::enabled() {
+			return;
+		}
+
+		debug::grid(&self.grid);
+
+		for (entity, _) in self.entities.colliders.iter() {
+			let thing = match self.entities.thing(entity) {
+				Some(thing) => thing,
+				None => continue,
+			};
+
+			if thing.rotated() {
+				debug::obb(&thing.corners());
+			}
+			debug::aabb(&thing);
+			debug::motion(thing.previous_center, thing.center);
+
+			// Wherever it's been this step
+			let (bl, tr) = (thing.bottom_left(), thing.top_right());
+			let (pbl, ptr) = (thing.previous_bottom_left(), thing.previous_top_rght());
+			let corners = (
+				self.grid
+					.get_coords(Vec2::new(bl.x.min(pbl.x), bl.y.min(pbl.y))),
+				self.grid
+					.get_coords(Vec2::new(tr.x.max(ptr.x), tr.y.max(ptr.y))),
+			);
+			let (from, to) = match corners {
+				(Some(from), Some(to)) => (from, to),
+				_ => continue,
+			};
+
+			let mut solid = vec![];
+			for x in from.width..=to.width {
+				for y in from.height..=to.height {
+					let coords = Size {
+						width: x,
+						height: y,
+					};
+					debug::cell(&self.grid, coords);
+
+					if self.grid.is_solid(coords) {
+						if let Some(center) = self.grid.coordinate_center(x, y) {
+							let side = self.grid.side_length;
+							solid.push(Thing::new(center, (side, side)));
+						}
+					}
+				}
+			}
+
+			if thing.center != thing.previous_center {
+				self.debug_sweep(&thing, &solid);
+			}
+		}
+	}
+
+	/// Each corner from where it was to where it is, stopping at the first
+	/// tile it crosses into
+	fn debug_sweep(&self, thing: &Thing, solid: &[Thing]) {
+		let moved = thing.center - thing.previous_center;
+
+		for end in thing.corners() {
+			let start = end - moved;
+			let segment = LineSegment::new(start, end);
+
+			let hit = solid
+				.iter()
+				.flat_map(|tile| tile.intersect_segment(&segment))
+				.filter_map(|(_, intersection)| match intersection {
+					Intersection::Point(p) => Some(p),
+					Intersection::Line(_) => None,
+				})
+				.min_by(|a, b| a.distance_with(start).total_cmp(&b.distance_with(start)));
+
+			debug::ray(start, end, hit);
+		}
+	}
+
+	/// Drop whatever the player's holding, or pick up the closest thing in
+	/// reach
+	fn grab(&mut self) {
+		let entities = &mut self.entities;
+		let player = self.player;
+		let held = |entities: &Entities, e: Entity| matches!(entities.attachments.get(e), Some(a) if a.parent == player);
+
+		let holding: Vec<Entity> = entities
+			.pickups
+			.iter()
+			.map(|(e, _)| e)
+			.filter(|e| held(entities, *e))
+			.collect();
+		if !holding.is_empty() {
+			for pickup in holding {
+				scene::detach(entities, pickup);
+			}
+			return;
+		}
+
+		let reach = |e: Entity| {
+			let distance = entities
+				.transforms
+				.get(e)?
+				.position
+				.distance_with(entities.transforms.get(player)?.position);
+			(distance <= GRAB_REACH).then_some((e, distance))
+		};
+		let closest = entities
+			.pickups
+			.iter()
+			.filter_map(|(e, _)| reach(e))
+			.min_by(|a, b| a.1.total_cmp(&b.1));
+
+		// Off of whatever it was on, and onto the player
+		if let Some((pickup, _)) = closest {
+			scene::detach(entities, pickup);
+			scene::attach(entities, pickup, player);
+		}
+	}
+
+	/// Everything with a controller moves with the input
+	fn control(&mut self, input: &ActionState) {
+		let movement = input.movement();
+
+		for (entity, controller) in self.entities.controllers.iter() {
+			if let Some(transform) = self.entities.transforms.get_mut(entity) {
+				transform.position += movement * controller.speed;
+			}
+		}
+	}
+
+	/// Everything with a body moves by its velocity
+	fn integrate(&mut self) {
+		for (entity, body) in self.entities.bodies.iter() {
+			if let Some(transform) = self.entities.transforms.get_mut(entity) {
+				transform.position += body.velocity * TIMESTEP;
+			}
+		}
+	}
+
+	/// Where the lamp hangs, if the grid is big enough to have it
+	pub fn lamp(&self) -> Option<Vec2> {
+		self.grid.coordinate_center(2, 2)
+	}
+
+	/// Everything giving off light. Players carry one around, and so does
+	/// anything that can be picked up.
+	pub fn lights(&self) -> Vec<Light> {
+		let mut lights: Vec<Light> = self
+			.entities
+			.controllers
+			.iter()
+			.filter_map(|(entity, _)| self.entities.transforms.get(entity))
+			.map(|t| Light::new(t.position, 3, Color::rgb(0.9, 0.8, 0.7)))
+			.collect();
+
+		for (entity, _) in self.entities.pickups.iter() {
+			if let Some(t) = self.entities.transforms.get(entity) {
+				lights.push(Light::new(t.position, 2, BEACON));
+			}
+		}
+
+		if let Some(p) = self.lamp() {
+			lights.push(Light::new(p, 4, Color::rgb(0.8, 0.7, 0.1)));
+		}
+
+		lights
+	}
+
+	/// Everything that should be on screen, back to front. `cursor` is the
+	/// cell the mouse is over.
+	pub fn draw(&self, camera: &Camera, assets: &Assets, cursor: Option<Size>) -> Vec<Command> {
+		let grid = &self.grid;
+		let tile_size = camera.scale((grid.side_length, grid.side_length));
+		let mut commands = vec![Command::Clear];
+
+		for line in grid.gridlines() {
+			commands.push(Command::Sdf(camera.sdf(line)));
+		}
+
+		let lightmap = LightMap::new(grid, &self.lights(), Color::rgb(0.1, 0.1, 0.15));
+
+		for (coords, tile, p) in grid.tiles_and_position() {
+			let p = camera.view(p);
+
+			match tile {
+				Some(Tile::Solid) => {
+					let sprite = assets.tileset.get(grid.neighbours(coords));
+					commands.push(assets.sprite(p, tile_size, sprite));
+				}
+				Some(Tile::Backdrop) => commands.push(Command::Rect {
+					position: p,
+					size: tile_size,
+					draw: BACKDROP.into(),
+				}),
+				None => continue,
+			}
+
+			commands.push(Command::Rect {
+				position: p,
+				size: tile_size,
+				draw: lightmap.overlay(coords).into(),
+			});
+		}
+
+		if let Some(p) = self.lamp() {
+			commands.push(Command::Sdf(camera.sdf(SignedDistance::Circle {
+				center: p,
+				radius: 2,
+				color: Color::YELLOW,
+			})));
+		}
+
+		for cell in self.editor.preview(cursor) {
+			if let Some(p) = grid.coordinate_center(cell.width, cell.height) {
+				commands.push(Command::Rect {
+					position: camera.view(p),
+					size: tile_size,
+					draw: Color::rgba(1.0, 1.0, 1.0, 0.3).into(),
+				});
+			}
+		}
+
+		commands.extend(self.particles.draw(camera));
+
+		for (entity, look) in self.entities.looks.iter() {
+			if let Some(transform) = self.entities.transforms.get(entity) {
+				commands.push(assets.rotated_sprite(
+					camera.view(transform.position),
+					camera.scale(look.size * transform.scale),
+					transform.rotation,
+					look.sprite,
+				));
+			}
+		}
+
+		commands
+	}
+
+	fn step_editor(&mut self, input: &ActionState, cursor: Option<Size>) {
+		let editor = &mut self.editor;
+
+		if input.pressed(Action::ToolBrush) {
+			editor.tool = Tool::Brush;
+		} else if input.pressed(Action::ToolRectangle) {
+			editor.tool = Tool::Rectangle;
+		} else if input.pressed(Action::ToolFill) {
+			editor.tool = Tool::Fill;
+		}
+
+		if input.pressed(Action::BrushGrow) {
+			editor.grow_brush();
+		} else if input.pressed(Action::BrushShrink) {
+			editor.shrink_brush();
+		}
+
+		if input.pressed(Action::PaletteNext) {
+			editor.select(editor.selected + 1);
+		} else if input.pressed(Action::PalettePrevious) {
+			editor.select(editor.selected.saturating_sub(1));
+		}
+
+		editor.update(
+			&mut self.grid,
+			&mut self.history,
+			Pointer {
+				cell: cursor,
+				place: input.held(Action::EditorPlace),
+				erase: input.held(Action::EditorErase),
+			},
+		);
+	}
+}
+
+/// Something that moves with the input, carries a light and can place tiles
+pub fn spawn_player(entities: &mut Entities, position: Vec2) -> Entity {
+	let player = entities.spawn();
+	let size = Vec2::new(1.0, 1.0);
+
+	entities.transforms.insert(player, Transform::new(position));
+	entities.colliders.insert(player, Collider { size });
+	entities.looks.insert(
+		player,
+		Look {
+			sprite: Color::rgb(0.1, 0.3, 0.5).into(),
+			size,
+		},
+	);
+	entities
+		.controllers
+		.insert(player, Controller { speed: SPEED });
+
+	player
+}
+
+/// A block that goes back and forth along `waypoints`, pushing things out of
+/// its way and carrying whatever's on top. Each one comes with a beacon, and
+/// both are returned.
+pub fn spawn_platform(
+	entities: &mut Entities,
+	waypoints: Vec<Vec2>,
+	size: Vec2,
+) -> (Entity, Entity) {
+	let platform = entities.spawn();
+	let start = waypoints[0];
+
+	entities
+		.transforms
+		.insert(platform, Transform::new(waypoints[0]));
+	entities.colliders.insert(platform, Collider { size });
+	entities.looks.insert(
+		platform,
+		Look {
+			sprite: PLATFORM.into(),
+			size,
+		},
+	);
+	entities.kinematics.insert(
+		platform,
+		Kinematic::new(waypoints, Route::PingPong, PLATFORM_SPEED).with_easing(Ease::Smooth),
+	);
+
+	let beacon = entities.spawn();
+	entities.transforms.insert(beacon, Transform::new(start));
+	entities.looks.insert(
+		beacon,
+		Look {
+			sprite: BEACON.into(),
+			size: size * 0.3,
+		},
+	);
+	entities.pickups.insert(beacon, Pickup);
+	entities
+		.attachments
+		.insert(beacon, Attachment::new(platform, Vec2::ZERO));
+
+	(platform, beacon)
+}
+
+#[cfg(test)]
+mod test {
+	use std::collections::HashSet;
+
+	use smitten::Vec2;
+
+	use super::{spawn_player, World, SPEED, TIMESTEP};
+	use crate::{
+		entity::{Body, Transform},
+		grid::{Grid, Size},
+		input::{Action, ActionState, Bindings},
+	};
+
+	#[test]
+	fn systems_move_everything() {
+		let mut world = World::new(Grid::new((5, 5), (0.0, 0.0), 1.0), (0.0, 0.0));
+		let second = spawn_player(&mut world.entities, Vec2::new(1.0, 1.0));
+
+		let arrow = world.entities.spawn();
+		world
+			.entities
+			.transforms
+			.insert(arrow, Transform::new((0.0, 0.0)));
+		world.entities.bodies.insert(
+			arrow,
+			Body {
+				velocity: Vec2::new(0.0, -2.0),
+			},
+		);
+
+		let mut input = ActionState::new(Bindings::default());
+		input.set_down(HashSet::from([Action::MoveRight]));
+		for _ in 0..10 {
+			world.step(&input, None);
+		}
+
+		let transforms = &world.entities.transforms;
+		assert!((transforms[world.player].position.x - SPEED * 10.0).abs() < 0.0001);
+		assert!((transforms[second].position.x - (1.0 + SPEED * 10.0)).abs() < 0.0001);
+		assert!((transforms[arrow].position.y + 2.0 * TIMESTEP * 10.0).abs() < 0.0001);
+		assert_eq!(transforms[arrow].position.x, 0.0);
+	}
+
+	#[test]
+	fn levels_bring_their_platforms() {
+		let mut grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		grid.platforms.push(vec![
+			Size {
+				width: 0,
+				height: 2,
+			},
+			Size {
+				width: 4,
+				height: 2,
+			},
+		]);
+
+		// Right in the platform's way
+		let mut world = World::new(grid, (0.0, 0.0));
+		let (platform, _) = world.entities.kinematics.iter().next().unwrap();
+		assert_eq!(
+			world.entities.transforms[platform].position,
+			Vec2::new(-2.0, 0.0)
+		);
+
+		let input = ActionState::new(Bindings::default());
+		for _ in 0..60 {
+			world.step(&input, None);
+		}
+		assert!(world.entities.transforms[platform].position.x > -2.0);
+		assert!(world.player_position().x > 0.0);
+		assert!(world.particles.len() > 0);
+
+		// A level without any takes them away again
+		world.set_grid(Grid::new((5, 5), (0.0, 0.0), 1.0));
+		assert!(!world.entities.is_alive(platform));
+		assert_eq!(world.entities.kinematics.iter().count(), 0);
+		assert!(world.entities.is_alive(world.player));
+	}
+
+	#[test]
+	fn beacons_can_be_carried() {
+		// Along the top, out of the player's way
+		let mut grid = Grid::new((5, 5), (0.0, 0.0), 1.0);
+		grid.platforms.push(vec![
+			Size {
+				width: 0,
+				height: 4,
+			},
+			Size {
+				width: 4,
+				height: 4,
+			},
+		]);
+		let mut world = World::new(grid, (0.0, 0.0));
+		let (platform, _) = world.entities.kinematics.iter().next().unwrap();
+		let (beacon, _) = world.entities.pickups.iter().next().unwrap();
+		assert_eq!(world.entities.attachments[beacon].parent, platform);
+
+		let mut input = ActionState::new(Bindings::default());
+		let mut step = |world: &mut World, down: &[Action]| {
+			input.set_down(down.iter().copied().collect());
+			world.step(&input, None);
+		};
+
+		// Nothing in reach
+		step(&mut world, &[Action::Grab]);
+		assert_eq!(world.entities.attachments[beacon].parent, platform);
+		step(&mut world, &[]);
+
+		let under = world.entities.transforms[beacon].position - Vec2::new(0.0, 0.5);
+		world.entities.transforms[world.player].position = under;
+		step(&mut world, &[Action::Grab]);
+		assert_eq!(world.entities.attachments[beacon].parent, world.player);
+
+		// It comes along
+		for _ in 0..30 {
+			step(&mut world, &[Action::MoveDown]);
+		}
+		let carried = world.entities.transforms[beacon].position;
+		assert!(carried.y < under.y);
+		assert!(carried.distance_with(world.player_position()) < super::GRAB_REACH);
+
+		// And stays where it's dropped
+		step(&mut world, &[Action::Grab]);
+		assert!(world.entities.attachments.get(beacon).is_none());
+		for _ in 0..30 {
+			step(&mut world, &[Action::MoveUp]);
+		}
+		assert_eq!(world.entities.transforms[beacon].position, carried);
+
+		// Leaving the level doesn't take it away since it's not on the platform
+		world.set_grid(Grid::new((5, 5), (0.0, 0.0), 1.0));
+		assert!(world.entities.is_alive(beacon));
+	}
+}